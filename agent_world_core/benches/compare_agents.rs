@@ -0,0 +1,47 @@
+//! Compares agent strategies on the bundled maps, reporting success rate, turns to win
+//! and wall-clock time. Run with `cargo bench -p agent_world_core`.
+
+use std::time::Instant;
+
+use agent_world_core::{
+    agent::{BiasedWalker, PlanningAgent, RandomWalker},
+    runner::{AgentFactory, compare_agents},
+};
+
+const TRIALS: u64 = 20;
+const MAX_TURNS: usize = 2000;
+
+fn main() {
+    let maps = [
+        ("map01", include_str!("../../maps/map01.txt")),
+        ("map02", include_str!("../../maps/map02.txt")),
+        ("map03", include_str!("../../maps/map03.txt")),
+    ];
+    let agents: [AgentFactory; 3] = [
+        ("random", &|id, seed| Box::new(RandomWalker::new(id, seed))),
+        ("biased", &|id, seed| Box::new(BiasedWalker::new(id, seed))),
+        ("planning", &|id, _| Box::new(PlanningAgent::new(id))),
+    ];
+
+    for (map_name, map) in maps {
+        println!("{} ({} seeds, up to {} turns)", map_name, TRIALS, MAX_TURNS);
+        for agent in &agents {
+            let start = Instant::now();
+            let results = compare_agents(&[map], std::slice::from_ref(agent), TRIALS, MAX_TURNS)
+                .expect("bundled maps load");
+            let elapsed = start.elapsed();
+            for result in results {
+                let turns = result
+                    .average_turns_to_win()
+                    .map_or("-".to_string(), |turns| format!("{:.1}", turns));
+                println!(
+                    "  {:<10} success {:>5.1}%  avg turns {:>7}  {:>8.2?}/run",
+                    result.name,
+                    result.success_rate() * 100.0,
+                    turns,
+                    elapsed / result.runs as u32
+                );
+            }
+        }
+    }
+}
@@ -1,13 +1,16 @@
 use std::{
-    cmp::Ordering,
-    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
 };
 
 use rand::{Rng, SeedableRng, rngs::StdRng};
 
 use crate::{
-    DoorKeyType, EntityId, Item, Position,
+    DoorKeyType, EntityId, Item, Offset, Position,
+    blackboard::Blackboard,
     environment::{Action, CellType, EnvironmentView},
+    map::Grid,
+    pathfinding::{PathfindingConfig, find_path, path_cost},
 };
 
 /// Trait defining the behavior of an agent.
@@ -19,20 +22,51 @@ pub trait Agent {
     /// Determines the action the agent wants to perform based on its view of the environment.
     /// `&mut self` allows the agent to maintain internal state for decision making (e.g., pathfinding).
     fn get_action(&mut self, view: &EnvironmentView) -> Action;
+
+    /// Posts messages for other agents, called right after `get_action` each turn.
+    fn write_blackboard(&mut self, _blackboard: &mut Blackboard) {}
+
+    /// Returns the position the agent is currently heading towards, if any.
+    /// Used for visualization and debugging of the agent's intent.
+    fn current_target(&self) -> Option<Position> {
+        None
+    }
+
+    /// Clears any internal decision state, e.g. when the environment is reset.
+    fn reset(&mut self) {}
+
+    /// Returns a boxed copy of this agent, including its internal state, so environments
+    /// can be cloned for branching search.
+    fn box_clone(&self) -> Box<dyn Agent>;
+}
+
+impl Clone for Box<dyn Agent> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
 }
 
 /// A simple agent that tries to move randomly.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RandomWalker {
     id: EntityId,
     rng: StdRng,
+    retries: usize, // Extra draws allowed when a move would fail
 }
 
 impl RandomWalker {
     pub fn new(id: EntityId, seed: u64) -> Self {
+        Self::new_smart(id, seed, 0)
+    }
+
+    /// A walker that redraws a move up to `retries` times when `EnvironmentView::is_walkable`
+    /// says it would fail, waiting if every draw fails. With 0 retries it behaves like
+    /// `RandomWalker::new`.
+    pub fn new_smart(id: EntityId, seed: u64, retries: usize) -> Self {
         Self {
             id,
             rng: StdRng::seed_from_u64(seed),
+            retries,
         }
     }
 }
@@ -42,48 +76,184 @@ impl Agent for RandomWalker {
         self.id
     }
 
-    fn get_action(&mut self, _view: &EnvironmentView) -> Action {
-        // Random movement
-        let dx: i8 = self.rng.random_range(-1..=1);
-        let dy: i8 = self.rng.random_range(-1..=1);
+    fn get_action(&mut self, view: &EnvironmentView) -> Action {
+        for _ in 0..=self.retries {
+            // Random movement
+            let dx: i8 = self.rng.random_range(-1..=1);
+            let dy: i8 = self.rng.random_range(-1..=1);
 
-        if dx == 0 && dy == 0 {
-            Action::Wait
-        } else {
-            Action::Move {
-                dx: dx as isize,
-                dy: dy as isize,
+            if dx == 0 && dy == 0 {
+                return Action::Wait;
+            }
+            let (dx, dy) = (dx as isize, dy as isize);
+            if self.retries == 0 || view.is_walkable(dx, dy) {
+                return Action::Move { dx, dy };
             }
         }
+        Action::Wait
+    }
+
+    fn box_clone(&self) -> Box<dyn Agent> {
+        Box::new(self.clone())
+    }
+}
+
+/// A random agent that prefers cells it hasn't recently visited and avoids walls.
+#[derive(Debug, Clone)]
+pub struct BiasedWalker {
+    id: EntityId,
+    rng: StdRng,
+    recent_positions: VecDeque<Position>, // Most recently visited positions, oldest first
+}
+
+impl BiasedWalker {
+    /// Number of recent positions remembered when weighting moves.
+    const MEMORY: usize = 32;
+
+    pub fn new(id: EntityId, seed: u64) -> Self {
+        Self {
+            id,
+            rng: StdRng::seed_from_u64(seed),
+            recent_positions: VecDeque::with_capacity(Self::MEMORY),
+        }
+    }
+}
+
+impl Agent for BiasedWalker {
+    fn id(&self) -> EntityId {
+        self.id
+    }
+
+    fn get_action(&mut self, view: &EnvironmentView) -> Action {
+        let current_pos = view.location;
+        if self.recent_positions.len() == Self::MEMORY {
+            self.recent_positions.pop_front();
+        }
+        self.recent_positions.push_back(current_pos);
+
+        // Weight each enterable neighbor by how rarely it was visited recently
+        let mut candidates: Vec<(Action, usize)> = Vec::new();
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                let Some(Position { x, y }) = current_pos.offset(dx, dy) else {
+                    continue;
+                };
+                match view.terrain_grid.get(x, y) {
+                    None
+                    | Some(CellType::Wall)
+                    | Some(CellType::Hazard)
+                    | Some(CellType::Void)
+                    | Some(CellType::Unknown) => continue,
+                    Some(_) => {}
+                }
+                let visits = self
+                    .recent_positions
+                    .iter()
+                    .filter(|p| **p == Position { x, y })
+                    .count();
+                candidates.push((Action::Move { dx, dy }, Self::MEMORY + 1 - visits));
+            }
+        }
+
+        let total: usize = candidates.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return Action::Wait;
+        }
+        let mut choice = self.rng.random_range(0..total);
+        for (action, weight) in candidates {
+            if choice < weight {
+                return action;
+            }
+            choice -= weight;
+        }
+        Action::Wait
+    }
+
+    fn box_clone(&self) -> Box<dyn Agent> {
+        Box::new(self.clone())
     }
 }
 
 /// A planning agent that tries to move towards the goal after collecting all chips.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PlanningAgent {
     id: EntityId,
     current_plan: VecDeque<Position>, // Queue of positions to visit
+    pathfinding: PathfindingConfig,
 }
 
 impl PlanningAgent {
     pub fn new(id: EntityId) -> Self {
+        Self::with_config(id, PathfindingConfig::default())
+    }
+
+    /// Creates a planning agent using the given pathfinding configuration
+    /// (e.g. to allow weighted diagonal moves).
+    pub fn with_config(id: EntityId, pathfinding: PathfindingConfig) -> Self {
         Self {
             id,
             current_plan: VecDeque::new(),
+            pathfinding,
         }
     }
 
-    /// Returns manhattan distance between two positions
-    fn manhattan_distance(a: &Position, b: &Position) -> usize {
-        let dx = if a.x > b.x { a.x - b.x } else { b.x - a.x };
-        let dy = if a.y > b.y { a.y - b.y } else { b.y - a.y };
-        dx + dy
+    /// A* path from `start` to `goal` through the agent's view, or `None` if the agent
+    /// lacks the energy to walk it
+    fn a_star_path(
+        &self,
+        start: Position,
+        goal: Position,
+        view: &EnvironmentView,
+        keys_held: &HashSet<DoorKeyType>,
+    ) -> Option<Vec<Position>> {
+        // Ordered chips that can't be collected yet block the way like other agents
+        let blocked = Self::blocked_cells(view);
+        find_path(
+            view.terrain_grid,
+            Some(blocked.as_ref().unwrap_or(view.agent_location_grid)),
+            start,
+            goal,
+            keys_held,
+            view.agent_state.spendable_chips(),
+            &self.config(view),
+        )
+        .filter(|path| view.agent_state.can_afford_path(path))
+    }
+
+    /// Returns the agent locations with ordered chips other than the next one marked as
+    /// occupied too, since stepping onto them fails, or `None` if there are none
+    fn blocked_cells(view: &EnvironmentView) -> Option<Grid<Option<EntityId>>> {
+        let mut blocked = None;
+        for (position, item) in view.item_grid.positions().zip(view.item_grid.iter()) {
+            if let Some(Item::OrderedChip { order }) = item
+                && *order != view.next_chip_order
+            {
+                blocked.get_or_insert_with(|| view.agent_location_grid.clone())[position] =
+                    Some(view.agent_state.id);
+            }
+        }
+        blocked
+    }
+
+    /// The agent's pathfinding configuration, adjusted to the environment's edges and the
+    /// agent's speed
+    fn config(&self, view: &EnvironmentView) -> PathfindingConfig {
+        PathfindingConfig {
+            wrap_edges: view.wrap_edges,
+            speed: view.agent_state.speed,
+            ..self.pathfinding.clone()
+        }
     }
 
     /// Converts a move between two adjacent positions into an Action
-    fn position_to_action(src: &Position, dst: &Position) -> Action {
-        let dx = dst.x as isize - src.x as isize;
-        let dy = dst.y as isize - src.y as isize;
+    fn position_to_action(view: &EnvironmentView, src: &Position, dst: &Position) -> Action {
+        let Offset { dx, dy } = view
+            .terrain_grid
+            .step_between(*src, *dst, view.wrap_edges)
+            .unwrap_or(*dst - *src);
 
         match (dx, dy) {
             (0, 0) => Action::Wait,
@@ -91,194 +261,83 @@ impl PlanningAgent {
             (0, -1) => Action::Move { dx: 0, dy: -1 },
             (1, 0) => Action::Move { dx: 1, dy: 0 },
             (-1, 0) => Action::Move { dx: -1, dy: 0 },
+            (-1..=1, -1..=1) => Action::Move { dx, dy },
             _ => {
                 // This shouldn't happen if positions are adjacent
-                eprintln!("Invalid move from {:?} to {:?}", src, dst);
+                log_warn!("Invalid move from {:?} to {:?}", src, dst);
                 Action::Wait
             }
         }
     }
 
-    /// A* pathfinding implementation
-    fn a_star_path(
-        &self,
-        start: Position,
-        goal: Position,
-        view: &EnvironmentView,
-        keys_held: &HashSet<DoorKeyType>,
-    ) -> Option<Vec<Position>> {
-        // For priority queue
-        #[derive(Clone, Eq, PartialEq)]
-        struct PrioritizedItem {
-            priority: usize,
-            position: Position,
+    /// Takes the next step of the current plan, or `None` if the plan is empty. A closed
+    /// door that only opens by interacting is opened first, keeping the step planned.
+    /// Agents faster than 1 dash along straight runs of at least their speed.
+    fn follow_plan(&mut self, view: &EnvironmentView) -> Option<Action> {
+        if let Some(action) = self.dash_along_plan(view) {
+            return Some(action);
         }
-
-        impl Ord for PrioritizedItem {
-            fn cmp(&self, other: &Self) -> Ordering {
-                // Reverse ordering for min-heap behavior
-                other.priority.cmp(&self.priority)
-            }
-        }
-
-        impl PartialOrd for PrioritizedItem {
-            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-                Some(self.cmp(other))
-            }
-        }
-
-        let mut frontier = BinaryHeap::new();
-        let mut came_from: HashMap<Position, Position> = HashMap::new();
-        let mut cost_so_far: HashMap<Position, usize> = HashMap::new();
-
-        frontier.push(PrioritizedItem {
-            priority: 0,
-            position: start,
-        });
-        cost_so_far.insert(start, 0);
-
-        let mut goal_reached = false;
-
-        while let Some(PrioritizedItem {
-            position: current, ..
-        }) = frontier.pop()
+        let next_pos = *self.current_plan.front()?;
+        if let Some(CellType::Door {
+            open: false,
+            auto_open: false,
+            ..
+        }) = view.terrain_grid.get(next_pos.x, next_pos.y)
+            && let Some(Offset { dx, dy }) =
+                view.terrain_grid
+                    .step_between(view.location, next_pos, view.wrap_edges)
         {
-            if current == goal {
-                goal_reached = true;
-                break;
-            }
-
-            // Get valid neighbors
-            let valid_neighbors = self.get_valid_neighbors(&current, view, keys_held);
-
-            for neighbor in valid_neighbors {
-                let new_cost = cost_so_far.get(&current).unwrap_or(&usize::MAX) + 1;
-
-                if !cost_so_far.contains_key(&neighbor)
-                    || new_cost < *cost_so_far.get(&neighbor).unwrap()
-                {
-                    cost_so_far.insert(neighbor, new_cost);
-                    let priority = new_cost + Self::manhattan_distance(&neighbor, &goal);
-                    frontier.push(PrioritizedItem {
-                        priority,
-                        position: neighbor,
-                    });
-                    came_from.insert(neighbor, current);
-                }
-            }
+            return Some(Action::Interact { dx, dy });
         }
+        self.current_plan.pop_front();
+        Some(Self::position_to_action(view, &view.location, &next_pos))
+    }
 
-        if !goal_reached {
+    /// Returns a dash covering the next `speed` steps of the plan and drops them from it,
+    /// if they run straight over plain cells. Doors and items end runs, since a dash
+    /// stopped early by them would leave the agent off its plan.
+    fn dash_along_plan(&mut self, view: &EnvironmentView) -> Option<Action> {
+        let speed = view.agent_state.speed;
+        if speed <= 1 || self.current_plan.len() < speed {
             return None;
         }
-
-        // Reconstruct path
-        let mut path = Vec::new();
-        let mut current = goal;
-        path.push(current);
-
-        while current != start {
-            current = *came_from.get(&current)?;
-            path.push(current);
-        }
-
-        path.reverse();
-        Some(path)
-    }
-
-    /// Gets valid neighbors for a position based on the environment and keys held
-    fn get_valid_neighbors(
-        &self,
-        position: &Position,
-        view: &EnvironmentView,
-        keys_held: &HashSet<DoorKeyType>,
-    ) -> Vec<Position> {
-        let mut neighbors = Vec::new();
-        let terrain = view.terrain_grid;
-        let agents = view.agent_location_grid;
-
-        // Check all four directions
-        let directions = [
-            (0, 1),  // Down
-            (0, -1), // Up
-            (1, 0),  // Right
-            (-1, 0), // Left
-        ];
-
-        for (dx, dy) in directions.iter() {
-            // Calculate neighbor position, handling potential overflow
-            let nx = match position.x.checked_add_signed(*dx) {
-                Some(x) => x,
-                None => continue, // Skip invalid positions
-            };
-
-            let ny = match position.y.checked_add_signed(*dy) {
-                Some(y) => y,
-                None => continue, // Skip invalid positions
-            };
-
-            // Check if position is valid in the grid
-            if !terrain.is_valid(nx, ny) {
-                continue;
+        let mut from = view.location;
+        let mut direction = None;
+        for next in self.current_plan.iter().take(speed) {
+            let offset = view
+                .terrain_grid
+                .step_between(from, *next, view.wrap_edges)?;
+            if direction.is_some_and(|direction| direction != offset)
+                || matches!(view.terrain_grid[*next], CellType::Door { .. })
+                || view.item_grid[*next].is_some()
+            {
+                return None;
             }
-
-            let neighbor_pos = Position { x: nx, y: ny };
-
-            // Check if position is occupied by another agent
-            if let Some(Some(_)) = agents.get(nx, ny) {
-                continue;
-            }
-
-            // Check terrain type
-            match terrain.get(nx, ny) {
-                Some(CellType::Wall) => continue,
-                Some(CellType::Door {
-                    open: false,
-                    door_type: Some(required_key),
-                }) => {
-                    // Check if we have the key for this door
-                    if !keys_held.contains(required_key) {
-                        continue;
-                    }
-                }
-                Some(CellType::Door {
-                    open: false,
-                    door_type: None,
-                }) => {
-                    // No key required, can be opened
-                }
-                Some(CellType::Door { open: true, .. }) | Some(CellType::Floor) => {
-                    // These are always valid
-                }
-                None => continue, // Should never happen with valid position
-            }
-
-            neighbors.push(neighbor_pos);
+            direction = Some(offset);
+            from = *next;
         }
-
-        neighbors
+        let Offset { dx, dy } = direction?;
+        self.current_plan.drain(..speed);
+        Some(Action::Dash { dx, dy })
     }
 
     /// Extracts the keys currently held by the agent
     fn get_keys_held(&self, view: &EnvironmentView) -> HashSet<DoorKeyType> {
-        let mut keys = HashSet::new();
-
-        for item in &view.agent_state.inventory {
-            if let Item::Key { key_type } = item {
-                keys.insert(*key_type);
-            }
-        }
-
-        keys
+        view.key_hierarchy.openable_doors(&view.usable_keys)
     }
 
-    /// Finds all positions with chips in the environment
+    /// Finds all positions with chips that can be collected now: plain and bonus chips,
+    /// and the ordered chip expected next
     fn find_chips(&self, view: &EnvironmentView) -> Vec<Position> {
         let mut chip_positions = Vec::new();
 
         for ((x, y), item_opt) in view.item_grid.enumerate() {
-            if let Some(Item::Chip) = item_opt {
-                chip_positions.push(Position { x, y });
+            match item_opt {
+                Some(Item::Chip | Item::BonusChip { .. }) => chip_positions.push(Position { x, y }),
+                Some(Item::OrderedChip { order }) if *order == view.next_chip_order => {
+                    chip_positions.push(Position { x, y })
+                }
+                _ => {}
             }
         }
 
@@ -327,14 +386,14 @@ impl PlanningAgent {
         }
 
         let mut best_plan = None;
-        let mut min_length = usize::MAX;
+        let mut min_cost = usize::MAX;
 
         for target in targets {
-            if let Some(plan) = self.a_star_path(start, *target, view, keys_held) {
-                if plan.len() < min_length {
-                    min_length = plan.len();
-                    best_plan = Some(plan);
-                }
+            if let Some(plan) = self.a_star_path(start, *target, view, keys_held)
+                && path_cost(view.terrain_grid, &plan, &self.config(view)) < min_cost
+            {
+                min_cost = path_cost(view.terrain_grid, &plan, &self.config(view));
+                best_plan = Some(plan);
             }
         }
 
@@ -351,20 +410,24 @@ impl PlanningAgent {
         let key_locations = self.find_keys(view);
 
         let mut best_plan = None;
-        let mut min_length = usize::MAX;
+        let mut min_cost = usize::MAX;
 
-        for (key_type, positions) in key_locations {
+        // Iterate key types in a fixed order so ties are broken deterministically
+        for key_type in DoorKeyType::ALL {
             // Skip keys we already have
             if keys_held.contains(&key_type) {
                 continue;
             }
+            let Some(positions) = key_locations.get(&key_type) else {
+                continue;
+            };
 
-            for key_pos in &positions {
-                if let Some(plan) = self.a_star_path(start, *key_pos, view, keys_held) {
-                    if plan.len() < min_length {
-                        min_length = plan.len();
-                        best_plan = Some(plan);
-                    }
+            for key_pos in positions {
+                if let Some(plan) = self.a_star_path(start, *key_pos, view, keys_held)
+                    && path_cost(view.terrain_grid, &plan, &self.config(view)) < min_cost
+                {
+                    min_cost = path_cost(view.terrain_grid, &plan, &self.config(view));
+                    best_plan = Some(plan);
                 }
             }
         }
@@ -378,13 +441,21 @@ impl Agent for PlanningAgent {
         self.id
     }
 
+    fn current_target(&self) -> Option<Position> {
+        self.current_plan.back().copied()
+    }
+
+    fn reset(&mut self) {
+        self.current_plan.clear();
+    }
+
     fn get_action(&mut self, view: &EnvironmentView) -> Action {
         let current_pos = view.location;
         let keys_held = self.get_keys_held(view);
 
         // 1. Follow existing plan if available
-        if let Some(next_pos) = self.current_plan.pop_front() {
-            return Self::position_to_action(&current_pos, &next_pos);
+        if let Some(action) = self.follow_plan(view) {
+            return action;
         }
 
         // 2. Determine primary targets (chips or goal)
@@ -392,47 +463,349 @@ impl Agent for PlanningAgent {
 
         if !chips.is_empty() {
             // Try to plan to the nearest chip
-            if let Some(plan) = self.plan_to_nearest_target(current_pos, &chips, view, &keys_held) {
-                if plan.len() > 1 {
-                    // Skip the first position (current position)
-                    self.current_plan.extend(plan.into_iter().skip(1));
-                    return if let Some(next_pos) = self.current_plan.pop_front() {
-                        Self::position_to_action(&current_pos, &next_pos)
-                    } else {
-                        Action::Wait
-                    };
-                }
+            if let Some(plan) = self.plan_to_nearest_target(current_pos, &chips, view, &keys_held)
+                && plan.len() > 1
+            {
+                // Skip the first position (current position)
+                self.current_plan.extend(plan.into_iter().skip(1));
+                return self.follow_plan(view).unwrap_or(Action::Wait);
             }
         } else {
             // No chips left, try to plan to the goal
             let goals = self.find_goals(view);
-            if let Some(plan) = self.plan_to_nearest_target(current_pos, &goals, view, &keys_held) {
-                if plan.len() > 1 {
-                    // Skip the first position (current position)
-                    self.current_plan.extend(plan.into_iter().skip(1));
-                    return if let Some(next_pos) = self.current_plan.pop_front() {
-                        Self::position_to_action(&current_pos, &next_pos)
-                    } else {
-                        Action::Wait
-                    };
-                }
+            if let Some(plan) = self.plan_to_nearest_target(current_pos, &goals, view, &keys_held)
+                && plan.len() > 1
+            {
+                // Skip the first position (current position)
+                self.current_plan.extend(plan.into_iter().skip(1));
+                return self.follow_plan(view).unwrap_or(Action::Wait);
             }
         }
 
         // 3. If primary targets unreachable, try to get a key
-        if let Some(key_plan) = self.plan_to_nearest_reachable_key(current_pos, view, &keys_held) {
-            if key_plan.len() > 1 {
-                // Skip the first position (current position)
-                self.current_plan.extend(key_plan.into_iter().skip(1));
-                return if let Some(next_pos) = self.current_plan.pop_front() {
-                    Self::position_to_action(&current_pos, &next_pos)
-                } else {
-                    Action::Wait
-                };
-            }
+        if let Some(key_plan) = self.plan_to_nearest_reachable_key(current_pos, view, &keys_held)
+            && key_plan.len() > 1
+        {
+            // Skip the first position (current position)
+            self.current_plan.extend(key_plan.into_iter().skip(1));
+            return self.follow_plan(view).unwrap_or(Action::Wait);
         }
 
         // 4. No valid plan, Do nothing
         Action::Wait
     }
+
+    fn box_clone(&self) -> Box<dyn Agent> {
+        Box::new(self.clone())
+    }
+}
+
+/// A planning agent that never steps onto a hazard.
+///
+/// Plans are made with hazards excluded from the pathfinder, and every step is checked
+/// against the current view before it is taken. If the next cell has become a hazard, is
+/// occupied or is next to a patroller, the plan is dropped and the agent waits rather than
+/// walking into it, stepping away instead if a patroller could reach it where it stands.
+#[derive(Debug, Clone)]
+pub struct CautiousPlanningAgent {
+    planner: PlanningAgent,
+}
+
+impl CautiousPlanningAgent {
+    pub fn new(id: EntityId) -> Self {
+        Self::with_config(id, PathfindingConfig::default())
+    }
+
+    /// Creates a cautious agent from the given pathfinding configuration. Hazard
+    /// avoidance is always enabled.
+    pub fn with_config(id: EntityId, pathfinding: PathfindingConfig) -> Self {
+        Self {
+            planner: PlanningAgent::with_config(
+                id,
+                PathfindingConfig {
+                    avoid_hazards: true,
+                    ..pathfinding
+                },
+            ),
+        }
+    }
+
+    /// Returns true if moving by `(dx, dy)` from the current location lands on a cell that
+    /// is neither a hazard nor occupied by another agent, and that no patroller can reach
+    /// in a single step. Steps off the edge wrap around if the map does.
+    fn is_safe_step(view: &EnvironmentView, dx: isize, dy: isize) -> bool {
+        let Some(target) = view
+            .terrain_grid
+            .step(view.location, dx, dy, view.wrap_edges)
+        else {
+            return false;
+        };
+        if matches!(
+            view.terrain_grid.get(target.x, target.y),
+            Some(CellType::Hazard)
+        ) || matches!(
+            view.agent_location_grid.get(target.x, target.y),
+            Some(Some(_))
+        ) {
+            return false;
+        }
+        !Self::is_threatened(view, target)
+    }
+
+    /// Returns true if a patroller stands on or next to `position`, including across
+    /// wrapping edges.
+    fn is_threatened(view: &EnvironmentView, position: Position) -> bool {
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+            .filter_map(|(dx, dy)| view.terrain_grid.step(position, dx, dy, view.wrap_edges))
+            .any(|neighbor| {
+                view.agent_location_grid[neighbor]
+                    .is_some_and(|occupant| view.patrollers.contains(&occupant))
+            })
+    }
+
+    /// Waits if the current cell is safe, otherwise steps to the first enterable neighbor
+    /// out of reach of every patroller.
+    fn retreat(view: &EnvironmentView) -> Action {
+        if !Self::is_threatened(view, view.location) {
+            return Action::Wait;
+        }
+
+        let keys_held = view.key_hierarchy.openable_doors(&view.usable_keys);
+        let current_cell = &view.terrain_grid[view.location];
+        for (dx, dy) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+            let enterable = view
+                .terrain_grid
+                .step(view.location, dx, dy, view.wrap_edges)
+                .and_then(|target| view.terrain_grid.get(target.x, target.y))
+                .is_some_and(|cell| {
+                    cell.is_passable_with(&keys_held) && cell.permits_direction(dx, dy)
+                });
+            if enterable
+                && current_cell.permits_direction(dx, dy)
+                && Self::is_safe_step(view, dx, dy)
+            {
+                return Action::Move { dx, dy };
+            }
+        }
+        Action::Wait
+    }
+}
+
+impl Agent for CautiousPlanningAgent {
+    fn id(&self) -> EntityId {
+        self.planner.id()
+    }
+
+    fn current_target(&self) -> Option<Position> {
+        self.planner.current_target()
+    }
+
+    fn reset(&mut self) {
+        self.planner.reset();
+    }
+
+    fn get_action(&mut self, view: &EnvironmentView) -> Action {
+        match self.planner.get_action(view) {
+            Action::Move { dx, dy } | Action::Dash { dx, dy }
+                if !Self::is_safe_step(view, dx, dy) =>
+            {
+                // The plan is stale; replan from scratch on the next turn
+                self.planner.reset();
+                Self::retreat(view)
+            }
+            Action::Wait => Self::retreat(view),
+            action => action,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Agent> {
+        Box::new(self.clone())
+    }
+}
+
+/// A patrolling enemy that loops over a fixed route of waypoints, taking one step towards
+/// the next waypoint each turn. Add it with `Environment::add_patroller`.
+#[derive(Debug, Clone)]
+pub struct PatrolAgent {
+    id: EntityId,
+    route: Vec<Position>,
+    next_waypoint: usize,
+}
+
+impl PatrolAgent {
+    /// Creates a patroller following `route`, returning to the first waypoint after the
+    /// last. An empty route makes the patroller stand still.
+    pub fn new(id: EntityId, route: Vec<Position>) -> Self {
+        Self {
+            id,
+            route,
+            next_waypoint: 0,
+        }
+    }
+}
+
+impl Agent for PatrolAgent {
+    fn id(&self) -> EntityId {
+        self.id
+    }
+
+    fn current_target(&self) -> Option<Position> {
+        self.route.get(self.next_waypoint).copied()
+    }
+
+    fn reset(&mut self) {
+        self.next_waypoint = 0;
+    }
+
+    fn get_action(&mut self, view: &EnvironmentView) -> Action {
+        if self.route.is_empty() {
+            return Action::Wait;
+        }
+        if view.location == self.route[self.next_waypoint] {
+            self.next_waypoint = (self.next_waypoint + 1) % self.route.len();
+        }
+
+        let target = self.route[self.next_waypoint];
+        let Offset { dx, dy } = (target - view.location).signum();
+        if dx == 0 && dy == 0 {
+            Action::Wait
+        } else {
+            Action::Move { dx, dy }
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Agent> {
+        Box::new(self.clone())
+    }
+}
+
+/// Replays a recorded path one entry per turn, e.g. to show a previous run alongside the
+/// current one. Add it with `Environment::add_ghost`. Repeated positions replay as waits,
+/// and the ghost waits once the path is exhausted.
+#[derive(Debug, Clone)]
+pub struct GhostAgent {
+    id: EntityId,
+    path: Vec<Position>,
+    next: usize,
+}
+
+impl GhostAgent {
+    /// Creates a ghost retracing `path`. A leading entry equal to the ghost's starting
+    /// position is skipped, so recordings may include or omit the start.
+    pub fn new(id: EntityId, path: Vec<Position>) -> Self {
+        Self { id, path, next: 0 }
+    }
+}
+
+impl Agent for GhostAgent {
+    fn id(&self) -> EntityId {
+        self.id
+    }
+
+    fn reset(&mut self) {
+        self.next = 0;
+    }
+
+    fn get_action(&mut self, view: &EnvironmentView) -> Action {
+        if self.next == 0 && self.path.first() == Some(&view.location) {
+            self.next = 1;
+        }
+        let Some(target) = self.path.get(self.next) else {
+            return Action::Wait;
+        };
+        self.next += 1;
+
+        let Offset { dx, dy } = (*target - view.location).signum();
+        if dx == 0 && dy == 0 {
+            Action::Wait
+        } else {
+            Action::Move { dx, dy }
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Agent> {
+        Box::new(self.clone())
+    }
+}
+
+/// A queue of actions waiting to be taken by a `HumanAgent`, shared between the agent and
+/// whatever reads the player's input.
+#[derive(Debug, Clone, Default)]
+pub struct HumanInput {
+    actions: Arc<Mutex<VecDeque<Action>>>,
+}
+
+impl HumanInput {
+    /// Creates an empty input queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues an action for the agent to take on a later turn.
+    pub fn push(&self, action: Action) {
+        if let Ok(mut actions) = self.actions.lock() {
+            actions.push_back(action);
+        }
+    }
+
+    /// Removes and returns the oldest queued action, if any.
+    pub fn pop(&self) -> Option<Action> {
+        self.actions.lock().ok()?.pop_front()
+    }
+
+    /// Drops every queued action.
+    pub fn clear(&self) {
+        if let Ok(mut actions) = self.actions.lock() {
+            actions.clear();
+        }
+    }
+
+    /// Returns the number of queued actions.
+    pub fn len(&self) -> usize {
+        self.actions.lock().map_or(0, |actions| actions.len())
+    }
+
+    /// Returns true if no actions are queued.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// An agent controlled by a player: each turn it takes the next action queued on its
+/// `HumanInput`, waiting if none was queued. Clones share the same input.
+#[derive(Debug, Clone)]
+pub struct HumanAgent {
+    id: EntityId,
+    input: HumanInput,
+}
+
+impl HumanAgent {
+    /// Creates an agent taking its actions from `input`.
+    pub fn new(id: EntityId, input: HumanInput) -> Self {
+        Self { id, input }
+    }
+
+    /// Returns the queue this agent takes its actions from.
+    pub fn input(&self) -> &HumanInput {
+        &self.input
+    }
+}
+
+impl Agent for HumanAgent {
+    fn id(&self) -> EntityId {
+        self.id
+    }
+
+    fn get_action(&mut self, _view: &EnvironmentView) -> Action {
+        self.input.pop().unwrap_or(Action::Wait)
+    }
+
+    fn reset(&mut self) {
+        self.input.clear();
+    }
+
+    fn box_clone(&self) -> Box<dyn Agent> {
+        Box::new(self.clone())
+    }
 }
@@ -0,0 +1,258 @@
+//! Compact binary encoding for grids.
+//!
+//! The format is a header of `width` and `height` as little-endian `u64`s, followed by
+//! each cell in row-major order using its [`CompactCell`] encoding. This is much smaller
+//! than the serde/JSON representation for large generated maps and snapshots.
+
+use crate::{
+    DoorKeyType, Item,
+    environment::CellType,
+    map::{Grid, GridError},
+};
+
+/// A cell value that can be written to and read from the compact binary grid format.
+pub trait CompactCell: Sized {
+    /// Appends the encoded cell to `out`.
+    fn encode(&self, out: &mut Vec<u8>);
+
+    /// Decodes a cell from the front of `input`, advancing it past the consumed bytes.
+    fn decode(input: &mut &[u8]) -> Result<Self, GridError>;
+}
+
+impl<T: CompactCell> Grid<T> {
+    /// Serializes the grid to the compact binary format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(16 + self.as_slice().len());
+        write_u64(&mut out, self.width() as u64);
+        write_u64(&mut out, self.height() as u64);
+        for cell in self.iter() {
+            cell.encode(&mut out);
+        }
+        out
+    }
+
+    /// Deserializes a grid from the compact binary format.
+    ///
+    /// Returns `Err(GridError::InvalidEncoding)` if the data is truncated, malformed,
+    /// or has trailing bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, GridError> {
+        let mut input = bytes;
+        let width = read_usize(&mut input)?;
+        let height = read_usize(&mut input)?;
+        let size = width
+            .checked_mul(height)
+            .ok_or_else(|| GridError::InvalidEncoding("grid size overflow".to_string()))?;
+
+        // Every cell takes at least one byte, so reject impossible sizes before allocating
+        if size > input.len() {
+            return Err(GridError::InvalidEncoding(format!(
+                "expected {} cells but only {} bytes remain",
+                size,
+                input.len()
+            )));
+        }
+
+        let mut cells = Vec::with_capacity(size);
+        for _ in 0..size {
+            cells.push(T::decode(&mut input)?);
+        }
+        if !input.is_empty() {
+            return Err(GridError::InvalidEncoding(format!(
+                "{} trailing bytes after grid data",
+                input.len()
+            )));
+        }
+
+        let mut cells = cells.into_iter();
+        Ok(Grid::from_generator(width, height, |_, _| {
+            cells.next().expect("cell count matches grid size")
+        }))
+    }
+}
+
+fn write_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn read_u8(input: &mut &[u8]) -> Result<u8, GridError> {
+    let (&byte, rest) = input
+        .split_first()
+        .ok_or_else(|| GridError::InvalidEncoding("unexpected end of data".to_string()))?;
+    *input = rest;
+    Ok(byte)
+}
+
+fn read_usize(input: &mut &[u8]) -> Result<usize, GridError> {
+    if input.len() < 8 {
+        return Err(GridError::InvalidEncoding(
+            "unexpected end of data".to_string(),
+        ));
+    }
+    let (bytes, rest) = input.split_at(8);
+    *input = rest;
+    let value = u64::from_le_bytes(bytes.try_into().expect("slice has 8 bytes"));
+    usize::try_from(value)
+        .map_err(|_| GridError::InvalidEncoding(format!("value {} does not fit usize", value)))
+}
+
+fn encode_key_type(key_type: Option<DoorKeyType>) -> u8 {
+    match key_type {
+        None => 0,
+        Some(DoorKeyType::Red) => 1,
+        Some(DoorKeyType::Green) => 2,
+        Some(DoorKeyType::Blue) => 3,
+        Some(DoorKeyType::Yellow) => 4,
+    }
+}
+
+fn decode_key_type(input: &mut &[u8]) -> Result<Option<DoorKeyType>, GridError> {
+    match read_u8(input)? {
+        0 => Ok(None),
+        1 => Ok(Some(DoorKeyType::Red)),
+        2 => Ok(Some(DoorKeyType::Green)),
+        3 => Ok(Some(DoorKeyType::Blue)),
+        4 => Ok(Some(DoorKeyType::Yellow)),
+        tag => Err(GridError::InvalidEncoding(format!(
+            "unknown key type tag {}",
+            tag
+        ))),
+    }
+}
+
+// Flags of a door cell, written after its tag. Set flags for optional fields mean the
+// field follows the key type, in the order of the flags.
+const DOOR_OPEN: u8 = 1;
+const DOOR_AUTO_OPEN: u8 = 1 << 1;
+const DOOR_AUTO_CLOSE: u8 = 1 << 2;
+const DOOR_COST: u8 = 1 << 3;
+const DOOR_FLAGS: u8 = DOOR_OPEN | DOOR_AUTO_OPEN | DOOR_AUTO_CLOSE | DOOR_COST;
+
+impl CompactCell for CellType {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            CellType::Floor => out.push(0),
+            CellType::Wall => out.push(1),
+            CellType::Door {
+                open,
+                door_type,
+                auto_close_after,
+                cost,
+                auto_open,
+            } => {
+                let mut flags = 0;
+                for (set, flag) in [
+                    (*open, DOOR_OPEN),
+                    (*auto_open, DOOR_AUTO_OPEN),
+                    (auto_close_after.is_some(), DOOR_AUTO_CLOSE),
+                    (cost.is_some(), DOOR_COST),
+                ] {
+                    if set {
+                        flags |= flag;
+                    }
+                }
+                out.push(2);
+                out.push(flags);
+                out.push(encode_key_type(*door_type));
+                for value in [auto_close_after, cost].into_iter().flatten() {
+                    write_u64(out, *value as u64);
+                }
+            }
+            CellType::Unknown => out.push(3),
+            CellType::Hazard => out.push(5),
+            CellType::Void => out.push(6),
+            CellType::OneWay { direction } => {
+                out.push(4);
+                out.push(direction.0 as i8 as u8);
+                out.push(direction.1 as i8 as u8);
+            }
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, GridError> {
+        match read_u8(input)? {
+            0 => Ok(CellType::Floor),
+            1 => Ok(CellType::Wall),
+            2 => {
+                let flags = read_u8(input)?;
+                if flags & !DOOR_FLAGS != 0 {
+                    return Err(GridError::InvalidEncoding(format!(
+                        "unknown door flags {:#04x}",
+                        flags
+                    )));
+                }
+                let door_type = decode_key_type(input)?;
+                let mut optional =
+                    |flag| (flags & flag != 0).then(|| read_usize(input)).transpose();
+                let auto_close_after = optional(DOOR_AUTO_CLOSE)?;
+                let cost = optional(DOOR_COST)?;
+                Ok(CellType::Door {
+                    open: flags & DOOR_OPEN != 0,
+                    door_type,
+                    auto_close_after,
+                    cost,
+                    auto_open: flags & DOOR_AUTO_OPEN != 0,
+                })
+            }
+            3 => Ok(CellType::Unknown),
+            4 => {
+                let dx = read_u8(input)? as i8 as isize;
+                let dy = read_u8(input)? as i8 as isize;
+                Ok(CellType::OneWay {
+                    direction: (dx, dy),
+                })
+            }
+            5 => Ok(CellType::Hazard),
+            6 => Ok(CellType::Void),
+            tag => Err(GridError::InvalidEncoding(format!(
+                "unknown cell type tag {}",
+                tag
+            ))),
+        }
+    }
+}
+
+impl CompactCell for Option<Item> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            None => out.push(0),
+            Some(Item::Key { key_type }) => {
+                out.push(1);
+                out.push(encode_key_type(Some(*key_type)));
+            }
+            Some(Item::Chip) => out.push(2),
+            Some(Item::BonusChip { value }) => {
+                out.push(3);
+                write_u64(out, *value as u64);
+            }
+            Some(Item::Goal) => out.push(4),
+            Some(Item::OrderedChip { order }) => {
+                out.push(5);
+                write_u64(out, *order as u64);
+            }
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, GridError> {
+        match read_u8(input)? {
+            0 => Ok(None),
+            1 => match decode_key_type(input)? {
+                Some(key_type) => Ok(Some(Item::Key { key_type })),
+                None => Err(GridError::InvalidEncoding(
+                    "key is missing its type".to_string(),
+                )),
+            },
+            2 => Ok(Some(Item::Chip)),
+            3 => Ok(Some(Item::BonusChip {
+                value: read_usize(input)?,
+            })),
+            4 => Ok(Some(Item::Goal)),
+            5 => Ok(Some(Item::OrderedChip {
+                order: read_usize(input)?,
+            })),
+            tag => Err(GridError::InvalidEncoding(format!(
+                "unknown item tag {}",
+                tag
+            ))),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! A shared message board agents can use to coordinate, e.g. to announce which key they
+//! are going for.
+
+use std::collections::BTreeMap;
+
+use crate::EntityId;
+
+/// A message posted to the blackboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlackboardMessage {
+    /// ID of the agent that posted the message.
+    pub author: EntityId,
+    /// Turn the message was posted in.
+    pub turn: usize,
+    /// Free-form message contents.
+    pub body: String,
+}
+
+/// Messages shared between agents, keyed by topic. Posting to a topic replaces the
+/// previous message on it.
+///
+/// Agents read the board through `EnvironmentView::blackboard` and write to it in
+/// `Agent::write_blackboard`, which runs right after their `get_action`. Messages posted
+/// by one agent are visible to every agent that acts after it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Blackboard {
+    messages: BTreeMap<String, BlackboardMessage>,
+    /// Turn stamped onto new messages, kept up to date by the environment.
+    pub(crate) turn: usize,
+}
+
+impl Blackboard {
+    /// Posts `body` on `topic` on behalf of `author`, returning the message it replaced.
+    pub fn post(
+        &mut self,
+        author: EntityId,
+        topic: impl Into<String>,
+        body: impl Into<String>,
+    ) -> Option<BlackboardMessage> {
+        self.messages.insert(
+            topic.into(),
+            BlackboardMessage {
+                author,
+                turn: self.turn,
+                body: body.into(),
+            },
+        )
+    }
+
+    /// Returns the message on `topic`, if any.
+    pub fn get(&self, topic: &str) -> Option<&BlackboardMessage> {
+        self.messages.get(topic)
+    }
+
+    /// Removes and returns the message on `topic`, if any.
+    pub fn remove(&mut self, topic: &str) -> Option<BlackboardMessage> {
+        self.messages.remove(topic)
+    }
+
+    /// Iterates over all topics and their messages, ordered by topic.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &BlackboardMessage)> {
+        self.messages
+            .iter()
+            .map(|(topic, message)| (topic.as_str(), message))
+    }
+
+    /// Returns the number of topics with a message.
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Returns true if nothing has been posted.
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Removes every message.
+    pub fn clear(&mut self) {
+        self.messages.clear();
+    }
+}
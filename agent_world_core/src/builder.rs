@@ -0,0 +1,255 @@
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+use crate::{
+    DoorKeyType, Item, Position,
+    environment::{CellType, Environment},
+    map::Grid,
+};
+
+/// How many times likelier a cell near a cluster centre is to be picked than any other
+/// cell under `PlacementStrategy::Clustered`.
+const CLUSTER_WEIGHT: u32 = 20;
+
+/// A side of a rectangular region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// How `MapBuilder::scatter` spreads items over the free floor cells.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlacementStrategy {
+    /// Every free floor cell is equally likely.
+    #[default]
+    Uniform,
+    /// Items gather around `clusters` randomly chosen floor cells, favouring cells within
+    /// `radius` (Chebyshev distance) of a centre.
+    Clustered { clusters: usize, radius: usize },
+}
+
+/// Items for `MapBuilder::scatter` to place at random.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemPlacement {
+    /// Number of chips to place.
+    pub chips: usize,
+    /// Colors to place one key and one matching locked door of.
+    pub key_colors: Vec<DoorKeyType>,
+    pub strategy: PlacementStrategy,
+    /// Seed for the random placement, so the same placement always gives the same map.
+    pub seed: u64,
+}
+
+/// Builds an environment programmatically rather than from a map string.
+///
+/// Methods can be chained; placement errors (e.g. out-of-bounds cells) are collected and
+/// reported by `build`.
+#[derive(Debug, Clone)]
+pub struct MapBuilder {
+    terrain: Grid<CellType>,
+    items: Grid<Option<Item>>,
+    start: Option<Position>,
+    errors: Vec<String>,
+}
+
+impl MapBuilder {
+    /// Creates a builder for a map of the given size filled with floor.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            terrain: Grid::new(width, height),
+            items: Grid::new(width, height),
+            start: None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Sets the terrain of a single cell.
+    pub fn cell(&mut self, position: Position, cell: CellType) -> &mut Self {
+        if let Err(err) = self.terrain.set(position.x, position.y, cell) {
+            self.errors.push(err.to_string());
+        }
+        self
+    }
+
+    /// Fills the `width` x `height` rectangle with its top-left corner at (`x`, `y`).
+    pub fn fill_region(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        cell: CellType,
+    ) -> &mut Self {
+        for cy in y..y + height {
+            for cx in x..x + width {
+                self.cell(Position { x: cx, y: cy }, cell.clone());
+            }
+        }
+        self
+    }
+
+    /// Lays down a room: a wall rectangle with a floor interior and a single closed door
+    /// in the middle of the given side. `door_type` is the key required, if any.
+    pub fn room(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        door_side: Side,
+        door_type: Option<DoorKeyType>,
+    ) -> &mut Self {
+        if width < 3 || height < 3 {
+            self.errors.push(format!(
+                "Room at ({}, {}) must be at least 3x3, got {}x{}.",
+                x, y, width, height
+            ));
+            return self;
+        }
+
+        self.fill_region(x, y, width, height, CellType::Wall);
+        self.fill_region(x + 1, y + 1, width - 2, height - 2, CellType::Floor);
+
+        let door = match door_side {
+            Side::Top => Position {
+                x: x + width / 2,
+                y,
+            },
+            Side::Bottom => Position {
+                x: x + width / 2,
+                y: y + height - 1,
+            },
+            Side::Left => Position {
+                x,
+                y: y + height / 2,
+            },
+            Side::Right => Position {
+                x: x + width - 1,
+                y: y + height / 2,
+            },
+        };
+        self.cell(
+            door,
+            CellType::Door {
+                open: false,
+                door_type,
+                auto_close_after: None,
+                cost: None,
+                auto_open: true,
+            },
+        )
+    }
+
+    /// Places an item on a cell.
+    pub fn item(&mut self, position: Position, item: Item) -> &mut Self {
+        if let Err(err) = self.items.set(position.x, position.y, Some(item)) {
+            self.errors.push(err.to_string());
+        }
+        self
+    }
+
+    /// Places the items of `placement` on random floor cells that have no item and aren't
+    /// the start, never two on the same cell. Doors replace the floor they land on. Records
+    /// an error if there aren't enough free cells for every item.
+    pub fn scatter(&mut self, placement: &ItemPlacement) -> &mut Self {
+        let mut rng = StdRng::seed_from_u64(placement.seed);
+        let mut free: Vec<Position> = self
+            .terrain
+            .positions()
+            .filter(|position| {
+                self.terrain[*position] == CellType::Floor
+                    && self.items[*position].is_none()
+                    && self.start != Some(*position)
+            })
+            .collect();
+
+        let wanted = placement.chips + 2 * placement.key_colors.len();
+        if wanted > free.len() {
+            self.errors.push(format!(
+                "Cannot scatter {} items over {} free floor cells.",
+                wanted,
+                free.len()
+            ));
+            return self;
+        }
+
+        let centres: Vec<Position> = match placement.strategy {
+            PlacementStrategy::Uniform => Vec::new(),
+            PlacementStrategy::Clustered { clusters, .. } => {
+                free.choose_multiple(&mut rng, clusters).copied().collect()
+            }
+        };
+        let weight = |position: &Position| match placement.strategy {
+            PlacementStrategy::Clustered { radius, .. }
+                if centres
+                    .iter()
+                    .any(|centre| centre.chebyshev_distance(position) <= radius) =>
+            {
+                CLUSTER_WEIGHT
+            }
+            _ => 1,
+        };
+
+        let take = |rng: &mut StdRng, free: &mut Vec<Position>| {
+            let total: u32 = free.iter().map(&weight).sum();
+            let mut choice = rng.random_range(0..total);
+            let index = free
+                .iter()
+                .position(|position| match choice.checked_sub(weight(position)) {
+                    Some(rest) => {
+                        choice = rest;
+                        false
+                    }
+                    None => true,
+                })
+                .unwrap_or(free.len() - 1);
+            free.swap_remove(index)
+        };
+
+        for _ in 0..placement.chips {
+            let position = take(&mut rng, &mut free);
+            self.items[position] = Some(Item::Chip);
+        }
+        for door_type in &placement.key_colors {
+            let position = take(&mut rng, &mut free);
+            self.items[position] = Some(Item::Key {
+                key_type: *door_type,
+            });
+            let position = take(&mut rng, &mut free);
+            self.terrain[position] = CellType::Door {
+                open: false,
+                door_type: Some(*door_type),
+                auto_close_after: None,
+                cost: None,
+                auto_open: true,
+            };
+        }
+        self
+    }
+
+    /// Sets the start position.
+    pub fn start(&mut self, position: Position) -> &mut Self {
+        if !self.terrain.is_valid(position.x, position.y) {
+            self.errors
+                .push(format!("Start position {:?} is out of bounds.", position));
+        }
+        self.start = Some(position);
+        self
+    }
+
+    /// Builds the environment, returning it with the start position like
+    /// `load_environment_from_string`.
+    pub fn build(&self) -> Result<(Environment, Position), String> {
+        if let Some(err) = self.errors.first() {
+            return Err(err.clone());
+        }
+        let start = self
+            .start
+            .ok_or_else(|| "No start position set.".to_string())?;
+
+        let environment = Environment::from_grids(self.terrain.clone(), self.items.clone())?;
+        Ok((environment, start))
+    }
+}
@@ -0,0 +1,119 @@
+//! Ordered sequences of maps where winning one map moves on to the next.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    Position,
+    environment::{Environment, load_environment_from_string},
+};
+
+/// What happened when a campaign was advanced past its current map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CampaignProgress {
+    /// Moved on to the map at the given path.
+    Next(PathBuf),
+    /// The final map was completed; there is no next map.
+    Complete,
+}
+
+/// An ordered list of map files played one after another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Campaign {
+    maps: Vec<PathBuf>,
+    current: usize,
+    complete: bool,
+}
+
+impl Campaign {
+    /// Creates a campaign playing `maps` in order.
+    pub fn new(maps: Vec<PathBuf>) -> Result<Self, String> {
+        if maps.is_empty() {
+            return Err("Campaign has no maps.".to_string());
+        }
+        Ok(Self {
+            maps,
+            current: 0,
+            complete: false,
+        })
+    }
+
+    /// Creates a campaign from every `.txt` file in `dir`, ordered by file name.
+    pub fn from_directory(dir: &Path) -> Result<Self, String> {
+        let entries = std::fs::read_dir(dir)
+            .map_err(|err| format!("Failed to read directory {}: {}", dir.display(), err))?;
+
+        let mut maps = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|err| format!("Failed to read directory {}: {}", dir.display(), err))?
+                .path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "txt") {
+                maps.push(path);
+            }
+        }
+        maps.sort();
+        Self::new(maps)
+    }
+
+    /// Creates a campaign from a manifest file listing one map path per line. Relative
+    /// paths are resolved against the manifest's directory; blank lines and lines starting
+    /// with `#` are ignored.
+    pub fn from_manifest(manifest: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(manifest)
+            .map_err(|err| format!("Failed to read manifest {}: {}", manifest.display(), err))?;
+        let base = manifest.parent().unwrap_or(Path::new(""));
+
+        let maps = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| base.join(line))
+            .collect();
+        Self::new(maps)
+    }
+
+    /// Returns the map currently being played.
+    pub fn current_map(&self) -> &Path {
+        &self.maps[self.current]
+    }
+
+    /// Returns the zero-based index of the current map.
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    /// Returns the number of maps in the campaign.
+    pub fn len(&self) -> usize {
+        self.maps.len()
+    }
+
+    /// Campaigns always have at least one map.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns true once the final map has been completed.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Loads the current map, returning the environment and start position like
+    /// `load_environment_from_string`.
+    pub fn load_current(&self) -> Result<(Environment, Position), String> {
+        let path = self.current_map();
+        let map = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read map {}: {}", path.display(), err))?;
+        load_environment_from_string(&map)
+    }
+
+    /// Marks the current map as won and moves on to the next one, if any.
+    pub fn advance(&mut self) -> CampaignProgress {
+        if self.current + 1 < self.maps.len() {
+            self.current += 1;
+            CampaignProgress::Next(self.maps[self.current].clone())
+        } else {
+            self.complete = true;
+            CampaignProgress::Complete
+        }
+    }
+}
@@ -1,23 +1,156 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    time::{Duration, Instant},
+};
 
-use crate::{DoorKeyType, EntityId, Item, Position, agent::Agent, map::Grid};
+use crate::{
+    DoorKeyType, EntityId, Item, KeyHierarchy, Offset, Position,
+    agent::Agent,
+    blackboard::Blackboard,
+    map::Grid,
+    pathfinding::{PathfindingConfig, find_path},
+};
 
 /// Represents the static type of a cell in the environment grid.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum CellType {
+    #[default]
     Floor,
     Wall,
     Door {
         open: bool,
         /// The type of key required, if any.
         door_type: Option<DoorKeyType>,
+        /// If set, the door closes again at the end of the given number of turns after
+        /// being opened, counting the turn it was opened in.
+        auto_close_after: Option<usize>,
+        /// Number of chips (`Item::Chip`) spent to open the door, if any. Paid in addition
+        /// to the key, if one is also required.
+        cost: Option<usize>,
+        /// Whether moving into the closed door opens it. Doors without it block moves
+        /// until opened with `Action::Interact`.
+        #[serde(default = "CellType::default_auto_open")]
+        auto_open: bool,
+    },
+    /// A passage that can only be entered and left moving in `direction` (`(dx, dy)`).
+    OneWay {
+        direction: (isize, isize),
     },
+    /// A deadly cell: an agent that moves onto it loses the run.
+    Hazard,
+    /// Nonexistent space outside the playable area of an irregularly shaped map. Never
+    /// enterable and, unlike walls, not rendered or counted as part of the map.
+    Void,
+    /// A cell whose contents are not known, used by agents building up their own map
+    /// (e.g. fog-of-war explorers). Never enterable in an actual environment.
+    Unknown,
+}
+
+impl CellType {
+    fn default_auto_open() -> bool {
+        true
+    }
+
+    /// Returns false if this cell is a one-way passage that cannot be entered or left
+    /// by a move of `(dx, dy)`.
+    pub fn permits_direction(&self, dx: isize, dy: isize) -> bool {
+        match self {
+            CellType::OneWay { direction } => *direction == (dx, dy),
+            _ => true,
+        }
+    }
+
+    /// Returns true if an agent holding `keys` could stand on this cell: floors, one-way
+    /// passages, and doors that are open, unlocked, or openable with one of the keys.
+    /// Movement direction is not considered.
+    pub fn is_passable_with(&self, keys: &HashSet<DoorKeyType>) -> bool {
+        match self {
+            CellType::Floor | CellType::OneWay { .. } => true,
+            CellType::Door {
+                open: false,
+                door_type: Some(required_key),
+                ..
+            } => keys.contains(required_key),
+            CellType::Door { .. } => true,
+            CellType::Wall | CellType::Hazard | CellType::Void | CellType::Unknown => false,
+        }
+    }
+
+    /// Returns why terrain rules forbid a move of `(dx, dy)` from this cell onto `target`
+    /// by an agent whose keys open the doors in `keys`, or `None` if the move is allowed.
+    ///
+    /// This is the single source of truth for passability shared by the engine and the
+    /// pathfinder. Hazards may be entered (and lose the run); other agents, items and
+    /// chip door costs are not considered.
+    pub fn entry_failure(
+        &self,
+        target: &CellType,
+        dx: isize,
+        dy: isize,
+        keys: &HashSet<DoorKeyType>,
+    ) -> Option<ActionFailure> {
+        if !self.permits_direction(dx, dy) {
+            return Some(ActionFailure::OneWayExit);
+        }
+        match target {
+            CellType::Wall => Some(ActionFailure::Wall),
+            CellType::Unknown => Some(ActionFailure::UnknownCell),
+            CellType::Void => Some(ActionFailure::Void),
+            CellType::OneWay { direction } if *direction != (dx, dy) => {
+                Some(ActionFailure::OneWayEntry)
+            }
+            CellType::Door {
+                open: false,
+                door_type: Some(required_key),
+                ..
+            } if !keys.contains(required_key) => Some(ActionFailure::MissingKey(*required_key)),
+            CellType::Floor
+            | CellType::Hazard
+            | CellType::OneWay { .. }
+            | CellType::Door { .. } => None,
+        }
+    }
 }
 
-impl Default for CellType {
-    fn default() -> Self {
-        CellType::Floor
+impl Grid<CellType> {
+    /// Labels each passable cell with the ID of its connected region, found by flood fill
+    /// over cardinal neighbors. Walls, void, unknown cells and locked doors without a
+    /// matching key are boundaries and get `None`. Region IDs are assigned in row-major order.
+    pub fn connected_components(&self, keys: &HashSet<DoorKeyType>) -> Grid<Option<usize>> {
+        let mut labels: Grid<Option<usize>> = Grid::new(self.width(), self.height());
+        let mut next_label = 0;
+
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                if labels[(x, y)].is_some() || !self[(x, y)].is_passable_with(keys) {
+                    continue;
+                }
+
+                let mut frontier = VecDeque::from([(x, y)]);
+                labels[(x, y)] = Some(next_label);
+                while let Some((cx, cy)) = frontier.pop_front() {
+                    for (dx, dy) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+                        let (Some(nx), Some(ny)) =
+                            (cx.checked_add_signed(dx), cy.checked_add_signed(dy))
+                        else {
+                            continue;
+                        };
+                        if self
+                            .get(nx, ny)
+                            .is_some_and(|cell| cell.is_passable_with(keys))
+                            && labels[(nx, ny)].is_none()
+                        {
+                            labels[(nx, ny)] = Some(next_label);
+                            frontier.push_back((nx, ny));
+                        }
+                    }
+                }
+                next_label += 1;
+            }
+        }
+
+        labels
     }
 }
 
@@ -25,23 +158,427 @@ impl Default for CellType {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Action {
     Wait,
-    Move { dx: isize, dy: isize },
+    Move {
+        dx: isize,
+        dy: isize,
+    },
+    /// Take the first step of a path towards `target`, found with the agent's current keys.
+    MoveTo {
+        target: Position,
+    },
+    /// Step onto `target`, which must be one of the agent's eight neighbors. Otherwise
+    /// behaves exactly like the equivalent `Move`.
+    MoveToAdjacent {
+        target: Position,
+    },
+    /// Drop a chip onto the neighboring cell at `(dx, dy)`, which must be empty floor or
+    /// a hazard. A chip dropped on a hazard is used up and turns it into floor, bridging
+    /// it. The pathfinder does not plan bridges.
+    Drop {
+        dx: isize,
+        dy: isize,
+    },
+    /// Move up to the agent's `speed` cells in a straight line in the direction of
+    /// `(dx, dy)`, collecting items on the way. Stops early at the first cell it can't
+    /// enter, and fails only if not even the first step succeeds.
+    Dash {
+        dx: isize,
+        dy: isize,
+    },
+    /// Open the closed door on the neighboring cell at `(dx, dy)` without moving onto it,
+    /// using up a key or chips just like moving into it would. The only way to open doors
+    /// whose `auto_open` is unset.
+    Interact {
+        dx: isize,
+        dy: isize,
+    },
+}
+
+/// A suggestion of what an agent should do next, see `Environment::next_hint`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Hint {
+    /// Collect the chip at the given position.
+    GoToChip(Position),
+    /// Fetch the key at the given position to get past a locked door.
+    GoToKey(DoorKeyType, Position),
+    /// Open the closed door at the given position, which is on the way to the next
+    /// chip, key or goal.
+    OpenDoor(Position),
+    /// Head for the goal at the given position.
+    GoToGoal(Position),
 }
 
 /// Represents the outcome of processing an agent's action.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ActionResult {
     Success,
-    Failure(String),
+    Failure(ActionFailure),
     Win,
+    /// An agent stepped onto a hazard and the run is lost.
+    Lose,
+    /// The turn budget set by `Environment::max_turns` ran out and the run is lost.
+    TimedOut,
+}
+
+/// Whether a run has ended, as reported by `Environment::game_status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameStatus {
+    /// The run can still be won.
+    InProgress,
+    /// Every agent has finished, or the win condition is otherwise met.
+    Won,
+    /// An agent is standing on a hazard, or the turn budget ran out.
+    Lost,
+    /// The run hasn't ended, but no agent can reach a goal (or a key, when collecting
+    /// keys) even with every key and enough chips for every door.
+    Stalled,
+}
+
+/// What an agent has to do to win a map.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WinCondition {
+    /// Step onto a goal item.
+    #[default]
+    ReachGoal,
+    /// Pick up every key on the map. Keys are picked up even if `allow_duplicate_keys` is
+    /// off. Goals only win once no keys are left, so maps without keys play like
+    /// `ReachGoal`.
+    AllKeysCollected,
+    /// Step onto a goal item while carrying every item in `required`. Goals can be
+    /// walked over without winning until then. Items listed twice must be carried twice.
+    DeliverToGoal { required: Vec<Item> },
+}
+
+impl WinCondition {
+    /// Returns true if an agent with `inventory` wins by stepping onto a goal, with
+    /// `keys_left` telling whether any keys still lie on the map.
+    fn wins_at_goal(&self, inventory: &[Item], keys_left: bool) -> bool {
+        match self {
+            WinCondition::ReachGoal => true,
+            WinCondition::AllKeysCollected => !keys_left,
+            WinCondition::DeliverToGoal { required } => {
+                let mut remaining = inventory.to_vec();
+                required.iter().all(
+                    |item| match remaining.iter().position(|held| held == item) {
+                        Some(index) => {
+                            remaining.swap_remove(index);
+                            true
+                        }
+                        None => false,
+                    },
+                )
+            }
+        }
+    }
+}
+
+/// Decides when a run is won (or lost). The environment consults its evaluator after
+/// every action, see `Environment::evaluate_win`. `WinCondition` implements the built-in
+/// rules; custom evaluators are set with `Environment::win_evaluator`.
+pub trait WinEvaluator {
+    /// Returns the result ending the run, usually `ActionResult::Win`, or `None` if the
+    /// run goes on.
+    fn evaluate(&self, env: &Environment) -> Option<ActionResult>;
+
+    /// Returns a boxed copy of this evaluator, so environments holding one can be cloned.
+    fn box_clone(&self) -> Box<dyn WinEvaluator>;
+}
+
+impl Clone for Box<dyn WinEvaluator> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+impl WinEvaluator for WinCondition {
+    /// Wins once every agent (other than patrollers and ghosts) has finished, or with
+    /// `AllKeysCollected` once the last key on the map has been picked up.
+    fn evaluate(&self, env: &Environment) -> Option<ActionResult> {
+        let mut players = env
+            .agents
+            .keys()
+            .filter(|id| !env.patrollers.contains(id) && !env.ghosts.contains(id));
+        let all_finished = players.all(|id| env.finished.contains_key(id));
+        let keys_collected = *self == WinCondition::AllKeysCollected
+            && env.keys_collected > 0
+            && !env.keys_remaining();
+        ((all_finished && !env.finished.is_empty()) || keys_collected).then_some(ActionResult::Win)
+    }
+
+    fn box_clone(&self) -> Box<dyn WinEvaluator> {
+        Box::new(self.clone())
+    }
+}
+
+/// The reason an agent's action failed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error, Serialize, Deserialize)]
+pub enum ActionFailure {
+    #[error("Agent {0} not found.")]
+    AgentNotFound(EntityId),
+    #[error("Agent {0} has no behavior.")]
+    NoBehavior(EntityId),
+    #[error("Agent {0} has already finished.")]
+    AgentFinished(EntityId),
+    #[error("Target position is out of bounds.")]
+    OutOfBounds,
+    #[error("Cannot move into a wall.")]
+    Wall,
+    #[error("Cannot move into an unknown cell.")]
+    UnknownCell,
+    #[error("Cannot move into void.")]
+    Void,
+    #[error("Target position is occupied by another agent.")]
+    OccupiedByAgent,
+    #[error("Agent lacks the required key type: {0:?}.")]
+    MissingKey(DoorKeyType),
+    #[error("Opening the door costs {0} chips.")]
+    NotEnoughChips(usize),
+    #[error("Chip {found} cannot be collected before chip {expected}.")]
+    ChipOutOfOrder { expected: usize, found: usize },
+    #[error("Cannot enter a one-way passage against its direction.")]
+    OneWayEntry,
+    #[error("Cannot leave a one-way passage against its direction.")]
+    OneWayExit,
+    #[error("Target position {0:?} is unreachable.")]
+    Unreachable(Position),
+    #[error("Target position {0:?} is not adjacent to the agent.")]
+    NotAdjacent(Position),
+    #[error("Agent has no chip to drop.")]
+    NoChipToDrop,
+    #[error("Chips can only be dropped on empty floor or hazards.")]
+    CannotDropHere,
+    #[error("The door has to be opened with an interact action first.")]
+    DoorClosed,
+    #[error("There is no closed door there to open.")]
+    NothingToInteract,
+    #[error("Agent has no energy left to move.")]
+    OutOfEnergy,
+}
+
+/// A likely authoring mistake in a map, found by `Environment::validate`.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MapWarning {
+    #[error("No goal is reachable from the start.")]
+    NoReachableGoal,
+    #[error("{item:?} at {position:?} can never be reached from the start.")]
+    UnreachableItem { position: Position, item: Item },
+    /// The map has too many combinations of keys and doors to check them all, and some
+    /// goal or item wasn't reached among those that were checked.
+    #[error("Too many key and door combinations to check whether everything is reachable.")]
+    ValidationIncomplete,
+}
+
+/// Most states `Environment::search_key_states` explores before giving up, so validating
+/// maps with many keys and doors stays fast enough to run on every editor change.
+const MAX_KEY_SEARCH_STATES: usize = 500;
+
+/// A node of the key-collection search in `Environment::search_key_states`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct KeySearchState {
+    /// Where the agent stands after its last pickup or door opening.
+    position: Position,
+    /// Key items picked up so far.
+    picked: BTreeSet<Position>,
+    /// Locked doors opened so far.
+    opened: BTreeSet<Position>,
+    /// Number of keys currently held of each type.
+    held: BTreeMap<DoorKeyType, usize>,
+}
+
+/// Changes to carry out when an agent's move action succeeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MoveOutcome {
+    from: Position,
+    to: Position,
+    /// Whether the item on the target cell is picked up.
+    pick_up: bool,
+    /// Whether the door on the target cell is opened.
+    open_door: bool,
+    /// Holder and type of the key consumed to open a locked door.
+    consume_key: Option<(EntityId, DoorKeyType)>,
+    /// Number of chips spent to open a door.
+    spend_chips: usize,
+}
+
+/// Chip value of a bonus chip placed with the `CB` map code.
+pub const BONUS_CHIP_VALUE: usize = 3;
+
+/// The items an agent carries, in the order they were picked up.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Inventory(Vec<Item>);
+
+impl Inventory {
+    /// Creates an empty inventory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an item at the end of the inventory.
+    pub fn add(&mut self, item: Item) {
+        self.0.push(item);
+    }
+
+    /// Removes the first item equal to `item`, returning true if there was one.
+    pub fn remove(&mut self, item: &Item) -> bool {
+        match self.0.iter().position(|held| held == item) {
+            Some(index) => {
+                self.0.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the total chip value of the inventory, counting bonus chips by value.
+    pub fn count_chips(&self) -> usize {
+        self.0.iter().map(Item::chip_value).sum()
+    }
+
+    /// Returns the number of items equal to `item`.
+    pub fn count(&self, item: &Item) -> usize {
+        self.0.iter().filter(|held| *held == item).count()
+    }
+
+    /// Iterates over the types of the keys held, once per key.
+    pub fn keys(&self) -> impl Iterator<Item = DoorKeyType> + '_ {
+        self.0.iter().filter_map(|item| match item {
+            Item::Key { key_type } => Some(*key_type),
+            _ => None,
+        })
+    }
+
+    /// Returns true if a key of exactly `key_type` is held.
+    pub fn has_key(&self, key_type: DoorKeyType) -> bool {
+        self.keys().any(|held| held == key_type)
+    }
+
+    /// Removes one key of `key_type`, returning true if there was one.
+    pub fn take_key(&mut self, key_type: DoorKeyType) -> bool {
+        self.remove(&Item::Key { key_type })
+    }
+
+    /// Iterates over the items in the order they were added.
+    pub fn iter(&self) -> std::slice::Iter<'_, Item> {
+        self.0.iter()
+    }
+
+    /// Returns the items in the order they were added.
+    pub fn as_slice(&self) -> &[Item] {
+        &self.0
+    }
+
+    /// Returns the number of items held.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if nothing is held.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<Item>> for Inventory {
+    fn from(items: Vec<Item>) -> Self {
+        Self(items)
+    }
+}
+
+impl<'a> IntoIterator for &'a Inventory {
+    type Item = &'a Item;
+    type IntoIter = std::slice::Iter<'a, Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
 }
 
 /// Holds the state of an agent within the environment.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AgentState {
     pub id: EntityId,
     pub position: Position,
-    pub inventory: Vec<Item>,
+    pub inventory: Inventory,
+    /// Direction of the agent's last successful move as `(dx, dy)`, initially facing right.
+    #[serde(default = "AgentState::initial_facing")]
+    pub facing: (isize, isize),
+    /// Most cells the agent covers with a single `Action::Dash`, 1 for normal agents.
+    #[serde(default = "AgentState::default_speed")]
+    pub speed: usize,
+    /// Steps the agent can still move, or `None` for no limit. Every step of a successful
+    /// move uses one, see `Environment::starting_energy`.
+    #[serde(default)]
+    pub energy: Option<usize>,
+}
+
+impl AgentState {
+    /// Direction agents face before they first move.
+    pub const INITIAL_FACING: (isize, isize) = (1, 0);
+
+    fn initial_facing() -> (isize, isize) {
+        Self::INITIAL_FACING
+    }
+
+    fn default_speed() -> usize {
+        1
+    }
+
+    /// Returns the total chip count (score) of the inventory, counting bonus chips by value.
+    pub fn chip_count(&self) -> usize {
+        self.inventory.count_chips()
+    }
+
+    /// Returns the number of plain chips in the inventory, which can be spent on doors.
+    /// Bonus chips only count towards the score.
+    pub fn spendable_chips(&self) -> usize {
+        self.inventory.count(&Item::Chip)
+    }
+
+    /// Returns true if the agent has no energy left to move.
+    pub fn is_exhausted(&self) -> bool {
+        self.energy == Some(0)
+    }
+
+    /// Returns true if the agent has the energy to walk `path`, which starts at its
+    /// position.
+    pub fn can_afford_path(&self, path: &[Position]) -> bool {
+        self.energy
+            .is_none_or(|energy| path.len().saturating_sub(1) <= energy)
+    }
+
+    /// Returns the set of key types in the inventory.
+    pub fn keys_held(&self) -> HashSet<DoorKeyType> {
+        self.inventory.keys().collect()
+    }
+
+    /// Counts the inventory contents: total chip value and number of keys per type.
+    pub fn inventory_summary(&self) -> InventorySummary {
+        let mut keys = HashMap::new();
+        for key_type in self.inventory.keys() {
+            *keys.entry(key_type).or_insert(0) += 1;
+        }
+        InventorySummary {
+            chips: self.chip_count(),
+            keys,
+        }
+    }
+}
+
+/// Aggregated counts of an agent's inventory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InventorySummary {
+    /// Total chip value collected, counting bonus chips by value.
+    pub chips: usize,
+    /// Number of keys held per type. Types with no keys are absent.
+    pub keys: HashMap<DoorKeyType, usize>,
+}
+
+impl InventorySummary {
+    /// Returns how many keys of the given type are held.
+    pub fn key_count(&self, key_type: DoorKeyType) -> usize {
+        self.keys.get(&key_type).copied().unwrap_or(0)
+    }
 }
 
 /// Provides a read-only view of the environment relevant to an agent.
@@ -49,12 +586,414 @@ pub struct AgentState {
 pub struct EnvironmentView<'a> {
     pub agent_state: &'a AgentState,
     pub location: Position,
+    /// The turn being processed, starting at 0.
+    pub turn: usize,
     pub terrain_grid: &'a Grid<CellType>,
     pub item_grid: &'a Grid<Option<Item>>,
     pub agent_location_grid: &'a Grid<Option<EntityId>>,
+    /// IDs of the patrolling agents, which end the run if they meet another agent.
+    pub patrollers: &'a HashSet<EntityId>,
+    /// Which keys open which doors.
+    pub key_hierarchy: &'a KeyHierarchy,
+    /// Messages posted by agents.
+    pub blackboard: &'a Blackboard,
+    /// Whether the map edges wrap around, see `Environment::wrap_edges`.
+    pub wrap_edges: bool,
+    /// See `Environment::terrain_version`. Plans computed at the same version only need
+    /// rechecking against agent positions.
+    pub terrain_version: u64,
+    /// Key types the agent can open doors with, see `Environment::usable_keys`.
+    pub usable_keys: HashSet<DoorKeyType>,
+    /// Order of the ordered chip that can be collected next, see
+    /// `Environment::next_chip_order`.
+    pub next_chip_order: usize,
+}
+
+impl EnvironmentView<'_> {
+    /// Returns the in-bounds cells within `range` (euclidean distance) of the agent whose
+    /// direction lies at most `half_angle` radians from the agent's facing, in row-major
+    /// order. The agent's own cell is not included. Walls do not block the cone.
+    pub fn cells_in_cone(&self, range: usize, half_angle: f64) -> Vec<Position> {
+        let (fx, fy) = self.agent_state.facing;
+        let facing_angle = (fy as f64).atan2(fx as f64);
+        let origin = self.location;
+
+        let mut cells = Vec::new();
+        for y in origin.y.saturating_sub(range)..=origin.y + range {
+            for x in origin.x.saturating_sub(range)..=origin.x + range {
+                if !self.terrain_grid.is_valid(x, y) || (x, y) == (origin.x, origin.y) {
+                    continue;
+                }
+                let dx = x as f64 - origin.x as f64;
+                let dy = y as f64 - origin.y as f64;
+                if dx.hypot(dy) > range as f64 {
+                    continue;
+                }
+                // Smallest angle between the facing and the direction to the cell
+                let offset = (dy.atan2(dx) - facing_angle).rem_euclid(std::f64::consts::TAU);
+                if offset.min(std::f64::consts::TAU - offset) <= half_angle {
+                    cells.push(Position { x, y });
+                }
+            }
+        }
+        cells
+    }
+
+    /// Returns true if the terrain lets the agent move by `(dx, dy)` in a single step:
+    /// the target is on the map (or wrapped onto it), free of other agents, enterable with
+    /// the agent's keys and affordable if it is a chip door. Hazards count as walkable.
+    pub fn is_walkable(&self, dx: isize, dy: isize) -> bool {
+        let Some(target) = self
+            .terrain_grid
+            .step(self.location, dx, dy, self.wrap_edges)
+        else {
+            return false;
+        };
+        if matches!(self.agent_location_grid.get(target.x, target.y), Some(Some(id)) if *id != self.agent_state.id)
+        {
+            return false;
+        }
+        let cell = &self.terrain_grid[target];
+        if let CellType::Door {
+            open: false,
+            cost: Some(cost),
+            ..
+        } = cell
+            && self.agent_state.spendable_chips() < *cost
+        {
+            return false;
+        }
+        let keys = self.key_hierarchy.openable_doors(&self.usable_keys);
+        self.terrain_grid[self.location]
+            .entry_failure(cell, dx, dy, &keys)
+            .is_none()
+    }
+
+    /// Captures what the agent sees as a serializable `Observation`, e.g. to send to an
+    /// agent running in another process. With `window` set, only the cells within that
+    /// many steps (chebyshev distance) of the agent are included, clipped to the map.
+    pub fn to_observation(&self, window: Option<usize>) -> Observation {
+        let (width, height) = (self.terrain_grid.width(), self.terrain_grid.height());
+        if width == 0 || height == 0 {
+            // Nothing to see on an empty map
+            return Observation {
+                turn: self.turn,
+                agent: self.agent_state.clone(),
+                origin: Position { x: 0, y: 0 },
+                terrain: Grid::new(0, 0),
+                items: Grid::new(0, 0),
+                agents: Vec::new(),
+            };
+        }
+        let (origin, end) = match window {
+            Some(range) => (
+                Position {
+                    x: self.location.x.saturating_sub(range),
+                    y: self.location.y.saturating_sub(range),
+                },
+                Position {
+                    x: self.location.x.saturating_add(range).min(width - 1),
+                    y: self.location.y.saturating_add(range).min(height - 1),
+                },
+            ),
+            None => (
+                Position { x: 0, y: 0 },
+                Position {
+                    x: width - 1,
+                    y: height - 1,
+                },
+            ),
+        };
+        let (window_width, window_height) = (end.x - origin.x + 1, end.y - origin.y + 1);
+        let at = |x, y| Position {
+            x: origin.x + x,
+            y: origin.y + y,
+        };
+
+        let mut agents: Vec<(EntityId, Position)> = Vec::new();
+        for y in origin.y..=end.y {
+            for x in origin.x..=end.x {
+                if let Some(Some(id)) = self.agent_location_grid.get(x, y)
+                    && *id != self.agent_state.id
+                {
+                    agents.push((*id, Position { x, y }));
+                }
+            }
+        }
+        agents.sort_unstable();
+
+        Observation {
+            turn: self.turn,
+            agent: self.agent_state.clone(),
+            origin,
+            terrain: Grid::from_generator(window_width, window_height, |x, y| {
+                self.terrain_grid[at(x, y)].clone()
+            }),
+            items: Grid::from_generator(window_width, window_height, |x, y| {
+                self.item_grid[at(x, y)].clone()
+            }),
+            agents,
+        }
+    }
+}
+
+/// A serializable copy of what an agent sees, built by `EnvironmentView::to_observation`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Observation {
+    /// The turn being processed.
+    pub turn: usize,
+    /// The observing agent's own state, including its position and inventory.
+    pub agent: AgentState,
+    /// Map position of the top-left cell of `terrain` and `items`.
+    pub origin: Position,
+    pub terrain: Grid<CellType>,
+    pub items: Grid<Option<Item>>,
+    /// Other agents in the observed area and their map positions, sorted by ID.
+    pub agents: Vec<(EntityId, Position)>,
+}
+
+/// A serializable capture of the dynamic state of an environment.
+/// Agent behaviors are not included.
+///
+/// Snapshots are versioned so older saves keep loading: fields added after version 1
+/// default when missing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvironmentSnapshot {
+    /// Format version, `SNAPSHOT_VERSION` for new snapshots. Snapshots saved before the
+    /// field existed are read as version 1.
+    #[serde(default = "EnvironmentSnapshot::first_version")]
+    pub version: u32,
+    pub turn: usize,
+    pub terrain: Grid<CellType>,
+    pub items: Grid<Option<Item>>,
+    /// Agent states, sorted by ID.
+    pub agents: Vec<AgentState>,
+    /// Turns left before each open auto-closing door closes, sorted by position.
+    #[serde(default)]
+    pub door_timers: Vec<(Position, usize)>,
+    /// Finished agents and the number of turns they took, sorted by ID.
+    #[serde(default)]
+    pub finished: Vec<(EntityId, usize)>,
+    /// Number of ordered chips collected so far.
+    #[serde(default)]
+    pub ordered_chips_collected: usize,
+    /// Number of keys picked up from the map so far.
+    #[serde(default)]
+    pub keys_collected: usize,
+    /// Collected chips waiting to respawn, with the turns left, sorted by position.
+    #[serde(default)]
+    pub chip_respawns: Vec<(Position, Item, usize)>,
+}
+
+/// Snapshot format version written by `Environment::snapshot`.
+///
+/// Version 1 had no door timers or finish turns; version 2 added them along with
+/// this field. Version 3 added the ordered chip count, version 4 agent speeds,
+/// version 5 chip respawn timers, version 6 agent energy and version 7 the collected
+/// key count.
+pub const SNAPSHOT_VERSION: u32 = 7;
+
+impl EnvironmentSnapshot {
+    fn first_version() -> u32 {
+        1
+    }
+
+    /// Lists what changed from this snapshot to `other`: terrain in row-major order, then
+    /// items, agents by ID and newly finished agents. Only cells inside both grids are
+    /// compared, and turn numbers and agent facings are ignored.
+    pub fn diff(&self, other: &EnvironmentSnapshot) -> Vec<Change> {
+        let mut changes = Vec::new();
+
+        for position in self.terrain.positions() {
+            let (Some(before), Some(after)) = (
+                self.terrain.get(position.x, position.y),
+                other.terrain.get(position.x, position.y),
+            ) else {
+                continue;
+            };
+            let change = match (before, after) {
+                _ if before == after => continue,
+                (CellType::Door { open: false, .. }, CellType::Door { open: true, .. }) => {
+                    Change::DoorOpened(position)
+                }
+                (CellType::Door { open: true, .. }, CellType::Door { open: false, .. }) => {
+                    Change::DoorClosed(position)
+                }
+                _ => Change::TerrainChanged {
+                    position,
+                    from: before.clone(),
+                    to: after.clone(),
+                },
+            };
+            changes.push(change);
+        }
+
+        for position in self.items.positions() {
+            let (Some(before), Some(after)) = (
+                self.items.get(position.x, position.y),
+                other.items.get(position.x, position.y),
+            ) else {
+                continue;
+            };
+            if before == after {
+                continue;
+            }
+            if let Some(item) = before {
+                changes.push(Change::ItemRemoved {
+                    position,
+                    item: item.clone(),
+                });
+            }
+            if let Some(item) = after {
+                changes.push(Change::ItemPlaced {
+                    position,
+                    item: item.clone(),
+                });
+            }
+        }
+
+        let before: HashMap<EntityId, &AgentState> =
+            self.agents.iter().map(|agent| (agent.id, agent)).collect();
+        let after: HashMap<EntityId, &AgentState> =
+            other.agents.iter().map(|agent| (agent.id, agent)).collect();
+        let mut ids: Vec<EntityId> = before.keys().chain(after.keys()).copied().collect();
+        ids.sort_unstable();
+        ids.dedup();
+        for id in ids {
+            match (before.get(&id), after.get(&id)) {
+                (Some(from), Some(to)) => {
+                    if from.position != to.position {
+                        changes.push(Change::AgentMoved {
+                            id,
+                            from: from.position,
+                            to: to.position,
+                        });
+                    }
+                    if from.inventory != to.inventory {
+                        changes.push(Change::InventoryChanged {
+                            id,
+                            from: from.inventory.clone(),
+                            to: to.inventory.clone(),
+                        });
+                    }
+                }
+                (None, Some(to)) => changes.push(Change::AgentAdded {
+                    id,
+                    position: to.position,
+                }),
+                (Some(_), None) => changes.push(Change::AgentRemoved(id)),
+                (None, None) => {}
+            }
+        }
+
+        for (id, turns) in &other.finished {
+            if !self.finished.iter().any(|(finished, _)| finished == id) {
+                changes.push(Change::AgentFinished {
+                    id: *id,
+                    turns: *turns,
+                });
+            }
+        }
+        changes
+    }
+}
+
+/// One difference between two snapshots, as listed by `EnvironmentSnapshot::diff`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Change {
+    DoorOpened(Position),
+    DoorClosed(Position),
+    /// Any other change of a cell, e.g. a hazard bridged by a dropped chip.
+    TerrainChanged {
+        position: Position,
+        from: CellType,
+        to: CellType,
+    },
+    /// An item left the cell, e.g. because an agent collected it.
+    ItemRemoved {
+        position: Position,
+        item: Item,
+    },
+    /// An item appeared on the cell, e.g. a dropped or respawned chip.
+    ItemPlaced {
+        position: Position,
+        item: Item,
+    },
+    AgentMoved {
+        id: EntityId,
+        from: Position,
+        to: Position,
+    },
+    InventoryChanged {
+        id: EntityId,
+        from: Inventory,
+        to: Inventory,
+    },
+    AgentAdded {
+        id: EntityId,
+        position: Position,
+    },
+    AgentRemoved(EntityId),
+    /// The agent reached a goal, taking `turns` turns.
+    AgentFinished {
+        id: EntityId,
+        turns: usize,
+    },
+}
+
+/// Time an agent spent deciding on its actions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecisionTiming {
+    /// Number of decisions timed.
+    pub decisions: usize,
+    /// Total time across all decisions.
+    pub total: Duration,
+    /// The slowest single decision.
+    pub max: Duration,
+}
+
+impl DecisionTiming {
+    fn record(&mut self, elapsed: Duration) {
+        self.decisions += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+
+    /// Mean time per decision, or zero if none were timed.
+    pub fn average(&self) -> Duration {
+        match u32::try_from(self.decisions) {
+            Ok(0) => Duration::ZERO,
+            Ok(decisions) => self.total / decisions,
+            Err(_) => self.total.div_f64(self.decisions as f64),
+        }
+    }
+}
+
+/// An action an agent took: the turn it was processed in, the action and its result.
+pub type ActionRecord = (usize, Action, ActionResult);
+
+/// Most recent actions of each agent, keeping at most `limit` per agent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ActionHistory {
+    limit: usize,
+    records: HashMap<EntityId, VecDeque<ActionRecord>>,
+}
+
+impl ActionHistory {
+    fn record(&mut self, agent_id: EntityId, record: ActionRecord) {
+        if self.limit == 0 {
+            return;
+        }
+        let records = self.records.entry(agent_id).or_default();
+        if records.len() == self.limit {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
 }
 
 /// Manages the simulation environment.
+#[derive(Clone)]
 pub struct Environment {
     pub terrain: Grid<CellType>,
     pub items: Grid<Option<Item>>,
@@ -62,6 +1001,76 @@ pub struct Environment {
     pub agents: HashMap<EntityId, AgentState>,
     pub agent_behaviors: HashMap<EntityId, Box<dyn Agent>>,
     pub next_entity_id: EntityId,
+    /// Number of turns processed so far.
+    pub turn: usize,
+    /// State captured before the first turn was processed, used by `reset_to_initial`.
+    pub initial_state: Option<EnvironmentSnapshot>,
+    /// Per-cell count of successful agent moves onto each cell, if tracking is enabled.
+    pub visit_counts: Option<Grid<usize>>,
+    /// Per-agent time spent in `Agent::get_action`, if timing is enabled.
+    pub decision_timings: Option<HashMap<EntityId, DecisionTiming>>,
+    /// Recent actions of each agent, if history is enabled.
+    pub action_history: Option<ActionHistory>,
+    /// Agents added with `add_patroller`. They ignore items and hazards, and the run is
+    /// lost when one moves onto another agent's cell or an agent moves onto theirs.
+    pub patrollers: HashSet<EntityId>,
+    /// Agents added with `add_ghost`. They move freely without occupying cells or
+    /// interacting with terrain, items or other agents.
+    pub ghosts: HashSet<EntityId>,
+    /// Turns left before each open auto-closing door closes again.
+    pub door_timers: HashMap<Position, usize>,
+    /// Turns after which collected chips and bonus chips reappear where they were picked
+    /// up, or `None` for chips that stay collected.
+    pub chip_respawn_delay: Option<usize>,
+    /// Collected chips waiting to respawn and the turns left until they do.
+    pub chip_respawns: HashMap<Position, (Item, usize)>,
+    /// Which keys open which doors. Exact-match only by default.
+    pub key_hierarchy: KeyHierarchy,
+    /// What agents have to do to win.
+    pub win_condition: WinCondition,
+    /// Custom victory logic used instead of `win_condition` to decide when the run ends.
+    /// Goals still only finish agents as `win_condition` allows.
+    pub win_evaluator: Option<Box<dyn WinEvaluator>>,
+    /// Whether agents pick up keys of a type they already hold. Off by default, which
+    /// leaves duplicate keys on the ground.
+    pub allow_duplicate_keys: bool,
+    /// Whether agents can open locked doors with keys held by any other agent, using up
+    /// the key from that agent's inventory. Their own keys are used first. Off by default.
+    pub shared_inventory: bool,
+    /// Whether moving off one edge of the map enters at the opposite edge instead of
+    /// failing with `ActionFailure::OutOfBounds`. Off by default.
+    pub wrap_edges: bool,
+    /// Turns allowed before `process_turn` ends the run with `ActionResult::TimedOut`,
+    /// or `None` for no limit.
+    pub max_turns: Option<usize>,
+    /// Energy given to agents when they are added, or `None` for agents that can move
+    /// without limit. See `AgentState::energy`.
+    pub starting_energy: Option<usize>,
+    /// Energy an agent regains for each chip or bonus chip it collects.
+    pub chip_energy: usize,
+    /// Whether an agent using up its last energy ends the run with `ActionResult::Lose`.
+    /// Otherwise exhausted agents stay on the map but can only wait.
+    pub exhaustion_ends_run: bool,
+    /// Seed given by the map's `# seed:` header, if any. Callers pass it on to agents
+    /// and generators that use randomness so runs on the map are reproducible.
+    pub seed: Option<u64>,
+    /// Named rectangular regions, each given by its inclusive top-left and bottom-right
+    /// corners. Maps define them with `# zone:` header lines.
+    pub zones: HashMap<String, (Position, Position)>,
+    /// Messages agents post for each other. Cleared on reset.
+    pub blackboard: Blackboard,
+    /// Agents that reached a goal, with the number of turns they took. Finished agents
+    /// stay in `agents` but are removed from `agent_locations` and no longer act.
+    pub finished: HashMap<EntityId, usize>,
+    /// Number of ordered chips collected so far, by any agent. The next chip to collect
+    /// is `Item::OrderedChip { order: ordered_chips_collected + 1 }`.
+    pub ordered_chips_collected: usize,
+    /// Number of keys picked up from the map so far, by any agent.
+    pub keys_collected: usize,
+    /// Increases whenever the environment changes the terrain: a door opening or closing,
+    /// a hazard bridged by a drop, or a reset. Code editing `terrain` directly should call
+    /// `touch_terrain`.
+    pub terrain_version: u64,
 }
 
 impl Environment {
@@ -74,310 +1083,1723 @@ impl Environment {
             agents: HashMap::new(),
             agent_behaviors: HashMap::new(),
             next_entity_id: 0,
+            turn: 0,
+            initial_state: None,
+            visit_counts: None,
+            decision_timings: None,
+            action_history: None,
+            patrollers: HashSet::new(),
+            ghosts: HashSet::new(),
+            door_timers: HashMap::new(),
+            chip_respawn_delay: None,
+            chip_respawns: HashMap::new(),
+            key_hierarchy: KeyHierarchy::default(),
+            win_condition: WinCondition::default(),
+            win_evaluator: None,
+            allow_duplicate_keys: false,
+            shared_inventory: false,
+            wrap_edges: false,
+            max_turns: None,
+            starting_energy: None,
+            chip_energy: 0,
+            exhaustion_ends_run: false,
+            seed: None,
+            zones: HashMap::new(),
+            blackboard: Blackboard::default(),
+            finished: HashMap::new(),
+            ordered_chips_collected: 0,
+            keys_collected: 0,
+            terrain_version: 0,
+        }
+    }
+
+    /// Creates an environment with no agents from existing terrain and item grids.
+    ///
+    /// Returns an error if the two grids have different dimensions.
+    pub fn from_grids(terrain: Grid<CellType>, items: Grid<Option<Item>>) -> Result<Self, String> {
+        if terrain.width() != items.width() || terrain.height() != items.height() {
+            return Err(format!(
+                "Terrain is {}x{} but items are {}x{}.",
+                terrain.width(),
+                terrain.height(),
+                items.width(),
+                items.height()
+            ));
+        }
+        let mut environment = Environment::new(terrain.width(), terrain.height());
+        environment.terrain = terrain;
+        environment.items = items;
+        Ok(environment)
+    }
+
+    /// Generates a unique entity ID for agents.
+    pub fn reserve_entity_id(&mut self) -> EntityId {
+        let id = self.next_entity_id;
+        self.next_entity_id += 1;
+        id
+    }
+
+    /// Adds an item to the environment grid.
+    pub fn add_item(&mut self, position: Position, item: Item) -> Result<(), String> {
+        if !self.terrain.is_valid(position.x, position.y) {
+            return Err(format!("Position {:?} is out of bounds.", position));
+        }
+        if self.items[position].is_some() {
+            return Err(format!("Position {:?} already contains an item.", position));
+        }
+        if self.agent_locations[position].is_some() {
+            return Err(format!("Position {:?} is occupied by an agent.", position));
+        }
+        if self.terrain[position] == CellType::Wall {
+            return Err(format!(
+                "Cannot place item inside a Wall at {:?}.",
+                position
+            ));
+        }
+        if self.terrain[position] == CellType::Void {
+            return Err(format!("Cannot place item in void at {:?}.", position));
+        }
+        self.items[position] = Some(item);
+        Ok(())
+    }
+
+    /// Adds every item in `items`, continuing past failed placements. Returns the position
+    /// and error of each placement that failed, in order.
+    pub fn add_items(
+        &mut self,
+        items: impl IntoIterator<Item = (Position, Item)>,
+    ) -> Result<(), Vec<(Position, String)>> {
+        let failures: Vec<(Position, String)> = items
+            .into_iter()
+            .filter_map(|(position, item)| {
+                self.add_item(position, item)
+                    .err()
+                    .map(|err| (position, err))
+            })
+            .collect();
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures)
+        }
+    }
+
+    /// Adds an agent to the environment.
+    pub fn add_agent(
+        &mut self,
+        position: Position,
+        behavior: Box<dyn Agent>,
+        initial_inventory: Vec<Item>,
+    ) -> Result<EntityId, String> {
+        let agent_id = behavior.id();
+
+        if !self.terrain.is_valid(position.x, position.y) {
+            return Err(format!("Position {:?} is out of bounds.", position));
+        }
+        if self.agent_locations[position].is_some() {
+            return Err(format!(
+                "Position {:?} is already occupied by an agent.",
+                position
+            ));
+        }
+        if self.items[position].is_some() {
+            log_warn!(
+                "Placing agent {} on top of item at {:?}",
+                agent_id,
+                position
+            );
+        }
+        match self.terrain[position] {
+            CellType::Wall => {
+                return Err(format!(
+                    "Cannot place agent inside a Wall at {:?}.",
+                    position
+                ));
+            }
+            CellType::Door { open: false, .. } => {
+                return Err(format!(
+                    "Cannot place agent inside a closed Door at {:?}.",
+                    position
+                ));
+            }
+            CellType::Unknown => {
+                return Err(format!(
+                    "Cannot place agent inside an unknown cell at {:?}.",
+                    position
+                ));
+            }
+            CellType::Void => {
+                return Err(format!("Cannot place agent in void at {:?}.", position));
+            }
+            _ => {}
+        }
+
+        if self.agents.contains_key(&agent_id) {
+            return Err(format!("Agent ID {} is already in use.", agent_id));
+        }
+
+        let agent_state = AgentState {
+            id: agent_id,
+            position,
+            inventory: initial_inventory.into(),
+            facing: AgentState::INITIAL_FACING,
+            speed: AgentState::default_speed(),
+            energy: self.starting_energy,
+        };
+
+        self.agent_locations[position] = Some(agent_id);
+        self.agents.insert(agent_id, agent_state.clone());
+        self.agent_behaviors.insert(agent_id, behavior);
+
+        self.next_entity_id = self.next_entity_id.max(agent_id + 1);
+
+        Ok(agent_id)
+    }
+
+    /// Adds a patrolling enemy, such as a `PatrolAgent`, that ends the run when it catches
+    /// another agent.
+    pub fn add_patroller(
+        &mut self,
+        position: Position,
+        behavior: Box<dyn Agent>,
+    ) -> Result<EntityId, String> {
+        let agent_id = self.add_agent(position, behavior, vec![])?;
+        self.patrollers.insert(agent_id);
+        Ok(agent_id)
+    }
+
+    /// Adds a ghost, such as a `GhostAgent` replaying a recorded run, that is only there to
+    /// be watched: it can share cells with other agents and passes through everything.
+    pub fn add_ghost(
+        &mut self,
+        position: Position,
+        behavior: Box<dyn Agent>,
+    ) -> Result<EntityId, String> {
+        let agent_id = behavior.id();
+        if !self.terrain.is_valid(position.x, position.y) {
+            return Err(format!("Position {:?} is out of bounds.", position));
+        }
+        if self.agents.contains_key(&agent_id) {
+            return Err(format!("Agent ID {} is already in use.", agent_id));
+        }
+
+        self.agents.insert(
+            agent_id,
+            AgentState {
+                id: agent_id,
+                position,
+                inventory: Inventory::new(),
+                facing: AgentState::INITIAL_FACING,
+                speed: AgentState::default_speed(),
+                energy: self.starting_energy,
+            },
+        );
+        self.agent_behaviors.insert(agent_id, behavior);
+        self.ghosts.insert(agent_id);
+        self.next_entity_id = self.next_entity_id.max(agent_id + 1);
+        Ok(agent_id)
+    }
+
+    /// Processes one turn for all unfinished agents, in ascending order of their IDs.
+    ///
+    /// An agent reaching a goal is marked finished and leaves play, freeing the goal for
+    /// the others. Returns `Win` once every agent other than patrollers and ghosts has
+    /// finished, and
+    /// `Lose` as soon as any agent loses.
+    pub fn process_turn(&mut self) -> ActionResult {
+        if self.turns_remaining() == Some(0) {
+            return ActionResult::TimedOut;
+        }
+        if self.turn == 0 && self.initial_state.is_none() {
+            self.initial_state = Some(self.snapshot());
+        }
+
+        let mut agent_ids: Vec<EntityId> = self
+            .agents
+            .keys()
+            .filter(|id| !self.finished.contains_key(id))
+            .cloned()
+            .collect();
+        agent_ids.sort_unstable();
+
+        let mut result = ActionResult::Success;
+        for agent_id in agent_ids {
+            match self.step_agent(agent_id) {
+                ActionResult::Success => {}
+                ActionResult::Win => {
+                    // The run is won once the win condition is met, even if other agents
+                    // haven't finished
+                    if self.evaluate_win() == Some(ActionResult::Win) {
+                        result = ActionResult::Win;
+                        break;
+                    }
+                }
+                ActionResult::Lose => {
+                    result = ActionResult::Lose;
+                    break;
+                }
+                ActionResult::Failure(_) | ActionResult::TimedOut => {}
+            }
+        }
+        self.tick_door_timers();
+        self.tick_chip_respawns();
+        self.turn += 1;
+
+        if result == ActionResult::Success && self.turns_remaining() == Some(0) {
+            result = ActionResult::TimedOut;
+        }
+        result
+    }
+
+    /// Returns how many more turns may be processed before the run times out, or `None`
+    /// if `max_turns` is not set.
+    pub fn turns_remaining(&self) -> Option<usize> {
+        self.max_turns
+            .map(|max_turns| max_turns.saturating_sub(self.turn))
+    }
+
+    /// Reports whether the run is won, lost or stuck by inspecting the current state,
+    /// without processing a turn.
+    ///
+    /// Losses that leave no trace in the state, such as a patroller catching an agent,
+    /// are only reported by `process_turn`.
+    pub fn game_status(&self) -> GameStatus {
+        match self.evaluate_win() {
+            Some(ActionResult::Win) => return GameStatus::Won,
+            Some(ActionResult::Lose | ActionResult::TimedOut) => return GameStatus::Lost,
+            _ => {}
+        }
+        let players: Vec<&AgentState> = self
+            .agents
+            .values()
+            .filter(|state| {
+                !self.patrollers.contains(&state.id) && !self.ghosts.contains(&state.id)
+            })
+            .collect();
+
+        let active: Vec<&AgentState> = players
+            .into_iter()
+            .filter(|state| !self.finished.contains_key(&state.id))
+            .collect();
+        if self.turns_remaining() == Some(0)
+            || active
+                .iter()
+                .any(|state| self.terrain[state.position] == CellType::Hazard)
+        {
+            return GameStatus::Lost;
+        }
+
+        // Reachability is checked generously, with every door passable
+        let keys_left = self.keys_remaining();
+        let targets: Vec<Position> = self
+            .items
+            .positions()
+            .filter(|position| match &self.items[*position] {
+                Some(Item::Goal) => {
+                    self.win_condition != WinCondition::AllKeysCollected || !keys_left
+                }
+                Some(Item::Key { .. }) => self.win_condition == WinCondition::AllKeysCollected,
+                _ => false,
+            })
+            .collect();
+        let all_keys: HashSet<DoorKeyType> = DoorKeyType::ALL.into_iter().collect();
+        let config = self.pathfinding_config();
+        let reachable = active.iter().any(|state| {
+            targets.iter().any(|target| {
+                find_path(
+                    &self.terrain,
+                    None,
+                    state.position,
+                    *target,
+                    &all_keys,
+                    usize::MAX,
+                    &config,
+                )
+                .is_some()
+            })
+        });
+        if !active.is_empty() && !reachable {
+            return GameStatus::Stalled;
+        }
+        GameStatus::InProgress
+    }
+
+    /// Bumps `terrain_version`, telling agents that cache plans the terrain changed.
+    pub fn touch_terrain(&mut self) {
+        self.terrain_version += 1;
+    }
+
+    /// Returns the name of the zone containing `position`, or `None` if it is outside
+    /// every zone. Where zones overlap the smallest wins, ties going to the first name.
+    pub fn zone_containing(&self, position: Position) -> Option<&str> {
+        self.zones
+            .iter()
+            .filter(|(_, (min, max))| {
+                (min.x..=max.x).contains(&position.x) && (min.y..=max.y).contains(&position.y)
+            })
+            .min_by_key(|(name, (min, max))| ((max.x - min.x + 1) * (max.y - min.y + 1), *name))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the finished agents and the number of turns each took, sorted by ID.
+    pub fn finish_turns(&self) -> Vec<(EntityId, usize)> {
+        let mut finish_turns: Vec<(EntityId, usize)> = self
+            .finished
+            .iter()
+            .map(|(id, turns)| (*id, *turns))
+            .collect();
+        finish_turns.sort_unstable();
+        finish_turns
+    }
+
+    /// Counts down auto-closing doors, closing each one whose timer has run out.
+    /// A door with an agent standing in it stays open until the cell is clear.
+    fn tick_door_timers(&mut self) {
+        let mut closing = Vec::new();
+        for (position, remaining) in self.door_timers.iter_mut() {
+            *remaining = remaining.saturating_sub(1);
+            if *remaining == 0 && self.agent_locations[*position].is_none() {
+                closing.push(*position);
+            }
+        }
+        for position in closing {
+            self.door_timers.remove(&position);
+            if let CellType::Door { open, .. } = &mut self.terrain[position] {
+                *open = false;
+                self.terrain_version += 1;
+            }
+        }
+    }
+
+    /// Counts down collected chips waiting to respawn, putting each one whose timer has run
+    /// out back on its cell. A chip waits while an agent stands on its cell and is dropped
+    /// if another item took the cell.
+    fn tick_chip_respawns(&mut self) {
+        let mut respawning = Vec::new();
+        for (position, (_, remaining)) in self.chip_respawns.iter_mut() {
+            *remaining = remaining.saturating_sub(1);
+            if *remaining == 0 && self.agent_locations[*position].is_none() {
+                respawning.push(*position);
+            }
+        }
+        for position in respawning {
+            if let Some((item, _)) = self.chip_respawns.remove(&position)
+                && self.items[position].is_none()
+            {
+                self.items[position] = Some(item);
+            }
+        }
+    }
+
+    /// Advances exactly one agent: asks its behavior for an action and processes it.
+    /// An agent reaching a goal is marked finished and leaves play; agents that already
+    /// finished are left alone and not asked for an action.
+    pub fn step_agent(&mut self, agent_id: EntityId) -> ActionResult {
+        if self.finished.contains_key(&agent_id) {
+            return ActionResult::Failure(ActionFailure::AgentFinished(agent_id));
+        }
+        // Clone agent state to avoid borrowing issues when calling get_action & process_action
+        let Some(agent_state) = self.agents.get(&agent_id).cloned() else {
+            return ActionResult::Failure(ActionFailure::AgentNotFound(agent_id));
+        };
+        let usable_keys = self.usable_keys(agent_id);
+        let next_chip_order = self.next_chip_order();
+        // Get mutable access to behavior
+        let Some(behavior) = self.agent_behaviors.get_mut(&agent_id) else {
+            return ActionResult::Failure(ActionFailure::NoBehavior(agent_id));
+        };
+
+        // Construct the view using the cloned state
+        let view = EnvironmentView {
+            agent_state: &agent_state, // Pass reference to cloned state
+            location: agent_state.position,
+            turn: self.turn,
+            terrain_grid: &self.terrain,
+            item_grid: &self.items,
+            agent_location_grid: &self.agent_locations,
+            patrollers: &self.patrollers,
+            key_hierarchy: &self.key_hierarchy,
+            blackboard: &self.blackboard,
+            wrap_edges: self.wrap_edges,
+            terrain_version: self.terrain_version,
+            usable_keys,
+            next_chip_order,
+        };
+        // Get action from agent, then let it post to the blackboard
+        let started = self.decision_timings.as_ref().map(|_| Instant::now());
+        let action = behavior.get_action(&view);
+        if let (Some(started), Some(timings)) = (started, &mut self.decision_timings) {
+            timings
+                .entry(agent_id)
+                .or_default()
+                .record(started.elapsed());
+        }
+        self.blackboard.turn = self.turn;
+        behavior.write_blackboard(&mut self.blackboard);
+        let (result, reached_goal) = self.carry_out(agent_id, action);
+        if reached_goal {
+            if let Some(agent_state) = self.agents.get(&agent_id) {
+                self.agent_locations[agent_state.position] = None;
+            }
+            self.finished.insert(agent_id, self.turn + 1);
+        }
+        result
+    }
+
+    /// Processes a single action for a given agent. If the action was carried out without
+    /// already ending the run, `evaluate_win` decides whether it does; failed actions are
+    /// always reported as failures.
+    pub fn process_action(&mut self, agent_id: EntityId, action: Action) -> ActionResult {
+        self.carry_out(agent_id, action).0
+    }
+
+    /// Processes an action like `process_action`, also returning whether the agent won by
+    /// reaching a goal itself rather than `evaluate_win` ending the run.
+    fn carry_out(&mut self, agent_id: EntityId, action: Action) -> (ActionResult, bool) {
+        let mut result = self.execute_action(agent_id, action);
+        let reached_goal = result == ActionResult::Win;
+        if result == ActionResult::Success
+            && let Some(outcome) = self.evaluate_win()
+        {
+            result = outcome;
+        }
+        if let Some(history) = &mut self.action_history {
+            history.record(agent_id, (self.turn, action, result.clone()));
+        }
+        (result, reached_goal)
+    }
+
+    /// Asks `win_evaluator`, or `win_condition` if none is set, whether the run has ended.
+    pub fn evaluate_win(&self) -> Option<ActionResult> {
+        match &self.win_evaluator {
+            Some(evaluator) => evaluator.evaluate(self),
+            None => self.win_condition.evaluate(self),
+        }
+    }
+
+    /// Resolves and carries out an action, returning its result.
+    fn execute_action(&mut self, agent_id: EntityId, action: Action) -> ActionResult {
+        if let Action::Dash { dx, dy } = action {
+            let Offset { dx, dy } = Offset { dx, dy }.signum();
+            let speed = self.agents.get(&agent_id).map_or(1, |state| state.speed);
+            let mut result = self.execute_action(agent_id, Action::Move { dx, dy });
+            for _ in 1..speed {
+                if result != ActionResult::Success {
+                    break;
+                }
+                match self.execute_action(agent_id, Action::Move { dx, dy }) {
+                    // Blocked, the dash ends on the last cell reached
+                    ActionResult::Failure(_) => break,
+                    next => result = next,
+                }
+            }
+            return result;
+        }
+        if let Action::Drop { dx, dy } = action
+            && let Ok(target) = self.resolve_drop(agent_id, dx, dy)
+        {
+            self.apply_drop(agent_id, target);
+            return ActionResult::Success;
+        }
+        if let Action::Interact { dx, dy } = action
+            && let Ok((door, key)) = self.resolve_interact(agent_id, dx, dy)
+        {
+            self.apply_interact(agent_id, door, key);
+            return ActionResult::Success;
+        }
+        let (result, outcome) = self.resolve_action(agent_id, action);
+        if let ActionResult::Failure(reason) = &result {
+            log_debug!("Agent {} action {:?} failed: {}", agent_id, action, reason);
+        }
+        if let Some(outcome) = outcome {
+            log_trace!(
+                "Agent {} moved from {:?} to {:?}",
+                agent_id,
+                outcome.from,
+                outcome.to
+            );
+            self.apply_move(agent_id, outcome);
+
+            if result == ActionResult::Success
+                && self.exhaustion_ends_run
+                && self
+                    .agents
+                    .get(&agent_id)
+                    .is_some_and(AgentState::is_exhausted)
+            {
+                return ActionResult::Lose;
+            }
+        }
+        result
+    }
+
+    /// Returns true if any key is still lying on the map.
+    fn keys_remaining(&self) -> bool {
+        self.items
+            .iter()
+            .any(|item| matches!(item, Some(Item::Key { .. })))
+    }
+
+    /// Computes the result `process_action` would return for the given action, without
+    /// modifying terrain, items, or agent positions. The action is carried out on a copy
+    /// of the environment, so every step of a dash, running out of energy and
+    /// `evaluate_win` are taken into account.
+    pub fn preview_action(&self, agent_id: EntityId, action: Action) -> ActionResult {
+        let mut preview = self.clone();
+        preview.action_history = None;
+        preview.process_action(agent_id, action)
+    }
+
+    /// Decides the outcome of an action against the current state without mutating it.
+    /// Returns the result and, if the agent moves, the changes needed to carry out the move.
+    fn resolve_action(
+        &self,
+        agent_id: EntityId,
+        action: Action,
+    ) -> (ActionResult, Option<MoveOutcome>) {
+        let agent_state = match self.agents.get(&agent_id) {
+            Some(state) => state,
+            None => {
+                return (
+                    ActionResult::Failure(ActionFailure::AgentNotFound(agent_id)),
+                    None,
+                );
+            }
+        };
+
+        let (dx, dy) = match action {
+            Action::Wait => return (ActionResult::Success, None),
+            Action::Drop { dx, dy } => {
+                let result = match self.resolve_drop(agent_id, dx, dy) {
+                    Ok(_) => ActionResult::Success,
+                    Err(reason) => ActionResult::Failure(reason),
+                };
+                return (result, None);
+            }
+            Action::Interact { dx, dy } => {
+                let result = match self.resolve_interact(agent_id, dx, dy) {
+                    Ok(_) => ActionResult::Success,
+                    Err(reason) => ActionResult::Failure(reason),
+                };
+                return (result, None);
+            }
+            Action::Move { dx, dy } => (dx, dy),
+            // Dashes are carried out one move at a time by `execute_action`
+            Action::Dash { dx, dy } => {
+                let Offset { dx, dy } = Offset { dx, dy }.signum();
+                (dx, dy)
+            }
+            Action::MoveToAdjacent { target } => {
+                match self
+                    .terrain
+                    .step_between(agent_state.position, target, self.wrap_edges)
+                {
+                    Some(Offset { dx, dy }) if (dx, dy) != (0, 0) => (dx, dy),
+                    _ => {
+                        return (
+                            ActionResult::Failure(ActionFailure::NotAdjacent(target)),
+                            None,
+                        );
+                    }
+                }
+            }
+            Action::MoveTo { target } => {
+                if target == agent_state.position {
+                    return (ActionResult::Success, None);
+                }
+                let path = find_path(
+                    &self.terrain,
+                    Some(&self.agent_locations),
+                    agent_state.position,
+                    target,
+                    &self
+                        .key_hierarchy
+                        .openable_doors(&self.usable_keys(agent_id)),
+                    agent_state.spendable_chips(),
+                    &self.pathfinding_config(),
+                );
+                let path = path.filter(|path| agent_state.can_afford_path(path));
+                match path.as_deref().and_then(|path| match path {
+                    [from, next, ..] => self.terrain.step_between(*from, *next, self.wrap_edges),
+                    _ => None,
+                }) {
+                    Some(Offset { dx, dy }) => (dx, dy),
+                    None => {
+                        return (
+                            ActionResult::Failure(ActionFailure::Unreachable(target)),
+                            None,
+                        );
+                    }
+                }
+            }
+        };
+
+        if agent_state.is_exhausted() {
+            return (ActionResult::Failure(ActionFailure::OutOfEnergy), None);
+        }
+        let current_pos = agent_state.position;
+        // Calculate target position, wrapping around the edges if enabled
+        let Some(target_pos) = self.terrain.step(current_pos, dx, dy, self.wrap_edges) else {
+            return (ActionResult::Failure(ActionFailure::OutOfBounds), None);
+        };
+        let mut outcome = MoveOutcome {
+            from: current_pos,
+            to: target_pos,
+            pick_up: false,
+            open_door: false,
+            consume_key: None,
+            spend_chips: 0,
+        };
+        if self.ghosts.contains(&agent_id) {
+            return (ActionResult::Success, Some(outcome));
+        }
+
+        let keys = self
+            .key_hierarchy
+            .openable_doors(&self.usable_keys(agent_id));
+        if let Some(failure) =
+            self.terrain[current_pos].entry_failure(&self.terrain[target_pos], dx, dy, &keys)
+        {
+            return (ActionResult::Failure(failure), None);
+        }
+
+        // A patroller and another agent meeting on one cell ends the run
+        let patrolling = self.patrollers.contains(&agent_id);
+        if let Some(occupant) = self.agent_locations[target_pos]
+            && patrolling != self.patrollers.contains(&occupant)
+        {
+            return (ActionResult::Lose, None);
+        }
+
+        // Check target cell for items
+        match &self.items[target_pos] {
+            // Patrollers walk over items without interacting with them
+            _ if patrolling => {}
+            Some(Item::Goal)
+                if self
+                    .win_condition
+                    .wins_at_goal(agent_state.inventory.as_slice(), self.keys_remaining()) =>
+            {
+                // Goal found, goto then end game
+                return (ActionResult::Win, Some(outcome));
+            }
+            Some(Item::Chip) | Some(Item::BonusChip { .. }) => {
+                // Chip found, collect it and remove it from the grid
+                outcome.pick_up = true;
+            }
+            Some(Item::OrderedChip { order }) => {
+                // Ordered chips stay on the grid until every earlier chip is collected
+                let expected = self.next_chip_order();
+                if *order != expected {
+                    return (
+                        ActionResult::Failure(ActionFailure::ChipOutOfOrder {
+                            expected,
+                            found: *order,
+                        }),
+                        None,
+                    );
+                }
+                outcome.pick_up = true;
+            }
+            Some(Item::Key { key_type: key }) => {
+                // Key found, pick it up only if agent doesn't have the key type already,
+                // unless duplicates are allowed or every key has to be collected
+                outcome.pick_up = self.allow_duplicate_keys
+                    || self.win_condition == WinCondition::AllKeysCollected
+                    || !agent_state.inventory.has_key(*key);
+            }
+            Some(Item::Goal) | None => {}
+        }
+
+        // Check target cell terrain and handle interactions (doors)
+        let occupied = self.agent_locations[target_pos].is_some();
+        let result = match &self.terrain[target_pos] {
+            CellType::Wall | CellType::Unknown | CellType::Void => {
+                unreachable!("impassable cells were rejected by entry_failure")
+            }
+            _ if occupied => ActionResult::Failure(ActionFailure::OccupiedByAgent),
+            CellType::Door {
+                open: false,
+                auto_open: false,
+                ..
+            } => ActionResult::Failure(ActionFailure::DoorClosed),
+            CellType::Door {
+                open: false,
+                cost: Some(cost),
+                ..
+            } if agent_state.spendable_chips() < *cost => {
+                ActionResult::Failure(ActionFailure::NotEnoughChips(*cost))
+            }
+            CellType::Door { open: true, .. } | CellType::Floor | CellType::OneWay { .. } => {
+                ActionResult::Success
+            }
+            CellType::Hazard if patrolling => ActionResult::Success,
+            CellType::Hazard => ActionResult::Lose,
+            CellType::Door {
+                open: false,
+                door_type: None,
+                cost,
+                ..
+            } => {
+                // Door is closed but needs no key (unlocked)
+                outcome.open_door = true;
+                outcome.spend_chips = cost.unwrap_or(0);
+                ActionResult::Success
+            }
+            CellType::Door {
+                open: false,
+                door_type: Some(required_type),
+                cost,
+                ..
+            } => {
+                // Door is closed and requires a specific key type. Use a matching key if
+                // possible, otherwise any higher key that can open it.
+                match self.door_key(agent_id, *required_type) {
+                    Some(key) => {
+                        // Agent (or a teammate) has the key: Consume it, open door, move.
+                        outcome.open_door = true;
+                        outcome.consume_key = Some(key);
+                        outcome.spend_chips = cost.unwrap_or(0);
+                        ActionResult::Success
+                    }
+                    // Agent lacks the required key type
+                    None => ActionResult::Failure(ActionFailure::MissingKey(*required_type)),
+                }
+            }
+        };
+
+        match result {
+            ActionResult::Success | ActionResult::Lose => (result, Some(outcome)),
+            _ => (result, None),
+        }
+    }
+
+    /// Picks the key agent `agent_id` opens a door requiring `required_type` with, and the
+    /// agent holding it. The agent's own keys come first, then in `shared_inventory` mode
+    /// those of the other agents by ID.
+    fn door_key(
+        &self,
+        agent_id: EntityId,
+        required_type: DoorKeyType,
+    ) -> Option<(EntityId, DoorKeyType)> {
+        let own = self.agents.get(&agent_id).and_then(|agent_state| {
+            self.inventory_key(&agent_state.inventory, required_type)
+                .map(|key_type| (agent_id, key_type))
+        });
+        if own.is_some() || !self.shared_inventory {
+            return own;
+        }
+        let mut holders: Vec<&AgentState> = self
+            .agents
+            .values()
+            .filter(|agent_state| agent_state.id != agent_id)
+            .collect();
+        holders.sort_by_key(|agent_state| agent_state.id);
+        holders.into_iter().find_map(|agent_state| {
+            self.inventory_key(&agent_state.inventory, required_type)
+                .map(|key_type| (agent_state.id, key_type))
+        })
+    }
+
+    /// Picks the key in `inventory` to open a door requiring `required_type`: a matching
+    /// key if there is one, otherwise any higher key that can open it.
+    fn inventory_key(
+        &self,
+        inventory: &Inventory,
+        required_type: DoorKeyType,
+    ) -> Option<DoorKeyType> {
+        Some(required_type)
+            .filter(|key_type| inventory.has_key(*key_type))
+            .or_else(|| {
+                inventory
+                    .keys()
+                    .find(|key_type| self.key_hierarchy.can_open(*key_type, required_type))
+            })
+    }
+
+    /// Checks whether agent `agent_id` can open the closed door on its neighbor at
+    /// `(dx, dy)`. Returns the door's position and the key used and its holder, if it
+    /// needs one.
+    fn resolve_interact(
+        &self,
+        agent_id: EntityId,
+        dx: isize,
+        dy: isize,
+    ) -> Result<(Position, Option<(EntityId, DoorKeyType)>), ActionFailure> {
+        let agent_state = self
+            .agents
+            .get(&agent_id)
+            .ok_or(ActionFailure::AgentNotFound(agent_id))?;
+        let target = self
+            .terrain
+            .step(agent_state.position, dx, dy, self.wrap_edges)
+            .ok_or(ActionFailure::OutOfBounds)?;
+        if (dx, dy) == (0, 0) || dx.abs() > 1 || dy.abs() > 1 {
+            return Err(ActionFailure::NotAdjacent(target));
+        }
+        let CellType::Door {
+            open: false,
+            door_type,
+            cost,
+            ..
+        } = &self.terrain[target]
+        else {
+            return Err(ActionFailure::NothingToInteract);
+        };
+        if let Some(cost) = cost
+            && agent_state.spendable_chips() < *cost
+        {
+            return Err(ActionFailure::NotEnoughChips(*cost));
+        }
+        match door_type {
+            Some(required_type) => self
+                .door_key(agent_id, *required_type)
+                .map(|key| (target, Some(key)))
+                .ok_or(ActionFailure::MissingKey(*required_type)),
+            None => Ok((target, None)),
+        }
+    }
+
+    /// Opens the door at `door` for agent `agent_id`, using up `key` from its holder's
+    /// inventory and the door's chip cost from the agent's.
+    fn apply_interact(
+        &mut self,
+        agent_id: EntityId,
+        door: Position,
+        key: Option<(EntityId, DoorKeyType)>,
+    ) {
+        let cost = match &self.terrain[door] {
+            CellType::Door { cost, .. } => cost.unwrap_or(0),
+            _ => return,
+        };
+        if let Some((holder, key_type)) = key {
+            self.take_key(holder, key_type);
+        }
+        let Some(agent_state) = self.agents.get_mut(&agent_id) else {
+            return;
+        };
+        for _ in 0..cost {
+            agent_state.inventory.remove(&Item::Chip);
+        }
+        self.open_door(door);
+    }
+
+    /// Removes a key of `key_type` from agent `holder`'s inventory.
+    fn take_key(&mut self, holder: EntityId, key_type: DoorKeyType) {
+        if let Some(agent_state) = self.agents.get_mut(&holder) {
+            agent_state.inventory.take_key(key_type);
+        }
+    }
+
+    /// Opens the door at `position`, starting its timer if it closes again by itself.
+    fn open_door(&mut self, position: Position) {
+        if let CellType::Door {
+            open,
+            auto_close_after,
+            ..
+        } = &mut self.terrain[position]
+        {
+            *open = true;
+            self.terrain_version += 1;
+            if let Some(turns) = *auto_close_after {
+                self.door_timers.insert(position, turns);
+            }
+        }
+    }
+
+    /// Checks whether agent `agent_id` can drop a chip onto its neighbor at `(dx, dy)`.
+    /// Returns the target position.
+    fn resolve_drop(
+        &self,
+        agent_id: EntityId,
+        dx: isize,
+        dy: isize,
+    ) -> Result<Position, ActionFailure> {
+        let agent_state = self
+            .agents
+            .get(&agent_id)
+            .ok_or(ActionFailure::AgentNotFound(agent_id))?;
+        if agent_state.spendable_chips() == 0 {
+            return Err(ActionFailure::NoChipToDrop);
+        }
+        let target = self
+            .terrain
+            .step(agent_state.position, dx, dy, self.wrap_edges)
+            .ok_or(ActionFailure::OutOfBounds)?;
+        if (dx, dy) == (0, 0) || dx.abs() > 1 || dy.abs() > 1 {
+            return Err(ActionFailure::NotAdjacent(target));
+        }
+        if self.agent_locations[target].is_some() {
+            return Err(ActionFailure::OccupiedByAgent);
+        }
+        match (&self.terrain[target], &self.items[target]) {
+            (CellType::Hazard, _) | (CellType::Floor, None) => Ok(target),
+            _ => Err(ActionFailure::CannotDropHere),
+        }
+    }
+
+    /// Drops one of the agent's chips onto `target`, bridging a hazard or leaving the
+    /// chip on the floor.
+    fn apply_drop(&mut self, agent_id: EntityId, target: Position) {
+        let Some(agent_state) = self.agents.get_mut(&agent_id) else {
+            return;
+        };
+        if !agent_state.inventory.remove(&Item::Chip) {
+            return;
+        }
+        if self.terrain[target] == CellType::Hazard {
+            log_trace!("Agent {} bridged the hazard at {:?}", agent_id, target);
+            self.terrain[target] = CellType::Floor;
+            self.terrain_version += 1;
+        } else {
+            self.items[target] = Some(Item::Chip);
+        }
+    }
+
+    /// Carries out a move previously decided by `resolve_action`.
+    fn apply_move(&mut self, agent_id: EntityId, outcome: MoveOutcome) {
+        if outcome.open_door && !self.ghosts.contains(&agent_id) {
+            self.open_door(outcome.to);
+            if let Some((holder, key_type)) = outcome.consume_key {
+                self.take_key(holder, key_type);
+            }
+        }
+        let Some(agent_state) = self.agents.get_mut(&agent_id) else {
+            return;
+        };
+        let Offset { dx, dy } = self
+            .terrain
+            .step_between(outcome.from, outcome.to, self.wrap_edges)
+            .unwrap_or(outcome.to - outcome.from);
+        agent_state.facing = (dx, dy);
+        if let Some(energy) = &mut agent_state.energy {
+            *energy = energy.saturating_sub(1);
+        }
+        if self.ghosts.contains(&agent_id) {
+            // Ghosts leave no trace on the grids
+            agent_state.position = outcome.to;
+            return;
+        }
+
+        for _ in 0..outcome.spend_chips {
+            agent_state.inventory.remove(&Item::Chip);
+        }
+        if outcome.pick_up
+            && let Some(Some(item)) = self.items.replace(outcome.to, None)
+        {
+            match item {
+                Item::OrderedChip { .. } => self.ordered_chips_collected += 1,
+                Item::Key { .. } => self.keys_collected += 1,
+                Item::Chip | Item::BonusChip { .. } => {
+                    if let Some(energy) = &mut agent_state.energy {
+                        *energy = energy.saturating_add(self.chip_energy);
+                    }
+                    if let Some(delay) = self.chip_respawn_delay {
+                        self.chip_respawns
+                            .insert(outcome.to, (item.clone(), delay.max(1)));
+                    }
+                }
+                _ => {}
+            }
+            agent_state.inventory.add(item);
+        }
+
+        // Update agent position in grid and state; the target cell is known to be empty
+        self.agent_locations
+            .swap(outcome.from, outcome.to)
+            .expect("move positions were bounds checked by resolve_action");
+        agent_state.position = outcome.to;
+
+        if let Some(visit_counts) = &mut self.visit_counts {
+            visit_counts[outcome.to] += 1;
+        }
+    }
+
+    /// Starts counting agent visits per cell. Does nothing if tracking is already enabled.
+    pub fn enable_visit_tracking(&mut self) {
+        if self.visit_counts.is_none() {
+            self.visit_counts = Some(Grid::new(self.terrain.width(), self.terrain.height()));
+        }
+    }
+
+    /// Starts timing agent decisions. Does nothing if timing is already enabled.
+    pub fn enable_decision_timing(&mut self) {
+        if self.decision_timings.is_none() {
+            self.decision_timings = Some(HashMap::new());
+        }
+    }
+
+    /// Starts recording each agent's actions, keeping the most recent `limit` per agent.
+    /// Changes the limit if history is already enabled, dropping older records as needed.
+    pub fn enable_action_history(&mut self, limit: usize) {
+        let history = self
+            .action_history
+            .get_or_insert_with(ActionHistory::default);
+        history.limit = limit;
+        for records in history.records.values_mut() {
+            while records.len() > limit {
+                records.pop_front();
+            }
+        }
+    }
+
+    /// Returns the recorded actions of `agent_id`, oldest first, or `None` if history is
+    /// not enabled. Agents that have not acted yet have an empty history.
+    pub fn agent_history(&self, agent_id: EntityId) -> Option<impl Iterator<Item = &ActionRecord>> {
+        let history = self.action_history.as_ref()?;
+        Some(history.records.get(&agent_id).into_iter().flatten())
+    }
+
+    /// Returns how long each agent's decisions took, or `None` if timing is not enabled.
+    pub fn decision_timings(&self) -> Option<&HashMap<EntityId, DecisionTiming>> {
+        self.decision_timings.as_ref()
+    }
+
+    /// Returns the per-cell visit counts, or `None` if tracking is not enabled.
+    pub fn visit_counts(&self) -> Option<&Grid<usize>> {
+        self.visit_counts.as_ref()
+    }
+
+    pub fn terrain(&self) -> &Grid<CellType> {
+        &self.terrain
+    }
+    pub fn items(&self) -> &Grid<Option<Item>> {
+        &self.items
+    }
+    pub fn agent_locations(&self) -> &Grid<Option<EntityId>> {
+        &self.agent_locations
+    }
+    pub fn get_agent_state(&self, agent_id: EntityId) -> Option<&AgentState> {
+        self.agents.get(&agent_id)
+    }
+
+    /// Captures the current terrain, items, agent states and turn number.
+    pub fn snapshot(&self) -> EnvironmentSnapshot {
+        let mut agents: Vec<AgentState> = self.agents.values().cloned().collect();
+        agents.sort_by_key(|agent| agent.id);
+        let mut door_timers: Vec<(Position, usize)> = self
+            .door_timers
+            .iter()
+            .map(|(position, remaining)| (*position, *remaining))
+            .collect();
+        door_timers.sort_unstable();
+        let mut chip_respawns: Vec<(Position, Item, usize)> = self
+            .chip_respawns
+            .iter()
+            .map(|(position, (item, remaining))| (*position, item.clone(), *remaining))
+            .collect();
+        chip_respawns.sort_unstable_by_key(|(position, _, _)| *position);
+        EnvironmentSnapshot {
+            version: SNAPSHOT_VERSION,
+            turn: self.turn,
+            terrain: self.terrain.clone(),
+            items: self.items.clone(),
+            agents,
+            door_timers,
+            finished: self.finish_turns(),
+            ordered_chips_collected: self.ordered_chips_collected,
+            keys_collected: self.keys_collected,
+            chip_respawns,
+        }
+    }
+
+    /// Restores terrain, items, agent states, door timers and the turn counter from a
+    /// snapshot. Agent behaviors are kept but reset via `Agent::reset`.
+    pub fn reset(&mut self, snapshot: &EnvironmentSnapshot) {
+        self.terrain = snapshot.terrain.clone();
+        self.terrain_version += 1;
+        self.items = snapshot.items.clone();
+        self.turn = snapshot.turn;
+        self.door_timers = snapshot.door_timers.iter().copied().collect();
+        self.chip_respawns = snapshot
+            .chip_respawns
+            .iter()
+            .map(|(position, item, remaining)| (*position, (item.clone(), *remaining)))
+            .collect();
+        self.finished = snapshot.finished.iter().copied().collect();
+        self.ordered_chips_collected = snapshot.ordered_chips_collected;
+        self.keys_collected = snapshot.keys_collected;
+        self.blackboard.clear();
+
+        self.agent_locations = Grid::new(self.terrain.width(), self.terrain.height());
+        if self.visit_counts.is_some() {
+            self.visit_counts = Some(Grid::new(self.terrain.width(), self.terrain.height()));
+        }
+        if let Some(history) = &mut self.action_history {
+            history.records.clear();
+        }
+        self.agents.clear();
+        for agent_state in &snapshot.agents {
+            if !self.finished.contains_key(&agent_state.id)
+                && !self.ghosts.contains(&agent_state.id)
+                && let Some(cell) = self
+                    .agent_locations
+                    .get_mut(agent_state.position.x, agent_state.position.y)
+            {
+                *cell = Some(agent_state.id);
+            }
+            self.agents.insert(agent_state.id, agent_state.clone());
         }
-    }
 
-    /// Generates a unique entity ID for agents.
-    pub fn reserve_entity_id(&mut self) -> EntityId {
-        let id = self.next_entity_id;
-        self.next_entity_id += 1;
-        id
+        for behavior in self.agent_behaviors.values_mut() {
+            behavior.reset();
+        }
     }
 
-    /// Adds an item to the environment grid.
-    pub fn add_item(&mut self, position: Position, item: Item) -> Result<(), String> {
-        if !self.terrain.is_valid(position.x, position.y) {
-            return Err(format!("Position {:?} is out of bounds.", position));
+    /// Restores the state captured just before the first turn was processed.
+    /// Does nothing if no turn has been processed yet.
+    pub fn reset_to_initial(&mut self) {
+        if let Some(initial_state) = self.initial_state.take() {
+            self.reset(&initial_state);
+            self.initial_state = Some(initial_state);
         }
-        if self.items[position].is_some() {
-            return Err(format!("Position {:?} already contains an item.", position));
+    }
+
+    /// Returns the ID of the agent at the given position, if any.
+    pub fn agent_at(&self, position: Position) -> Option<EntityId> {
+        self.agent_locations
+            .get(position.x, position.y)
+            .copied()
+            .flatten()
+    }
+
+    /// Returns the IDs of all agents within the rectangle spanned by the two corners
+    /// (inclusive), in row-major order. Parts of the region outside the grid are ignored.
+    pub fn agents_in_region(&self, corner_a: Position, corner_b: Position) -> Vec<EntityId> {
+        let (min_x, max_x) = (corner_a.x.min(corner_b.x), corner_a.x.max(corner_b.x));
+        let (min_y, max_y) = (corner_a.y.min(corner_b.y), corner_a.y.max(corner_b.y));
+        let max_x = max_x.min(self.agent_locations.width().saturating_sub(1));
+        let max_y = max_y.min(self.agent_locations.height().saturating_sub(1));
+
+        (min_y..=max_y)
+            .flat_map(|y| (min_x..=max_x).map(move |x| Position { x, y }))
+            .filter_map(|position| self.agent_at(position))
+            .collect()
+    }
+
+    /// Returns the top-left and bottom-right corners of the smallest rectangle containing
+    /// every item, or `None` if there are no items.
+    pub fn items_bounding_box(&self) -> Option<(Position, Position)> {
+        bounding_box(
+            self.items
+                .enumerate()
+                .filter(|(_, item)| item.is_some())
+                .map(|((x, y), _)| Position { x, y }),
+        )
+    }
+
+    /// Returns the top-left and bottom-right corners of the smallest rectangle containing
+    /// every agent still on the map (including ghosts), or `None` if there are none.
+    pub fn agents_bounding_box(&self) -> Option<(Position, Position)> {
+        bounding_box(
+            self.agents
+                .values()
+                .filter(|agent| !self.finished.contains_key(&agent.id))
+                .map(|agent| agent.position),
+        )
+    }
+
+    /// Returns the position of the item matching `predicate` that is the fewest steps from
+    /// `from`, along with that number of steps. Distances are path lengths using the
+    /// default pathfinding configuration with no keys or chips, so walls and locked doors
+    /// are walked around. Other agents are not treated as obstacles. Ties go to the first
+    /// item in row-major order.
+    pub fn nearest_item(
+        &self,
+        from: Position,
+        predicate: impl Fn(&Item) -> bool,
+    ) -> Option<(Position, usize)> {
+        let candidates: Vec<Position> = self
+            .items
+            .enumerate()
+            .filter(|(_, item)| item.as_ref().is_some_and(&predicate))
+            .map(|((x, y), _)| Position { x, y })
+            .collect();
+        self.nearest_by_path(from, candidates, |position| {
+            let path = find_path(
+                &self.terrain,
+                None,
+                from,
+                position,
+                &HashSet::new(),
+                0,
+                &self.pathfinding_config(),
+            )?;
+            Some(path.len() - 1)
+        })
+    }
+
+    /// Returns the position and type of the door that is the fewest steps from `from`,
+    /// considering only open doors if `open` is true and closed ones otherwise. Distances
+    /// are path lengths as in `nearest_item`, with the door itself the only one that may be
+    /// passed using its key. Ties go to the first door in row-major order.
+    pub fn closest_door(
+        &self,
+        from: Position,
+        open: bool,
+    ) -> Option<(Position, Option<DoorKeyType>)> {
+        let candidates: Vec<Position> = self
+            .terrain
+            .enumerate()
+            .filter(|(_, cell)| matches!(cell, CellType::Door { open: is_open, .. } if *is_open == open))
+            .map(|((x, y), _)| Position { x, y })
+            .collect();
+        let (door, _) =
+            self.nearest_by_path(from, candidates, |door| self.steps_to_door(from, door))?;
+        match &self.terrain[door] {
+            CellType::Door { door_type, .. } => Some((door, *door_type)),
+            _ => None,
         }
-        if self.agent_locations[position].is_some() {
-            return Err(format!("Position {:?} is occupied by an agent.", position));
+    }
+
+    /// Returns the fewest steps from `from` onto the door at `door`: a keyless path to a
+    /// neighbor it can be entered from (with its own key), plus the step through.
+    fn steps_to_door(&self, from: Position, door: Position) -> Option<usize> {
+        if from == door {
+            return Some(0);
         }
-        match self.terrain[position] {
-            CellType::Wall => {
-                return Err(format!(
-                    "Cannot place item inside a Wall at {:?}.",
-                    position
-                ));
+        let keys = match &self.terrain[door] {
+            CellType::Door {
+                door_type: Some(door_type),
+                ..
+            } => self
+                .key_hierarchy
+                .openable_doors(&HashSet::from([*door_type])),
+            _ => HashSet::new(),
+        };
+        [(0, 1), (0, -1), (1, 0), (-1, 0)]
+            .into_iter()
+            .filter_map(|(dx, dy)| self.terrain.step(door, -dx, -dy, self.wrap_edges))
+            .filter(|neighbor| self.can_enter(*neighbor, door, &keys))
+            .filter_map(|neighbor| {
+                let path = find_path(
+                    &self.terrain,
+                    None,
+                    from,
+                    neighbor,
+                    &HashSet::new(),
+                    0,
+                    &self.pathfinding_config(),
+                )?;
+                Some(path.len())
+            })
+            .min()
+    }
+
+    /// Returns the candidate with the fewest steps from `from` according to `steps_to`,
+    /// along with that number of steps. `steps_to` must never return less than the
+    /// manhattan distance. Ties go to the first candidate in row-major order.
+    fn nearest_by_path(
+        &self,
+        from: Position,
+        mut candidates: Vec<Position>,
+        steps_to: impl Fn(Position) -> Option<usize>,
+    ) -> Option<(Position, usize)> {
+        // A path is never shorter than the manhattan distance, so once that reaches the
+        // best path length found the remaining candidates can be skipped
+        candidates.sort_by_key(|position| position.manhattan_distance(&from));
+
+        let mut nearest: Option<(Position, usize)> = None;
+        for position in candidates {
+            if nearest.is_some_and(|(_, steps)| position.manhattan_distance(&from) > steps) {
+                break;
+            }
+            let Some(steps) = steps_to(position) else {
+                continue;
+            };
+            if nearest.is_none_or(|(best, best_steps)| {
+                steps < best_steps
+                    || (steps == best_steps && (position.y, position.x) < (best.y, best.x))
+            }) {
+                nearest = Some((position, steps));
             }
-            _ => {}
         }
-        self.items[position] = Some(item);
-        Ok(())
+        nearest
     }
 
-    /// Adds an agent to the environment.
-    pub fn add_agent(
-        &mut self,
-        position: Position,
-        behavior: Box<dyn Agent>,
-        initial_inventory: Vec<Item>,
-    ) -> Result<EntityId, String> {
-        let agent_id = behavior.id();
+    /// Suggests what agent `agent_id` should do next, following the same reasoning as
+    /// `PlanningAgent`: collect the nearest reachable chip, or head for the goal once no
+    /// chips are left, and otherwise fetch the nearest reachable key it can't use yet. If
+    /// a closed door lies on the way, opening it is suggested first. Returns `None` if the
+    /// agent doesn't exist or nothing useful is reachable.
+    pub fn next_hint(&self, agent_id: EntityId) -> Option<Hint> {
+        let agent_state = self.agents.get(&agent_id)?;
+        let from = agent_state.position;
+        // Shortest path to an item matching the predicate, ties going to row-major order
+        let nearest = |predicate: &dyn Fn(&Item) -> bool| {
+            self.items
+                .enumerate()
+                .filter(|(_, item)| item.as_ref().is_some_and(predicate))
+                .filter_map(|((x, y), _)| self.find_path(from, Position { x, y }, agent_id))
+                .min_by_key(|path| path.len())
+        };
+        let hint_along = |path: Vec<Position>, hint: Hint| {
+            let door = path.iter().skip(1).find(|position| {
+                matches!(self.terrain[**position], CellType::Door { open: false, .. })
+            });
+            match door {
+                Some(door) => Hint::OpenDoor(*door),
+                None => hint,
+            }
+        };
 
-        if !self.terrain.is_valid(position.x, position.y) {
-            return Err(format!("Position {:?} is out of bounds.", position));
-        }
-        if self.agent_locations[position].is_some() {
-            return Err(format!(
-                "Position {:?} is already occupied by an agent.",
-                position
-            ));
-        }
-        if self.items[position].is_some() {
-            eprintln!(
-                "Warning: Placing agent {} on top of item at {:?}",
-                agent_id, position
-            );
+        let next_chip_order = self.next_chip_order();
+        let is_chip = |item: &Item| match item {
+            Item::Chip | Item::BonusChip { .. } => true,
+            Item::OrderedChip { order } => *order == next_chip_order,
+            _ => false,
+        };
+        let chips_left = self.items.iter().flatten().any(is_chip);
+        let objective = if chips_left {
+            nearest(&is_chip).and_then(|path| Some((Hint::GoToChip(*path.last()?), path)))
+        } else {
+            nearest(&|item| *item == Item::Goal)
+                .and_then(|path| Some((Hint::GoToGoal(*path.last()?), path)))
+        };
+        if let Some((hint, path)) = objective {
+            return Some(hint_along(path, hint));
         }
-        match self.terrain[position] {
-            CellType::Wall => {
-                return Err(format!(
-                    "Cannot place agent inside a Wall at {:?}.",
-                    position
-                ));
+
+        let keys_held = self
+            .key_hierarchy
+            .openable_doors(&self.usable_keys(agent_id));
+        let path = nearest(
+            &|item| matches!(item, Item::Key { key_type } if !keys_held.contains(key_type)),
+        )?;
+        let key_position = *path.last()?;
+        let Some(Item::Key { key_type }) = self.items[key_position] else {
+            return None;
+        };
+        Some(hint_along(path, Hint::GoToKey(key_type, key_position)))
+    }
+
+    /// Returns the shortest path from agent `agent_id` to the nearest item it would
+    /// interact with: a chip, a goal, or a key of a type it doesn't hold yet. Paths lead
+    /// through doors the agent can open, as with `find_path`. Returns `None` if the agent
+    /// doesn't exist or can't reach any such item.
+    pub fn path_to_nearest_objective(&self, agent_id: EntityId) -> Option<Vec<Position>> {
+        let agent_state = self.agents.get(&agent_id)?;
+        let from = agent_state.position;
+        let keys_held = self.usable_keys(agent_id);
+
+        let mut candidates: Vec<Position> = self
+            .items
+            .enumerate()
+            .filter(|(_, item)| match item {
+                Some(Item::Key { key_type }) => !keys_held.contains(key_type),
+                Some(_) => true,
+                None => false,
+            })
+            .map(|((x, y), _)| Position { x, y })
+            .collect();
+        candidates.sort_by_key(|position| position.manhattan_distance(&from));
+
+        let mut nearest: Option<Vec<Position>> = None;
+        for position in candidates {
+            if nearest
+                .as_ref()
+                .is_some_and(|path| position.manhattan_distance(&from) >= path.len())
+            {
+                break;
             }
-            CellType::Door { open: false, .. } => {
-                return Err(format!(
-                    "Cannot place agent inside a closed Door at {:?}.",
-                    position
-                ));
+            if let Some(path) = self.find_path(from, position, agent_id)
+                && nearest.as_ref().is_none_or(|best| path.len() < best.len())
+            {
+                nearest = Some(path);
             }
-            _ => {}
         }
+        nearest
+    }
 
-        if self.agents.contains_key(&agent_id) {
-            return Err(format!("Agent ID {} is already in use.", agent_id));
+    /// Returns true if terrain allows an agent whose keys open the doors in `keys` to step
+    /// from `from` onto the adjacent position `to`. Uses the same rules as `process_action`
+    /// and the pathfinder (see `CellType::entry_failure`), so hazards count as enterable,
+    /// as do closed doors that first have to be opened with `Action::Interact`. Other
+    /// agents, items and chip door costs are ignored.
+    pub fn can_enter(&self, from: Position, to: Position, keys: &HashSet<DoorKeyType>) -> bool {
+        let (Some(from_cell), Some(to_cell)) = (
+            self.terrain.get(from.x, from.y),
+            self.terrain.get(to.x, to.y),
+        ) else {
+            return false;
+        };
+        match self.terrain.step_between(from, to, self.wrap_edges) {
+            Some(Offset { dx, dy }) if (dx, dy) != (0, 0) => {
+                from_cell.entry_failure(to_cell, dx, dy, keys).is_none()
+            }
+            _ => false,
         }
+    }
 
-        let agent_state = AgentState {
-            id: agent_id,
-            position,
-            inventory: initial_inventory,
-        };
+    /// Returns the order of the ordered chip that can be collected next.
+    pub fn next_chip_order(&self) -> usize {
+        self.ordered_chips_collected + 1
+    }
 
-        self.agent_locations[position] = Some(agent_id);
-        self.agents.insert(agent_id, agent_state.clone());
-        self.agent_behaviors.insert(agent_id, behavior);
+    /// Returns the key types agent `agent_id` can open doors with: its own and, in
+    /// `shared_inventory` mode, those held by every other agent.
+    pub fn usable_keys(&self, agent_id: EntityId) -> HashSet<DoorKeyType> {
+        if !self.shared_inventory {
+            return self
+                .agents
+                .get(&agent_id)
+                .map(AgentState::keys_held)
+                .unwrap_or_default();
+        }
+        self.agents
+            .values()
+            .flat_map(|agent_state| agent_state.inventory.keys())
+            .collect()
+    }
 
-        self.next_entity_id = self.next_entity_id.max(agent_id + 1);
+    /// Pathfinding configuration matching this environment's movement rules.
+    fn pathfinding_config(&self) -> PathfindingConfig {
+        PathfindingConfig {
+            wrap_edges: self.wrap_edges,
+            ..PathfindingConfig::default()
+        }
+    }
 
-        Ok(agent_id)
+    /// Finds a path from `from` to `to` through locked doors the agent `agent_id` holds a
+    /// key for, using the default pathfinding configuration. Other agents are not treated
+    /// as obstacles. Returns `None` if the agent doesn't exist, there is no path or the
+    /// agent lacks the energy to walk it.
+    pub fn find_path(
+        &self,
+        from: Position,
+        to: Position,
+        agent_id: EntityId,
+    ) -> Option<Vec<Position>> {
+        let agent_state = self.agents.get(&agent_id)?;
+        let keys_held = self
+            .key_hierarchy
+            .openable_doors(&self.usable_keys(agent_id));
+        find_path(
+            &self.terrain,
+            None,
+            from,
+            to,
+            &keys_held,
+            agent_state.spendable_chips(),
+            &self.pathfinding_config(),
+        )
+        .filter(|path| agent_state.can_afford_path(path))
     }
 
-    /// Processes one turn for all agents.
-    pub fn process_turn(&mut self) -> ActionResult {
-        let agent_ids: Vec<EntityId> = self.agents.keys().cloned().collect();
+    /// Returns every position reachable from `start` by walking through floors and doors
+    /// that are open, unlocked, or openable with one of the given `keys`.
+    /// Other agents are not treated as obstacles since they may move out of the way.
+    pub fn reachable_from(
+        &self,
+        start: Position,
+        keys: &HashSet<DoorKeyType>,
+    ) -> HashSet<Position> {
+        self.flood_fill(start, |_, cell| cell.is_passable_with(keys))
+    }
 
-        for agent_id in agent_ids {
-            // Clone agent state to avoid borrowing issues when calling get_action & process_action
-            if let Some(agent_state) = self.agents.get(&agent_id).cloned() {
-                // Get mutable access to behavior
-                if let Some(behavior) = self.agent_behaviors.get_mut(&agent_id) {
-                    // Construct the view using the cloned state
-                    let view = EnvironmentView {
-                        agent_state: &agent_state, // Pass reference to cloned state
-                        location: agent_state.position,
-                        terrain_grid: &self.terrain,
-                        item_grid: &self.items,
-                        agent_location_grid: &self.agent_locations,
-                    };
-                    // Get action from agent
-                    let action = behavior.get_action(&view);
-                    let result = self.process_action(agent_id, action);
-                    match result {
-                        ActionResult::Success => {}
-                        ActionResult::Win => {
-                            return ActionResult::Win;
-                        }
-                        ActionResult::Failure(_reason) => {
-                            // eprintln!("Agent {} action {:?} failed: {}", agent_id, action, reason);
-                        }
-                    }
+    /// Breadth-first search over cardinal moves from `start`, entering cells for which
+    /// `passable` returns true and respecting one-way passages.
+    fn flood_fill<F>(&self, start: Position, passable: F) -> HashSet<Position>
+    where
+        F: Fn(Position, &CellType) -> bool,
+    {
+        let mut reachable = HashSet::new();
+        if !self.terrain.is_valid(start.x, start.y) {
+            return reachable;
+        }
+
+        let mut frontier = VecDeque::new();
+        reachable.insert(start);
+        frontier.push_back(start);
+
+        while let Some(current) = frontier.pop_front() {
+            for (dx, dy) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+                let Some(neighbor) = self.terrain.step(current, dx, dy, self.wrap_edges) else {
+                    continue;
+                };
+                if !self.terrain[current].permits_direction(dx, dy) {
+                    continue;
+                }
+                let enterable = self
+                    .terrain
+                    .get(neighbor.x, neighbor.y)
+                    .is_some_and(|cell| passable(neighbor, cell) && cell.permits_direction(dx, dy));
+                if enterable && reachable.insert(neighbor) {
+                    frontier.push_back(neighbor);
                 }
             }
         }
-        ActionResult::Success
+
+        reachable
     }
 
-    /// Processes a single action for a given agent.
-    pub fn process_action(&mut self, agent_id: EntityId, action: Action) -> ActionResult {
-        // Get mutable access to the agent's state
-        let agent_state = match self.agents.get_mut(&agent_id) {
-            Some(state) => state,
-            None => return ActionResult::Failure(format!("Agent {} not found.", agent_id)),
+    /// Returns the fewest key pickups needed to walk from `start` to a goal, or `None` if
+    /// no goal can be reached within the search's state limit. Doors that can be bypassed
+    /// don't count, and since keys are consumed when opening a door, a key type needed
+    /// twice counts twice.
+    pub fn min_keys_required(&self, start: Position) -> Option<usize> {
+        let mut required = None;
+        self.search_key_states(start, &HashSet::new(), |region, pickups| {
+            if region.iter().any(|p| self.items[*p] == Some(Item::Goal)) {
+                required = Some(pickups);
+                return true;
+            }
+            false
+        });
+        required
+    }
+
+    /// Returns the goals that can be reached from `start` holding `keys`, including by
+    /// collecting and using keys found along the way, as far as the search's state limit
+    /// allows. Goals are sorted by position.
+    pub fn reachable_goals(&self, start: Position, keys: &HashSet<DoorKeyType>) -> Vec<Position> {
+        let mut goals = BTreeSet::new();
+        self.search_key_states(start, keys, |region, _| {
+            goals.extend(
+                region
+                    .iter()
+                    .filter(|p| self.items[**p] == Some(Item::Goal)),
+            );
+            false
+        });
+        goals.into_iter().collect()
+    }
+
+    /// Checks the map for likely authoring mistakes when starting from `start`: no goal
+    /// being reachable, and items stranded where no sequence of key pickups leads to them.
+    /// Items are reported in row-major order. Maps too complex to check fully get a single
+    /// `MapWarning::ValidationIncomplete` instead of warnings that may be wrong.
+    pub fn validate(&self, start: Position) -> Vec<MapWarning> {
+        let mut reachable = HashSet::new();
+        let complete = self.search_key_states(start, &HashSet::new(), |region, _| {
+            reachable.extend(region.iter().copied());
+            false
+        });
+
+        let mut warnings = Vec::new();
+        if !reachable
+            .iter()
+            .any(|position| self.items[*position] == Some(Item::Goal))
+        {
+            warnings.push(MapWarning::NoReachableGoal);
+        }
+        for ((x, y), item) in self.items.enumerate() {
+            let position = Position { x, y };
+            if let Some(item) = item
+                && !reachable.contains(&position)
+            {
+                warnings.push(MapWarning::UnreachableItem {
+                    position,
+                    item: item.clone(),
+                });
+            }
+        }
+        if !complete && !warnings.is_empty() {
+            return vec![MapWarning::ValidationIncomplete];
+        }
+        warnings
+    }
+
+    /// Explores every combination of key pickups and door openings available from `start`,
+    /// in order of increasing number of pickups.
+    ///
+    /// For each distinct state, `visit` is called with the region walkable without opening
+    /// further doors and the number of keys picked up so far; returning true stops the
+    /// search. Returns false if the search gave up after `MAX_KEY_SEARCH_STATES` states
+    /// with some left unexplored.
+    fn search_key_states<F>(
+        &self,
+        start: Position,
+        keys: &HashSet<DoorKeyType>,
+        mut visit: F,
+    ) -> bool
+    where
+        F: FnMut(&HashSet<Position>, usize) -> bool,
+    {
+        if !self.terrain.is_valid(start.x, start.y) {
+            return true;
+        }
+
+        let initial = KeySearchState {
+            position: start,
+            picked: BTreeSet::new(),
+            opened: BTreeSet::new(),
+            held: keys.iter().map(|key| (*key, 1)).collect(),
         };
+        // 0-1 BFS: opening a door is free, picking up a key costs one
+        let mut frontier = VecDeque::from([(initial, 0)]);
+        let mut settled = HashSet::new();
 
-        match action {
-            Action::Wait => ActionResult::Success,
-            Action::Move { dx, dy } => {
-                let current_pos = agent_state.position;
-                // Calculate target position
-                let target_x = current_pos.x.wrapping_add_signed(dx);
-                let target_y = current_pos.y.wrapping_add_signed(dy);
-
-                // Check bounds
-                if !self.terrain.is_valid(target_x, target_y) {
-                    return ActionResult::Failure("Target position is out of bounds.".to_string());
-                }
-                let target_pos = Position {
-                    x: target_x,
-                    y: target_y,
-                };
+        while let Some((state, pickups)) = frontier.pop_front() {
+            if settled.contains(&state) {
+                continue;
+            }
+            if settled.len() == MAX_KEY_SEARCH_STATES {
+                return false;
+            }
+            settled.insert(state.clone());
 
-                // Check target cell for items
-                if let Some(item_pos) = self.items.get_mut(target_x, target_y) {
-                    if let Some(item) = item_pos {
-                        match item {
-                            Item::Goal => {
-                                // Goal found, goto then end game
-                                self.agent_locations[current_pos] = None;
-                                self.agent_locations[target_pos] = Some(agent_id);
-                                agent_state.position = target_pos;
-                                return ActionResult::Win;
-                            }
-                            Item::Chip => {
-                                // Chip found, collect it and remove it from the grid
-                                agent_state.inventory.push(item.clone());
-                                self.items[target_pos] = None;
-                            }
-                            Item::Key { key_type: key } => {
-                                // Key found, check if agent has the key type
-                                let has_key = agent_state.inventory.iter().find(
-                                    |i| matches!(i, Item::Key { key_type } if *key_type == *key),
-                                );
-                                // do nothing if agent has the key already
-                                if has_key.is_none() {
-                                    // pick up key and remove it from the grid
-                                    agent_state.inventory.push(item.clone());
-                                    self.items[target_pos] = None;
-                                }
-                            }
-                        }
+            let region = self.flood_fill(state.position, |position, cell| {
+                state.opened.contains(&position) || cell.is_passable_with(&HashSet::new())
+            });
+            if visit(&region, pickups) {
+                return true;
+            }
+
+            for &position in &region {
+                // Keys are only picked up if the agent doesn't hold one of that type,
+                // unless duplicates are allowed
+                if let Some(Item::Key { key_type }) = self.items[position]
+                    && !state.picked.contains(&position)
+                    && (self.allow_duplicate_keys || !state.held.contains_key(&key_type))
+                {
+                    let mut next = state.clone();
+                    next.position = position;
+                    next.picked.insert(position);
+                    *next.held.entry(key_type).or_insert(0) += 1;
+                    if !settled.contains(&next) {
+                        frontier.push_back((next, pickups + 1));
                     }
                 }
 
-                // Check target cell terrain and handle interactions (doors)
-                match self.terrain.get(target_x, target_y).cloned() {
-                    Some(CellType::Wall) => {
-                        ActionResult::Failure("Cannot move into a wall.".to_string())
-                    }
-                    Some(CellType::Door { open: true, .. }) => {
-                        // Door is already open, check only for agent occupancy
-                        if self.agent_locations[target_pos].is_some() {
-                            ActionResult::Failure(
-                                "Target position is occupied by another agent.".to_string(),
-                            )
-                        } else {
-                            // Move succeeds: Update agent_locations and agent's state
-                            self.agent_locations[current_pos] = None;
-                            self.agent_locations[target_pos] = Some(agent_id);
-                            agent_state.position = target_pos; // Update the mutable agent state
-                            ActionResult::Success
-                        }
-                    }
-                    Some(CellType::Door {
+                for (dx, dy) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+                    let Some(door) = position.offset(dx, dy) else {
+                        continue;
+                    };
+                    let Some(CellType::Door {
                         open: false,
-                        door_type: None,
-                    }) => {
-                        // Door is closed but needs no key (unlocked)
-                        if self.agent_locations[target_pos].is_some() {
-                            ActionResult::Failure(
-                                "Target position is occupied by another agent.".to_string(),
-                            )
-                        } else {
-                            // Open the door in the grid and move
-                            if let Some(cell) = self.terrain.get_mut(target_x, target_y) {
-                                *cell = CellType::Door {
-                                    open: true,
-                                    door_type: None,
-                                };
-                            }
-
-                            // Update agent_locations and agent's state
-                            self.agent_locations[current_pos] = None;
-                            self.agent_locations[target_pos] = Some(agent_id);
-                            agent_state.position = target_pos;
-                            ActionResult::Success
-                        }
+                        door_type: Some(door_type),
+                        ..
+                    }) = self.terrain.get(door.x, door.y)
+                    else {
+                        continue;
+                    };
+                    if !self.terrain[position].permits_direction(dx, dy)
+                        || state.opened.contains(&door)
+                    {
+                        continue;
                     }
-                    Some(CellType::Door {
-                        open: false,
-                        door_type: Some(required_type),
-                    }) => {
-                        // Door is closed and requires a specific key type
-                        if self.agent_locations[target_pos].is_some() {
-                            ActionResult::Failure(
-                                "Target position is occupied by another agent.".to_string(),
-                            )
-                        } else {
-                            // Check if agent has the key type
-                            let key_index =
-                                agent_state.inventory.iter().position(|item| match item {
-                                    Item::Key { key_type } => *key_type == required_type, // Compare types
-                                    _ => false,
-                                });
-
-                            if let Some(index) = key_index {
-                                // Agent has the key: Consume it, open door, move.
-                                agent_state.inventory.remove(index); // Consume key from agent state
-
-                                // Update door state in the terrain grid
-                                if let Some(cell) = self.terrain.get_mut(target_x, target_y) {
-                                    *cell = CellType::Door {
-                                        open: true,
-                                        door_type: Some(required_type),
-                                    };
+                    // Any held key able to open the door may be the one spent on it
+                    for key in state.held.keys() {
+                        if self.key_hierarchy.can_open(*key, *door_type) {
+                            let mut next = state.clone();
+                            next.position = door;
+                            next.opened.insert(door);
+                            if let Some(count) = next.held.get_mut(key) {
+                                *count -= 1;
+                                if *count == 0 {
+                                    next.held.remove(key);
                                 }
-
-                                // Update agent position in grid and state
-                                self.agent_locations[current_pos] = None;
-                                self.agent_locations[target_pos] = Some(agent_id);
-                                agent_state.position = target_pos;
-                                ActionResult::Success
-                            } else {
-                                // Agent lacks the required key type
-                                ActionResult::Failure(format!(
-                                    "Agent lacks the required key type: {:?}.",
-                                    required_type
-                                ))
+                            }
+                            if !settled.contains(&next) {
+                                frontier.push_front((next, pickups));
                             }
                         }
                     }
-                    Some(CellType::Floor) => {
-                        if self.agent_locations[target_pos].is_some() {
-                            ActionResult::Failure(
-                                "Target position is occupied by another agent.".to_string(),
-                            )
-                        } else {
-                            // Move succeeds
-                            self.agent_locations[current_pos] = None;
-                            self.agent_locations[target_pos] = Some(agent_id);
-                            agent_state.position = target_pos;
-                            ActionResult::Success
-                        }
-                    }
-                    None => {
-                        ActionResult::Failure("Target cell not found (internal error).".to_string())
-                    }
                 }
             }
         }
-    }
-
-    pub fn terrain(&self) -> &Grid<CellType> {
-        &self.terrain
-    }
-    pub fn items(&self) -> &Grid<Option<Item>> {
-        &self.items
-    }
-    pub fn agent_locations(&self) -> &Grid<Option<EntityId>> {
-        &self.agent_locations
-    }
-    pub fn get_agent_state(&self, agent_id: EntityId) -> Option<&AgentState> {
-        self.agents.get(&agent_id)
+        true
     }
 
     /// Finds all positions of *closed* doors of a specific type.
@@ -389,22 +2811,31 @@ impl Environment {
                 CellType::Door {
                     open: false,
                     door_type,
+                    ..
                 } if *door_type == type_filter => Some(Position { x, y }),
                 _ => None,
             })
             .collect()
     }
 
-    /// Finds the location of the first occurrence of a specific key *type* on the ground.
-    pub fn get_key_location(&self, type_to_find: DoorKeyType) -> Option<Position> {
-        self.items
+    /// Lists every door, open or closed, as `(position, open, door_type)` in row-major order.
+    pub fn all_doors(&self) -> Vec<(Position, bool, Option<DoorKeyType>)> {
+        self.terrain
             .enumerate()
-            .find_map(|((x, y), item_option)| match item_option {
-                Some(Item::Key { key_type }) if *key_type == type_to_find => {
-                    Some(Position { x, y })
-                }
+            .filter_map(|((x, y), cell)| match cell {
+                CellType::Door {
+                    open, door_type, ..
+                } => Some((Position { x, y }, *open, *door_type)),
                 _ => None,
             })
+            .collect()
+    }
+
+    /// Finds the location of the first occurrence of a specific key *type* on the ground.
+    pub fn get_key_location(&self, type_to_find: DoorKeyType) -> Option<Position> {
+        self.items.find_position(
+            |item| matches!(item, Some(Item::Key { key_type }) if *key_type == type_to_find),
+        )
     }
 
     /// Given a door's position, finds the location of the corresponding key type on the ground.
@@ -424,37 +2855,262 @@ impl Environment {
     }
 }
 
-/// Loads an environment state from a string representation of a map.
-/// Uses DoorKeyType enum for keys/doors.
-pub fn load_environment_from_string(map_string: &str) -> Result<(Environment, Position), String> {
-    let lines: Vec<&str> = map_string.trim().lines().collect();
-    if lines.is_empty() {
-        return Err("Map string is empty.".to_string());
+/// Writes the environment's terrain and items as a map string that
+/// `load_environment_from_string` reads back, with `ST` at `start`. Agents are not saved.
+///
+/// Returns an error for cells no map code describes, such as open or auto-closing doors,
+/// unknown cells, items on anything but floor, or a start that isn't empty floor.
+pub fn save_environment_to_string(
+    environment: &Environment,
+    start: Position,
+) -> Result<String, String> {
+    let terrain = &environment.terrain;
+    if !terrain.is_valid(start.x, start.y) {
+        return Err(format!("Start position {:?} is out of bounds.", start));
     }
 
-    let height = lines.len();
-    let mut width = 0;
-    let mut parsed_rows: Vec<Vec<&str>> = Vec::with_capacity(height);
+    let mut rows = Vec::with_capacity(terrain.height());
+    for y in 0..terrain.height() {
+        let mut codes = Vec::with_capacity(terrain.width());
+        for x in 0..terrain.width() {
+            let position = Position { x, y };
+            let code = match (&terrain[position], &environment.items[position]) {
+                (CellType::Floor, None) if position == start => "ST".to_string(),
+                (_, _) if position == start => {
+                    return Err(format!("Start position {:?} is not empty floor.", start));
+                }
+                (cell, item) => map_code(cell, item.as_ref())
+                    .ok_or_else(|| format!("Cell at {:?} has no map code.", position))?,
+            };
+            codes.push(code);
+        }
+        rows.push(codes.join(" "));
+    }
+    let mut header = Vec::new();
+    if let Some(seed) = environment.seed {
+        header.push(format!("# seed: {}", seed));
+    }
+    let mut zones: Vec<_> = environment.zones.iter().collect();
+    zones.sort_unstable_by_key(|(name, _)| *name);
+    for (name, (min, max)) in zones {
+        header.push(format!(
+            "# zone: {} {} {} {} {}",
+            name, min.x, min.y, max.x, max.y
+        ));
+    }
+    header.extend(rows);
+    Ok(header.join("\n"))
+}
+
+/// Returns the map code for a cell and the item on it, as read by
+/// `load_environment_from_string`, or `None` if the map format can't express it (e.g. an
+/// open door, or an item on anything but floor). Never returns the start code `ST`.
+pub(crate) fn map_code(cell: &CellType, item: Option<&Item>) -> Option<String> {
+    let key_code = |key_type: DoorKeyType| match key_type {
+        DoorKeyType::Green => 'G',
+        DoorKeyType::Yellow => 'Y',
+        DoorKeyType::Blue => 'B',
+        DoorKeyType::Red => 'R',
+    };
+
+    let code = match (cell, item) {
+        (CellType::Floor, None) => "BL".to_string(),
+        (CellType::Floor, Some(Item::Goal)) => "PL".to_string(),
+        (CellType::Floor, Some(Item::Chip)) => "CH".to_string(),
+        (CellType::Floor, Some(Item::BonusChip { value })) if *value == BONUS_CHIP_VALUE => {
+            "CB".to_string()
+        }
+        (CellType::Floor, Some(Item::OrderedChip { order })) if (1..=9).contains(order) => {
+            format!("C{}", order)
+        }
+        (CellType::Floor, Some(Item::Key { key_type })) => format!("K{}", key_code(*key_type)),
+        (CellType::Wall, None) => "WL".to_string(),
+        (CellType::Hazard, None) => "HZ".to_string(),
+        (CellType::Void, None) => "..".to_string(),
+        (CellType::OneWay { direction }, None) => match direction {
+            (1, 0) => ">>".to_string(),
+            (-1, 0) => "<<".to_string(),
+            (0, -1) => "^^".to_string(),
+            (0, 1) => "vv".to_string(),
+            _ => return None,
+        },
+        (
+            CellType::Door {
+                open: false,
+                door_type: Some(key_type),
+                auto_close_after: None,
+                cost: None,
+                auto_open: true,
+            },
+            None,
+        ) => format!("D{}", key_code(*key_type)),
+        (
+            CellType::Door {
+                open: false,
+                door_type: None,
+                auto_close_after: None,
+                cost: Some(cost),
+                auto_open: true,
+            },
+            None,
+        ) => format!("${}", cost),
+        (
+            CellType::Door {
+                open: false,
+                door_type: None,
+                auto_close_after: None,
+                cost: None,
+                auto_open: false,
+            },
+            None,
+        ) => "DI".to_string(),
+        _ => return None,
+    };
+    Some(code)
+}
 
-    for (y, line) in lines.iter().enumerate() {
-        let tokens: Vec<&str> = line.trim().split_whitespace().collect();
+/// Returns the corners of the smallest rectangle containing all `positions`.
+fn bounding_box(positions: impl Iterator<Item = Position>) -> Option<(Position, Position)> {
+    positions.fold(None, |bounds, position| {
+        Some(match bounds {
+            None => (position, position),
+            Some((min, max)) => (
+                Position {
+                    x: min.x.min(position.x),
+                    y: min.y.min(position.y),
+                },
+                Position {
+                    x: max.x.max(position.x),
+                    y: max.y.max(position.y),
+                },
+            ),
+        })
+    })
+}
+
+/// Splits a map row into its tokens, separated by whitespace, commas, or a mix of both.
+fn map_row_tokens(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+}
+
+/// Returns the `(width, height)` of a map without parsing its cells, e.g. to show map
+/// sizes in a browser. Rejects empty maps and rows of differing widths with the same
+/// errors as `load_environment_from_string`, but does not check the tokens themselves.
+pub fn peek_map_dimensions(map_string: &str) -> Result<(usize, usize), String> {
+    let (_, body) = split_map_header(map_string);
+    let mut width = 0;
+    let mut height = 0;
+    for (y, line) in body.lines().enumerate() {
+        let tokens = map_row_tokens(line).count();
         if y == 0 {
-            width = tokens.len();
+            width = tokens;
             if width == 0 {
                 return Err("Map has zero width.".to_string());
             }
-        } else if tokens.len() != width {
+        } else if tokens != width {
             return Err(format!(
                 "Inconsistent width at row {}: expected {}, found {}",
-                y,
-                width,
-                tokens.len()
+                y, width, tokens
             ));
         }
-        parsed_rows.push(tokens);
+        height += 1;
+    }
+    if height == 0 {
+        return Err("Map string is empty.".to_string());
+    }
+    Ok((width, height))
+}
+
+/// Splits a map into its header, the lines at the top starting with `#`, and the rest.
+fn split_map_header(map_string: &str) -> (Vec<&str>, &str) {
+    let mut header = Vec::new();
+    let mut rest = map_string.trim();
+    while rest.starts_with('#') {
+        let (line, tail) = rest.split_once('\n').unwrap_or((rest, ""));
+        header.push(line);
+        rest = tail.trim_start();
+    }
+    (header, rest)
+}
+
+/// Reads the seed from a `# seed: <number>` header line, if there is one. Other header
+/// lines are comments.
+fn parse_map_seed(header: &[&str]) -> Result<Option<u64>, String> {
+    let mut seed = None;
+    for line in header {
+        if let Some(value) = line.trim_start_matches('#').trim().strip_prefix("seed:") {
+            let value = value.trim();
+            seed = Some(
+                value
+                    .parse()
+                    .map_err(|_| format!("Invalid seed '{}' in map header.", value))?,
+            );
+        }
+    }
+    Ok(seed)
+}
+
+/// Reads `# zone: <name> <x1> <y1> <x2> <y2>` header lines, each naming the rectangle
+/// with corners (x1, y1) and (x2, y2), which must lie within a `width` x `height` map.
+fn parse_map_zones(
+    header: &[&str],
+    (width, height): (usize, usize),
+) -> Result<HashMap<String, (Position, Position)>, String> {
+    let mut zones = HashMap::new();
+    for line in header {
+        let Some(value) = line.trim_start_matches('#').trim().strip_prefix("zone:") else {
+            continue;
+        };
+        let invalid = || format!("Invalid zone '{}' in map header.", value.trim());
+        let words: Vec<&str> = value.split_whitespace().collect();
+        let [name, coords @ ..] = words.as_slice() else {
+            return Err(invalid());
+        };
+        let coords: Vec<usize> = coords
+            .iter()
+            .map(|word| word.parse().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        let [x1, y1, x2, y2] = coords[..] else {
+            return Err(invalid());
+        };
+        if x1.max(x2) >= width || y1.max(y2) >= height {
+            return Err(format!("Zone '{}' extends outside the map.", name));
+        }
+        let corners = (
+            Position {
+                x: x1.min(x2),
+                y: y1.min(y2),
+            },
+            Position {
+                x: x1.max(x2),
+                y: y1.max(y2),
+            },
+        );
+        if zones.insert(name.to_string(), corners).is_some() {
+            return Err(format!("Zone '{}' is defined twice.", name));
+        }
     }
+    Ok(zones)
+}
+
+/// Loads an environment state from a string representation of a map.
+/// Uses DoorKeyType enum for keys/doors.
+/// Tokens may be separated by whitespace, commas, or a mix of both.
+/// The map may start with header lines beginning with `#`; `# seed: <number>` sets
+/// `Environment::seed`, `# zone: <name> <x1> <y1> <x2> <y2>` adds to
+/// `Environment::zones`, anything else is a comment.
+pub fn load_environment_from_string(map_string: &str) -> Result<(Environment, Position), String> {
+    let (width, height) = peek_map_dimensions(map_string)?;
+    let (header, body) = split_map_header(map_string);
+    let parsed_rows: Vec<Vec<&str>> = body
+        .lines()
+        .map(|line| map_row_tokens(line).collect())
+        .collect();
 
     let mut environment = Environment::new(width, height);
+    environment.seed = parse_map_seed(&header)?;
+    environment.zones = parse_map_zones(&header, (width, height))?;
     let mut start_position: Option<Position> = None;
 
     for (y, row_tokens) in parsed_rows.iter().enumerate() {
@@ -471,14 +3127,29 @@ pub fn load_environment_from_string(map_string: &str) -> Result<(Environment, Po
                 }
                 "BL" => (CellType::Floor, None),
                 "WL" | "WA" => (CellType::Wall, None),
+                "HZ" => (CellType::Hazard, None),
+                ".." | "--" => (CellType::Void, None),
+                ">>" => (CellType::OneWay { direction: (1, 0) }, None),
+                "<<" => (CellType::OneWay { direction: (-1, 0) }, None),
+                "^^" => (CellType::OneWay { direction: (0, -1) }, None),
+                "vv" => (CellType::OneWay { direction: (0, 1) }, None),
                 "DP" => (CellType::Floor, None), // Goal door is floor
                 "PL" => (CellType::Floor, Some(Item::Goal)),
                 "CH" => (CellType::Floor, Some(Item::Chip)),
+                "CB" => (
+                    CellType::Floor,
+                    Some(Item::BonusChip {
+                        value: BONUS_CHIP_VALUE,
+                    }),
+                ),
                 // Use DoorKeyType enum for doors
                 "DG" => (
                     CellType::Door {
                         open: false,
                         door_type: Some(DoorKeyType::Green),
+                        auto_close_after: None,
+                        cost: None,
+                        auto_open: true,
                     },
                     None,
                 ),
@@ -486,6 +3157,9 @@ pub fn load_environment_from_string(map_string: &str) -> Result<(Environment, Po
                     CellType::Door {
                         open: false,
                         door_type: Some(DoorKeyType::Yellow),
+                        auto_close_after: None,
+                        cost: None,
+                        auto_open: true,
                     },
                     None,
                 ),
@@ -493,6 +3167,9 @@ pub fn load_environment_from_string(map_string: &str) -> Result<(Environment, Po
                     CellType::Door {
                         open: false,
                         door_type: Some(DoorKeyType::Blue),
+                        auto_close_after: None,
+                        cost: None,
+                        auto_open: true,
                     },
                     None,
                 ),
@@ -500,6 +3177,20 @@ pub fn load_environment_from_string(map_string: &str) -> Result<(Environment, Po
                     CellType::Door {
                         open: false,
                         door_type: Some(DoorKeyType::Red),
+                        auto_close_after: None,
+                        cost: None,
+                        auto_open: true,
+                    },
+                    None,
+                ),
+                // Unlocked door opened only with `Action::Interact`
+                "DI" => (
+                    CellType::Door {
+                        open: false,
+                        door_type: None,
+                        auto_close_after: None,
+                        cost: None,
+                        auto_open: false,
                     },
                     None,
                 ),
@@ -528,6 +3219,30 @@ pub fn load_environment_from_string(map_string: &str) -> Result<(Environment, Po
                         key_type: DoorKeyType::Red,
                     }),
                 ),
+                // Unlocked door costing the given number of chips, e.g. `$2`
+                code if let Some(cost) = code
+                    .strip_prefix('$')
+                    .and_then(|cost| cost.parse::<usize>().ok()) =>
+                {
+                    (
+                        CellType::Door {
+                            open: false,
+                            door_type: None,
+                            auto_close_after: None,
+                            cost: Some(cost),
+                            auto_open: true,
+                        },
+                        None,
+                    )
+                }
+                // Chip collected in sequence, `C1` through `C9`
+                code if let Some(order) = code
+                    .strip_prefix('C')
+                    .and_then(|order| order.parse::<usize>().ok())
+                    .filter(|order| (1..=9).contains(order)) =>
+                {
+                    (CellType::Floor, Some(Item::OrderedChip { order }))
+                }
                 unknown => {
                     return Err(format!(
                         "Unknown map code '{}' at position ({}, {}).",
@@ -545,6 +3260,13 @@ pub fn load_environment_from_string(map_string: &str) -> Result<(Environment, Po
 
     let start_pos =
         start_position.ok_or_else(|| "No start position ('ST') found in map.".to_string())?;
+    if !environment
+        .items
+        .iter()
+        .any(|item| *item == Some(Item::Goal))
+    {
+        log_warn!("Map has no goal ('PL') and cannot be won.");
+    }
 
     Ok((environment, start_pos))
 }
@@ -0,0 +1,210 @@
+//! An agent whose decisions are made by another program, e.g. a learning agent written in
+//! another language, talking over the child process's stdin and stdout.
+//!
+//! Each turn the agent writes its observation to the program as lines of text:
+//!
+//! ```text
+//! turn <turn>
+//! agent <id> <x> <y>
+//! inventory <count> <item codes...>
+//! map <width> <height>
+//! <height rows of width map codes>
+//! end
+//! ```
+//!
+//! Items and cells use the codes of the map file format, with `??` for any the format
+//! can't express (e.g. open doors). The program answers with a single line, one of
+//! `wait`, `move <dx> <dy>`, `dash <dx> <dy>`, `moveto <x> <y>`, `adjacent <x> <y>`,
+//! `drop <dx> <dy>` or `interact <dx> <dy>`. Responses that are malformed or arrive too
+//! late are treated as `wait`.
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, RecvTimeoutError},
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    EntityId, Position,
+    agent::Agent,
+    environment::{Action, CellType, EnvironmentView, Observation, map_code},
+};
+
+/// Code written for cells and items the map format has no code for.
+const UNKNOWN_CODE: &str = "??";
+
+/// The running program and the lines it has written to stdout.
+#[derive(Debug)]
+struct ExternalProcess {
+    child: Child,
+    stdin: ChildStdin,
+    responses: Receiver<String>,
+}
+
+impl Drop for ExternalProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// An agent that asks an external program for each action, see the module docs for the
+/// protocol. Clones share the same program.
+#[derive(Debug, Clone)]
+pub struct ExternalAgent {
+    id: EntityId,
+    process: Arc<Mutex<ExternalProcess>>,
+    timeout: Duration,
+}
+
+impl ExternalAgent {
+    /// Starts `command` with piped stdin and stdout. Each turn the agent waits up to
+    /// `timeout` for the program's response.
+    pub fn spawn(id: EntityId, mut command: Command, timeout: Duration) -> io::Result<Self> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            let _ = child.kill();
+            return Err(io::Error::other("child process has no stdin or stdout"));
+        };
+
+        // Read responses on a separate thread so a silent program can be timed out
+        let (sender, responses) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            id,
+            process: Arc::new(Mutex::new(ExternalProcess {
+                child,
+                stdin,
+                responses,
+            })),
+            timeout,
+        })
+    }
+
+    /// Sends the observation and waits for the response line, or `None` if writing
+    /// failed, the program exited or the response timed out.
+    fn request(&self, observation: &Observation) -> Option<String> {
+        let mut process = self.process.lock().ok()?;
+        // Responses that arrived after an earlier timeout are stale
+        while process.responses.try_recv().is_ok() {}
+
+        let message = encode_observation(observation);
+        process.stdin.write_all(message.as_bytes()).ok()?;
+        process.stdin.flush().ok()?;
+        match process.responses.recv_timeout(self.timeout) {
+            Ok(line) => Some(line),
+            Err(RecvTimeoutError::Timeout) => {
+                log_warn!("External agent {} timed out", self.id);
+                None
+            }
+            Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+}
+
+impl Agent for ExternalAgent {
+    fn id(&self) -> EntityId {
+        self.id
+    }
+
+    fn get_action(&mut self, view: &EnvironmentView) -> Action {
+        let Some(response) = self.request(&view.to_observation(None)) else {
+            return Action::Wait;
+        };
+        parse_action(&response).unwrap_or_else(|| {
+            log_warn!(
+                "External agent {} sent a malformed action: {:?}",
+                self.id,
+                response
+            );
+            Action::Wait
+        })
+    }
+
+    fn box_clone(&self) -> Box<dyn Agent> {
+        Box::new(self.clone())
+    }
+}
+
+/// Writes an observation in the protocol's text form.
+fn encode_observation(observation: &Observation) -> String {
+    let code = |cell: &CellType, item| map_code(cell, item).unwrap_or(UNKNOWN_CODE.to_string());
+    let agent = &observation.agent;
+    let inventory: Vec<String> = agent
+        .inventory
+        .iter()
+        .map(|item| code(&CellType::Floor, Some(item)))
+        .collect();
+
+    let mut message = format!(
+        "turn {}\nagent {} {} {}\ninventory {}",
+        observation.turn,
+        agent.id,
+        agent.position.x,
+        agent.position.y,
+        inventory.len()
+    );
+    for item in &inventory {
+        message.push(' ');
+        message.push_str(item);
+    }
+    let terrain = &observation.terrain;
+    message.push_str(&format!("\nmap {} {}\n", terrain.width(), terrain.height()));
+    for (cells, items) in terrain.rows().zip(observation.items.rows()) {
+        let row: Vec<String> = cells
+            .iter()
+            .zip(items)
+            .map(|(cell, item)| code(cell, item.as_ref()))
+            .collect();
+        message.push_str(&row.join(" "));
+        message.push('\n');
+    }
+    message.push_str("end\n");
+    message
+}
+
+/// Parses a response line into an action, or `None` if it is malformed.
+fn parse_action(response: &str) -> Option<Action> {
+    let mut words = response.split_whitespace();
+    let command = words.next()?;
+    let numbers: Vec<isize> = words.map(|word| word.parse().ok()).collect::<Option<_>>()?;
+    let position = |x: isize, y: isize| {
+        Some(Position {
+            x: usize::try_from(x).ok()?,
+            y: usize::try_from(y).ok()?,
+        })
+    };
+    let action = match (command, numbers.as_slice()) {
+        ("wait", []) => Action::Wait,
+        ("move", [dx, dy]) => Action::Move { dx: *dx, dy: *dy },
+        ("dash", [dx, dy]) => Action::Dash { dx: *dx, dy: *dy },
+        ("moveto", [x, y]) => Action::MoveTo {
+            target: position(*x, *y)?,
+        },
+        ("adjacent", [x, y]) => Action::MoveToAdjacent {
+            target: position(*x, *y)?,
+        },
+        ("drop", [dx, dy]) => Action::Drop { dx: *dx, dy: *dy },
+        ("interact", [dx, dy]) => Action::Interact { dx: *dx, dy: *dy },
+        _ => return None,
+    };
+    Some(action)
+}
@@ -0,0 +1,144 @@
+//! The glyphs and colors cells are drawn with. Front ends such as the TUI and the SVG
+//! export share this mapping so a map looks the same everywhere.
+//!
+//! In symbol mode keys and locked doors additionally get glyphs of their own, so they can
+//! be told apart without color.
+
+use crate::{DoorKeyType, Item, environment::CellType};
+
+/// A color from the basic terminal palette, mapped by each front end to its own colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GlyphColor {
+    /// The front end's default foreground.
+    Default,
+    DarkGray,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+}
+
+/// The text drawn on a cell and how it is styled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glyph {
+    pub text: String,
+    pub color: GlyphColor,
+    pub bold: bool,
+    /// Drawn faintly, e.g. for ghosts that are only there to be watched.
+    pub dim: bool,
+}
+
+impl Glyph {
+    fn new(text: impl Into<String>, color: GlyphColor) -> Self {
+        Glyph {
+            text: text.into(),
+            color,
+            bold: false,
+            dim: false,
+        }
+    }
+
+    fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    fn dim(mut self) -> Self {
+        self.dim = true;
+        self
+    }
+}
+
+/// Returns the color used for keys and doors of a type.
+pub fn key_color(key_type: DoorKeyType) -> GlyphColor {
+    match key_type {
+        DoorKeyType::Red => GlyphColor::Red,
+        DoorKeyType::Blue => GlyphColor::Blue,
+        DoorKeyType::Yellow => GlyphColor::Yellow,
+        DoorKeyType::Green => GlyphColor::Green,
+    }
+}
+
+/// Returns the glyph of a key. In symbol mode each key type gets its own letter.
+pub fn key_glyph(key_type: DoorKeyType, symbols: bool) -> Glyph {
+    let text = if symbols {
+        match key_type {
+            DoorKeyType::Red => "R",
+            DoorKeyType::Green => "G",
+            DoorKeyType::Blue => "B",
+            DoorKeyType::Yellow => "Y",
+        }
+    } else {
+        "k"
+    };
+    Glyph::new(text, key_color(key_type))
+}
+
+/// Returns the glyph of an item lying on a cell.
+pub fn item_glyph(item: &Item, symbols: bool) -> Glyph {
+    match item {
+        Item::Chip => Glyph::new("c", GlyphColor::Yellow),
+        Item::BonusChip { .. } => Glyph::new("C", GlyphColor::Yellow).bold(),
+        Item::OrderedChip { order } => Glyph::new(order.to_string(), GlyphColor::Yellow),
+        Item::Goal => Glyph::new("g", GlyphColor::Green),
+        Item::Key { key_type } => key_glyph(*key_type, symbols),
+    }
+}
+
+/// Returns the glyph of an agent; patrollers are drawn as `E`, everyone else as `@`.
+pub fn agent_glyph(patroller: bool) -> Glyph {
+    if patroller {
+        Glyph::new("E", GlyphColor::Magenta).bold()
+    } else {
+        Glyph::new("@", GlyphColor::Red).bold()
+    }
+}
+
+/// Returns the glyph of a ghost replaying a recorded run: a faint `@`.
+pub fn ghost_glyph() -> Glyph {
+    Glyph::new("@", GlyphColor::DarkGray).dim()
+}
+
+/// Returns the glyph of a cell's terrain. Floors and void are blank. In symbol mode
+/// closed locked doors show a digit matching their key type.
+pub fn cell_glyph(cell: &CellType, symbols: bool) -> Glyph {
+    match cell {
+        CellType::Floor | CellType::Void => Glyph::new(" ", GlyphColor::Default),
+        CellType::Wall => Glyph::new("#", GlyphColor::DarkGray),
+        CellType::Unknown => Glyph::new("?", GlyphColor::Default),
+        CellType::Hazard => Glyph::new("!", GlyphColor::Red),
+        CellType::OneWay { direction } => {
+            let text = match direction {
+                (1, 0) => ">",
+                (-1, 0) => "<",
+                (0, -1) => "^",
+                (0, 1) => "v",
+                _ => "~",
+            };
+            Glyph::new(text, GlyphColor::Default)
+        }
+        // Unlocked doors paid for with chips
+        CellType::Door {
+            open: false,
+            door_type: None,
+            cost: Some(_),
+            ..
+        } => Glyph::new("$", GlyphColor::Yellow),
+        CellType::Door {
+            open, door_type, ..
+        } => {
+            let text = match (open, door_type) {
+                (true, _) => "+",
+                (false, Some(key_type)) if symbols => match key_type {
+                    DoorKeyType::Red => "1",
+                    DoorKeyType::Green => "2",
+                    DoorKeyType::Blue => "3",
+                    DoorKeyType::Yellow => "4",
+                },
+                (false, _) => "|",
+            };
+            Glyph::new(text, door_type.map_or(GlyphColor::Default, key_color))
+        }
+    }
+}
@@ -1,21 +1,100 @@
+use std::{
+    collections::HashSet,
+    ops::{Add, Sub},
+};
+
 use serde::{Deserialize, Serialize};
 
+#[macro_use]
+mod logging;
+
 pub mod agent;
+#[cfg(feature = "binary")]
+pub mod binary;
+pub mod blackboard;
+pub mod builder;
+pub mod campaign;
 pub mod environment;
+pub mod external;
+pub mod glyph;
 pub mod map;
+pub mod pathfinding;
+pub mod runner;
+#[cfg(feature = "svg")]
+pub mod svg;
 
 /// Unique identifier for entities (agents, items, etc.).
 pub type EntityId = usize;
 
 /// Represents a 2D coordinate.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Position {
     pub x: usize,
     pub y: usize,
 }
 
+impl Position {
+    /// Number of cardinal steps between the two positions.
+    pub fn manhattan_distance(&self, other: &Position) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    /// Number of steps between the two positions when diagonal moves are allowed.
+    pub fn chebyshev_distance(&self, other: &Position) -> usize {
+        self.x.abs_diff(other.x).max(self.y.abs_diff(other.y))
+    }
+
+    /// Returns the position moved by `(dx, dy)`, or `None` if either coordinate would
+    /// become negative or overflow. The result is not checked against any grid bounds.
+    pub fn offset(&self, dx: isize, dy: isize) -> Option<Position> {
+        Some(Position {
+            x: self.x.checked_add_signed(dx)?,
+            y: self.y.checked_add_signed(dy)?,
+        })
+    }
+}
+
+/// A signed displacement between two positions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Offset {
+    pub dx: isize,
+    pub dy: isize,
+}
+
+impl Offset {
+    /// Returns the offset with each component reduced to -1, 0 or 1, i.e. a single step
+    /// in the same direction.
+    pub fn signum(self) -> Offset {
+        Offset {
+            dx: self.dx.signum(),
+            dy: self.dy.signum(),
+        }
+    }
+}
+
+impl Sub for Position {
+    type Output = Offset;
+
+    /// Returns the offset that moves `rhs` to `self`.
+    fn sub(self, rhs: Position) -> Offset {
+        Offset {
+            dx: self.x as isize - rhs.x as isize,
+            dy: self.y as isize - rhs.y as isize,
+        }
+    }
+}
+
+impl Add<Offset> for Position {
+    type Output = Option<Position>;
+
+    /// Same as `offset`; `None` if a coordinate would become negative or overflow.
+    fn add(self, rhs: Offset) -> Option<Position> {
+        self.offset(rhs.dx, rhs.dy)
+    }
+}
+
 /// Represents the specific type (color) of a door or key.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum DoorKeyType {
     Red,
     Green,
@@ -23,10 +102,90 @@ pub enum DoorKeyType {
     Yellow,
 }
 
+impl DoorKeyType {
+    /// All door/key types, in a fixed order suitable for display.
+    pub const ALL: [DoorKeyType; 4] = [
+        DoorKeyType::Red,
+        DoorKeyType::Green,
+        DoorKeyType::Blue,
+        DoorKeyType::Yellow,
+    ];
+}
+
+/// A partial order over key types deciding which keys open which doors.
+///
+/// A key always opens doors of its own type. `with_rank` additionally lets a higher key
+/// open lower doors, e.g. a yellow "gold" key that also opens red and green doors. The
+/// default hierarchy is exact-match only.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyHierarchy {
+    /// `(higher, lower)` pairs, transitively closed.
+    outranks: HashSet<(DoorKeyType, DoorKeyType)>,
+}
+
+impl KeyHierarchy {
+    /// Lets `higher` keys open `lower` doors, and every door `lower` keys can open.
+    pub fn with_rank(mut self, higher: DoorKeyType, lower: DoorKeyType) -> Self {
+        self.outranks.insert((higher, lower));
+        // Keep the relation transitively closed
+        loop {
+            let implied: Vec<(DoorKeyType, DoorKeyType)> = self
+                .outranks
+                .iter()
+                .flat_map(|&(a, b)| {
+                    self.outranks
+                        .iter()
+                        .filter(move |&&(c, _)| c == b)
+                        .map(move |&(_, d)| (a, d))
+                })
+                .filter(|pair| !self.outranks.contains(pair))
+                .collect();
+            if implied.is_empty() {
+                return self;
+            }
+            self.outranks.extend(implied);
+        }
+    }
+
+    /// Returns true if a `key` key opens a `door` door.
+    pub fn can_open(&self, key: DoorKeyType, door: DoorKeyType) -> bool {
+        key == door || self.outranks.contains(&(key, door))
+    }
+
+    /// Returns every door type that one of `keys` can open.
+    pub fn openable_doors(&self, keys: &HashSet<DoorKeyType>) -> HashSet<DoorKeyType> {
+        DoorKeyType::ALL
+            .into_iter()
+            .filter(|door| keys.iter().any(|key| self.can_open(*key, *door)))
+            .collect()
+    }
+}
+
 /// Represents items that can exist in the environment or agent inventories.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Item {
-    Key { key_type: DoorKeyType },
+    Key {
+        key_type: DoorKeyType,
+    },
     Chip,
+    /// A high-value chip that counts as `value` chips when collected.
+    BonusChip {
+        value: usize,
+    },
+    /// A chip that must be collected in ascending order, starting at 1.
+    OrderedChip {
+        order: usize,
+    },
     Goal,
 }
+
+impl Item {
+    /// Returns how many chips this item counts as (0 for non-chip items).
+    pub fn chip_value(&self) -> usize {
+        match self {
+            Item::Chip | Item::OrderedChip { .. } => 1,
+            Item::BonusChip { value } => *value,
+            _ => 0,
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::ops::{Index, IndexMut};
 
 use serde::{Deserialize, Serialize};
 
-use crate::Position;
+use crate::{Offset, Position};
 
 /// Represents errors that can occur within the grid operations.
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
@@ -14,19 +14,54 @@ pub enum GridError {
         width: usize,
         height: usize,
     },
+    #[error("Invalid grid encoding: {0}")]
+    InvalidEncoding(String),
 }
 
 /// A generic 2D grid structure.
 ///
 /// Stores elements of type `T` in a flat vector using row-major order.
 /// Provides methods for accessing and modifying elements via (x, y) coordinates.
+///
+/// Either dimension may be zero, giving a grid with no cells: iterators are empty and
+/// every coordinate is out of bounds.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawGrid<T>", bound(deserialize = "T: Deserialize<'de>"))]
 pub struct Grid<T> {
     width: usize,
     height: usize,
     cells: Vec<T>,
 }
 
+/// Unvalidated serialized form of a `Grid`.
+#[derive(Deserialize)]
+struct RawGrid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> TryFrom<RawGrid<T>> for Grid<T> {
+    type Error = GridError;
+
+    /// Rejects grids whose cell count doesn't match their dimensions.
+    fn try_from(raw: RawGrid<T>) -> Result<Self, GridError> {
+        if raw.width.checked_mul(raw.height) != Some(raw.cells.len()) {
+            return Err(GridError::InvalidEncoding(format!(
+                "{} cells for a {}x{} grid",
+                raw.cells.len(),
+                raw.width,
+                raw.height
+            )));
+        }
+        Ok(Grid {
+            width: raw.width,
+            height: raw.height,
+            cells: raw.cells,
+        })
+    }
+}
+
 impl<T> Grid<T> {
     /// Creates a new grid with the specified dimensions, filled with default values.
     ///
@@ -69,9 +104,12 @@ impl<T> Grid<T> {
     {
         let size = width.checked_mul(height).expect("Grid size overflow");
         let mut cells = Vec::with_capacity(size);
-        for y in 0..height {
-            for x in 0..width {
-                cells.push(f(x, y));
+        // Skip the rows entirely for degenerate grids, which may have a huge height
+        if size > 0 {
+            for y in 0..height {
+                for x in 0..width {
+                    cells.push(f(x, y));
+                }
             }
         }
         Grid {
@@ -125,6 +163,37 @@ impl<T> Grid<T> {
         x < self.width && y < self.height
     }
 
+    /// Returns the position reached by moving `(dx, dy)` from `position`, or `None` if it
+    /// is off the grid. With `wrap`, moving off one edge enters at the opposite edge.
+    pub fn step(&self, position: Position, dx: isize, dy: isize, wrap: bool) -> Option<Position> {
+        if !wrap {
+            return position
+                .offset(dx, dy)
+                .filter(|target| self.is_valid(target.x, target.y));
+        }
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+        let wrapped = |coordinate: usize, delta: isize, size: usize| {
+            (coordinate as i128 + delta as i128).rem_euclid(size as i128) as usize
+        };
+        Some(Position {
+            x: wrapped(position.x, dx, self.width),
+            y: wrapped(position.y, dy, self.height),
+        })
+    }
+
+    /// Returns the single step (each component -1, 0 or 1) that `step` turns into a move
+    /// from `from` to `to`, or `None` if they are neither equal nor neighbors.
+    pub fn step_between(&self, from: Position, to: Position, wrap: bool) -> Option<Offset> {
+        if from == to {
+            return Some(Offset { dx: 0, dy: 0 });
+        }
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| Offset { dx, dy }))
+            .find(|offset| self.step(from, offset.dx, offset.dy, wrap) == Some(to))
+    }
+
     /// Gets an immutable reference to the cell at the given coordinates.
     ///
     /// Returns `None` if the coordinates are out of bounds.
@@ -154,14 +223,75 @@ impl<T> Grid<T> {
     /// Returns `Ok(())` on success, or `Err(GridError::OutOfBounds)` if the
     /// coordinates are invalid.
     pub fn set(&mut self, x: usize, y: usize, value: T) -> Result<(), GridError> {
-        let index = self.coords_to_index(x, y).ok_or(GridError::OutOfBounds {
+        let index = self.checked_index(x, y)?;
+        self.cells[index] = value;
+        Ok(())
+    }
+
+    /// Swaps the values of the cells at positions `a` and `b`.
+    ///
+    /// Returns `Err(GridError::OutOfBounds)` for the first invalid position, leaving the
+    /// grid unchanged.
+    pub fn swap(&mut self, a: Position, b: Position) -> Result<(), GridError> {
+        let a = self.checked_index(a.x, a.y)?;
+        let b = self.checked_index(b.x, b.y)?;
+        self.cells.swap(a, b);
+        Ok(())
+    }
+
+    /// Replaces the value of the cell at `position`, returning the previous value.
+    ///
+    /// Returns `None` and leaves the grid unchanged if the position is out of bounds.
+    pub fn replace(&mut self, position: Position, value: T) -> Option<T> {
+        self.get_mut(position.x, position.y)
+            .map(|cell| std::mem::replace(cell, value))
+    }
+
+    /// Resizes the grid to `new_width` x `new_height`, keeping the cells in the region
+    /// both sizes share (anchored at the top-left) and filling new cells with `fill`.
+    /// Cells outside the new bounds are discarded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_width * new_height` overflows `usize`.
+    pub fn resize(&mut self, new_width: usize, new_height: usize, fill: T)
+    where
+        T: Clone,
+    {
+        let size = new_width
+            .checked_mul(new_height)
+            .expect("Grid size overflow");
+        let mut old = std::mem::take(&mut self.cells).into_iter();
+        let mut cells = Vec::with_capacity(size);
+        // Skip the rows entirely for degenerate grids, which may have a huge height
+        if size > 0 {
+            for y in 0..new_height {
+                if y < self.height {
+                    let mut row = old.by_ref().take(self.width);
+                    cells.extend(row.by_ref().take(new_width));
+                    // Drop the part of the old row past the new width
+                    row.for_each(drop);
+                    let extra = new_width.saturating_sub(self.width);
+                    cells.extend(std::iter::repeat_n(fill.clone(), extra));
+                } else {
+                    cells.extend(std::iter::repeat_n(fill.clone(), new_width));
+                }
+            }
+        }
+
+        self.width = new_width;
+        self.height = new_height;
+        self.cells = cells;
+    }
+
+    /// Converts coordinates to a flat index, or `GridError::OutOfBounds` if invalid.
+    fn checked_index(&self, x: usize, y: usize) -> Result<usize, GridError> {
+        self.coords_to_index(x, y).ok_or(GridError::OutOfBounds {
             x,
             y,
             width: self.width,
             height: self.height,
-        })?;
-        self.cells[index] = value;
-        Ok(())
+        })
     }
 
     /// Returns an iterator over the cells of the grid in row-major order.
@@ -174,21 +304,68 @@ impl<T> Grid<T> {
         self.cells.iter_mut()
     }
 
+    /// Returns an iterator over the rows of the grid from top to bottom, each a slice of
+    /// `width` cells. Grids with no cells yield no rows.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // `chunks` panics on zero; a zero-width grid has no cells to chunk anyway
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// Returns an iterator over the in-bounds neighbors of `position` with their cells:
+    /// the four cardinal neighbors (down, up, right, left), followed by the four diagonal
+    /// ones if `diagonal` is true.
+    pub fn neighbors_of(
+        &self,
+        position: Position,
+        diagonal: bool,
+    ) -> impl Iterator<Item = (Position, &T)> {
+        const CARDINAL: [(isize, isize); 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+        const DIAGONAL: [(isize, isize); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+        let diagonals: &[(isize, isize)] = if diagonal { &DIAGONAL } else { &[] };
+        CARDINAL
+            .iter()
+            .chain(diagonals)
+            .filter_map(move |(dx, dy)| {
+                let neighbor = position.offset(*dx, *dy)?;
+                self.get(neighbor.x, neighbor.y)
+                    .map(|cell| (neighbor, cell))
+            })
+    }
+
+    /// Returns an iterator over the position of every cell in row-major order.
+    pub fn positions(&self) -> impl Iterator<Item = Position> + use<T> {
+        let (width, height) = (self.width, self.height);
+        // Degenerate grids have no positions even if the other dimension is nonzero
+        let rows = if width == 0 { 0 } else { height };
+        (0..rows).flat_map(move |y| (0..width).map(move |x| Position { x, y }))
+    }
+
     /// Returns an iterator that yields `((x, y), &T)` for each cell.
     pub fn enumerate(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
-        self.cells
-            .iter()
-            .enumerate()
-            .map(move |(index, cell)| (self.index_to_coords(index).unwrap(), cell))
+        self.positions()
+            .zip(self.cells.iter())
+            .map(|(position, cell)| ((position.x, position.y), cell))
     }
 
     /// Returns a mutable iterator that yields `((x, y), &mut T)` for each cell.
     pub fn enumerate_mut(&mut self) -> impl Iterator<Item = ((usize, usize), &mut T)> {
-        let width = self.width; // Capture width for the closure
-        self.cells.iter_mut().enumerate().map(move |(index, cell)| {
-            let y = index / width;
-            let x = index % width;
-            ((x, y), cell)
+        self.positions()
+            .zip(self.cells.iter_mut())
+            .map(|(position, cell)| ((position.x, position.y), cell))
+    }
+
+    /// Returns the number of cells matching `predicate`.
+    pub fn count(&self, predicate: impl Fn(&T) -> bool) -> usize {
+        self.cells.iter().filter(|cell| predicate(cell)).count()
+    }
+
+    /// Returns the position of the first cell in row-major order matching `predicate`.
+    pub fn find_position(&self, predicate: impl Fn(&T) -> bool) -> Option<Position> {
+        let index = self.cells.iter().position(predicate)?;
+        Some(Position {
+            x: index % self.width,
+            y: index / self.width,
         })
     }
 
@@ -0,0 +1,456 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use crate::{
+    DoorKeyType, EntityId, Position,
+    environment::{ActionFailure, CellType},
+    map::Grid,
+};
+
+/// Cost of a single cardinal (up/down/left/right) step.
+pub const CARDINAL_COST: usize = 10;
+
+/// Default cost of a single diagonal step, approximating `CARDINAL_COST * √2`.
+pub const DIAGONAL_COST: usize = 14;
+
+/// Cardinal movement directions.
+const CARDINAL_DIRECTIONS: [(isize, isize); 4] = [
+    (0, 1),  // Down
+    (0, -1), // Up
+    (1, 0),  // Right
+    (-1, 0), // Left
+];
+
+/// Diagonal movement directions.
+const DIAGONAL_DIRECTIONS: [(isize, isize); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+/// The straight run a step continues when `PathfindingConfig::speed` is above 1: its
+/// direction and the further steps a single dash still covers, if any.
+type Run = Option<((isize, isize), usize)>;
+
+/// Why `find_path_diagnosed` found no path.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PathfindingFailure {
+    /// The path is blocked by the locked door at the given position, the first one on
+    /// the route that would be taken with every key.
+    #[error("Path blocked by a locked {0:?} door at {1:?}.")]
+    BlockedByDoor(DoorKeyType, Position),
+    /// The path is blocked by the door at the given position costing more chips than
+    /// are left when reaching it.
+    #[error("Path blocked by a door costing {cost} chips at {position:?}.")]
+    NotEnoughChips { cost: usize, position: Position },
+    /// A path exists but the search gave up after `max_expansions` nodes.
+    #[error("Search gave up after the node expansion limit.")]
+    ExpansionLimit,
+    /// No path exists even with every key and unlimited chips.
+    #[error("Goal is unreachable.")]
+    Unreachable,
+}
+
+/// How the pathfinder treats `CellType::Unknown` cells.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownCellPolicy {
+    /// Unknown cells are never entered.
+    #[default]
+    Pessimistic,
+    /// Unknown cells are assumed to be floor, allowing routes through unexplored space.
+    Optimistic,
+}
+
+/// Configuration for the pathfinder.
+///
+/// Costs are scaled integers so a cardinal step costs `CARDINAL_COST` (10) and a diagonal
+/// step costs `diagonal_cost` (14 by default), keeping the math integer-only.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathfindingConfig {
+    /// Cost of a diagonal step, or `None` to only allow cardinal moves.
+    pub diagonal_cost: Option<usize>,
+    /// Whether unknown cells are treated as passable or impassable.
+    pub unknown_cells: UnknownCellPolicy,
+    /// Whether hazard cells are excluded from paths. When false, paths may lead straight
+    /// through hazards.
+    pub avoid_hazards: bool,
+    /// Maximum number of nodes A* may expand before giving up, or `None` for no limit.
+    /// Bounds the cost of a single search on very large or pathological maps.
+    pub max_expansions: Option<usize>,
+    /// Whether moving off one edge of the grid enters at the opposite edge, as in an
+    /// environment with `wrap_edges` set.
+    pub wrap_edges: bool,
+    /// Most cells the agent covers per turn with `Action::Dash`. Above 1, each straight
+    /// run of up to `speed` steps costs a single step, so a run of `n` steps costs as
+    /// many as `path_turns` gives. 0 and 1 both mean plain single steps.
+    pub speed: usize,
+}
+
+impl PathfindingConfig {
+    /// Configuration allowing diagonal moves at the default `DIAGONAL_COST`.
+    pub fn with_diagonals() -> Self {
+        Self {
+            diagonal_cost: Some(DIAGONAL_COST),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the cost of a step in `direction` from `a` to `b` following the straight
+    /// run `run` (its direction and the free steps left in it), and the run afterwards.
+    fn run_step(
+        &self,
+        run: Run,
+        direction: (isize, isize),
+        a: &Position,
+        b: &Position,
+    ) -> (usize, Run) {
+        let speed = self.speed();
+        if speed == 1 {
+            return (self.step_cost(a, b), None);
+        }
+        match run {
+            Some((run_direction, left)) if run_direction == direction && left > 0 => {
+                (0, Some((direction, left - 1)))
+            }
+            _ => (self.step_cost(a, b), Some((direction, speed - 1))),
+        }
+    }
+
+    /// Returns the speed with 0 counted as 1.
+    fn speed(&self) -> usize {
+        self.speed.max(1)
+    }
+
+    /// Returns the cost of stepping from `a` to the adjacent position `b`.
+    fn step_cost(&self, a: &Position, b: &Position) -> usize {
+        if a.x != b.x && a.y != b.y {
+            self.diagonal_cost.unwrap_or(CARDINAL_COST)
+        } else {
+            CARDINAL_COST
+        }
+    }
+
+    /// Admissible heuristic estimate of the cost between two positions.
+    ///
+    /// Uses manhattan distance for cardinal-only movement and octile distance when
+    /// diagonals are enabled. If diagonals are cheaper than a cardinal step the diagonal
+    /// cost is used for the whole distance so the estimate never overshoots. With wrapping
+    /// edges each axis uses the shorter way around a grid of the given `size`.
+    fn heuristic(&self, a: &Position, b: &Position, (width, height): (usize, usize)) -> usize {
+        let mut dx = a.x.abs_diff(b.x);
+        let mut dy = a.y.abs_diff(b.y);
+        if self.wrap_edges {
+            dx = dx.min(width.saturating_sub(dx));
+            dy = dy.min(height.saturating_sub(dy));
+        }
+        // Dashing covers up to `speed` cells for the cost of one step
+        let estimate = match self.diagonal_cost {
+            None => CARDINAL_COST * (dx + dy),
+            Some(diagonal) => {
+                let (long, short) = (dx.max(dy), dx.min(dy));
+                let diagonal = diagonal.min(2 * CARDINAL_COST);
+                if diagonal < CARDINAL_COST {
+                    diagonal * long
+                } else {
+                    diagonal * short + CARDINAL_COST * (long - short)
+                }
+            }
+        };
+        estimate / self.speed()
+    }
+}
+
+/// Returns the direction of the step from `from` to its neighbor `to` on `terrain`,
+/// wrapping around the edges if `wrap` is set, or `(0, 0)` if they aren't neighbors.
+fn step_direction<T>(
+    terrain: &Grid<T>,
+    from: Position,
+    to: Position,
+    wrap: bool,
+) -> (isize, isize) {
+    terrain
+        .step_between(from, to, wrap)
+        .map_or((0, 0), |offset| (offset.dx, offset.dy))
+}
+
+/// Returns the total cost of walking along `path` on `terrain` under the given
+/// configuration. With a `speed` above 1, steps continuing a straight run are free until
+/// the run reaches `speed` steps, as in `find_path`.
+pub fn path_cost<T>(terrain: &Grid<T>, path: &[Position], config: &PathfindingConfig) -> usize {
+    let mut cost = 0;
+    let mut run: Run = None;
+    for step in path.windows(2) {
+        let direction = step_direction(terrain, step[0], step[1], config.wrap_edges);
+        let (step_cost, next) = config.run_step(run, direction, &step[0], &step[1]);
+        cost += step_cost;
+        run = next;
+    }
+    cost
+}
+
+/// Returns how many turns an agent of the given `speed` needs to walk `path` on
+/// `terrain` using `Action::Dash`, which covers up to `speed` cells of a straight run per
+/// turn. Steps across the edges count as straight if `wrap` is set.
+pub fn path_turns<T>(terrain: &Grid<T>, path: &[Position], speed: usize, wrap: bool) -> usize {
+    let speed = speed.max(1);
+    let mut turns = 0;
+    let mut run: Option<((isize, isize), usize)> = None;
+    for step in path.windows(2) {
+        let direction = step_direction(terrain, step[0], step[1], wrap);
+        run = match run {
+            Some((run_direction, length)) if run_direction == direction => {
+                Some((direction, length + 1))
+            }
+            Some((_, length)) => {
+                turns += length.div_ceil(speed);
+                Some((direction, 1))
+            }
+            None => Some((direction, 1)),
+        };
+    }
+    if let Some((_, length)) = run {
+        turns += length.div_ceil(speed);
+    }
+    turns
+}
+
+/// Finds a lowest-cost path from `start` to `goal` using A*.
+///
+/// Cells occupied by agents in `agents` (if given) are treated as blocked, as are walls,
+/// locked doors whose key is not in `keys_held`, and closed doors costing more chips than
+/// remain of `chips_held` after paying for earlier doors on the path. The returned path
+/// includes both `start` and `goal`. Returns `None` if there is no path or the search
+/// exceeds `config.max_expansions`.
+pub fn find_path(
+    terrain: &Grid<CellType>,
+    agents: Option<&Grid<Option<EntityId>>>,
+    start: Position,
+    goal: Position,
+    keys_held: &HashSet<DoorKeyType>,
+    chips_held: usize,
+    config: &PathfindingConfig,
+) -> Option<Vec<Position>> {
+    // Search nodes are positions paired with the chips left to spend and, for agents
+    // faster than 1, the straight run they are in
+    type Node = (Position, usize, Run);
+
+    // For priority queue
+    #[derive(Clone, Eq, PartialEq)]
+    struct PrioritizedItem {
+        priority: usize,
+        node: Node,
+    }
+
+    impl Ord for PrioritizedItem {
+        fn cmp(&self, other: &Self) -> Ordering {
+            // Reverse ordering for min-heap behavior
+            other.priority.cmp(&self.priority)
+        }
+    }
+
+    impl PartialOrd for PrioritizedItem {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    let mut frontier = BinaryHeap::new();
+    let mut came_from: HashMap<Node, Node> = HashMap::new();
+    let mut cost_so_far: HashMap<Node, usize> = HashMap::new();
+
+    let start_node = (start, chips_held, None);
+    frontier.push(PrioritizedItem {
+        priority: 0,
+        node: start_node,
+    });
+    cost_so_far.insert(start_node, 0);
+
+    let mut goal_node = None;
+    let mut expansions = 0;
+    let size = (terrain.width(), terrain.height());
+
+    while let Some(PrioritizedItem { node: current, .. }) = frontier.pop() {
+        if current.0 == goal {
+            goal_node = Some(current);
+            break;
+        }
+
+        expansions += 1;
+        if config
+            .max_expansions
+            .is_some_and(|limit| expansions > limit)
+        {
+            return None;
+        }
+
+        let current_cost = *cost_so_far.get(&current).unwrap_or(&usize::MAX);
+
+        for (position, chips) in
+            valid_neighbors(terrain, agents, (current.0, current.1), keys_held, config)
+        {
+            let direction = step_direction(terrain, current.0, position, config.wrap_edges);
+            let (step_cost, run) = config.run_step(current.2, direction, &current.0, &position);
+            let neighbor = (position, chips, run);
+            let new_cost = current_cost.saturating_add(step_cost);
+
+            if cost_so_far
+                .get(&neighbor)
+                .is_none_or(|&existing| new_cost < existing)
+            {
+                cost_so_far.insert(neighbor, new_cost);
+                let priority = new_cost + config.heuristic(&neighbor.0, &goal, size);
+                frontier.push(PrioritizedItem {
+                    priority,
+                    node: neighbor,
+                });
+                came_from.insert(neighbor, current);
+            }
+        }
+    }
+
+    // Reconstruct path
+    let mut current = goal_node?;
+    let mut path = vec![current.0];
+
+    while current != start_node {
+        current = *came_from.get(&current)?;
+        path.push(current.0);
+    }
+
+    path.reverse();
+    Some(path)
+}
+
+/// Like `find_path`, but on failure reports why no path was found.
+///
+/// Runs extra searches only when the first one fails: without the expansion limit, then
+/// with every key and unlimited chips to find the door blocking the way.
+pub fn find_path_diagnosed(
+    terrain: &Grid<CellType>,
+    agents: Option<&Grid<Option<EntityId>>>,
+    start: Position,
+    goal: Position,
+    keys_held: &HashSet<DoorKeyType>,
+    chips_held: usize,
+    config: &PathfindingConfig,
+) -> Result<Vec<Position>, PathfindingFailure> {
+    if let Some(path) = find_path(terrain, agents, start, goal, keys_held, chips_held, config) {
+        return Ok(path);
+    }
+
+    let unlimited = PathfindingConfig {
+        max_expansions: None,
+        ..config.clone()
+    };
+    if config.max_expansions.is_some()
+        && find_path(
+            terrain, agents, start, goal, keys_held, chips_held, &unlimited,
+        )
+        .is_some()
+    {
+        return Err(PathfindingFailure::ExpansionLimit);
+    }
+
+    let all_keys: HashSet<DoorKeyType> = DoorKeyType::ALL.into_iter().collect();
+    let relaxed = find_path(
+        terrain,
+        agents,
+        start,
+        goal,
+        &all_keys,
+        usize::MAX,
+        &unlimited,
+    )
+    .ok_or(PathfindingFailure::Unreachable)?;
+    let mut chips = chips_held;
+    for position in relaxed {
+        match terrain.get(position.x, position.y) {
+            Some(CellType::Door {
+                open: false,
+                door_type: Some(door_type),
+                ..
+            }) if !keys_held.contains(door_type) => {
+                return Err(PathfindingFailure::BlockedByDoor(*door_type, position));
+            }
+            Some(CellType::Door {
+                open: false,
+                cost: Some(cost),
+                ..
+            }) => match chips.checked_sub(*cost) {
+                Some(left) => chips = left,
+                None => {
+                    return Err(PathfindingFailure::NotEnoughChips {
+                        cost: *cost,
+                        position,
+                    });
+                }
+            },
+            _ => {}
+        }
+    }
+    // Only reached if the relaxed path needed nothing the agent lacks
+    Err(PathfindingFailure::Unreachable)
+}
+
+/// Gets valid neighbors for a position based on the terrain, agent occupancy, keys held and
+/// chips left, paired with the chips left after entering them.
+fn valid_neighbors(
+    terrain: &Grid<CellType>,
+    agents: Option<&Grid<Option<EntityId>>>,
+    (position, chips): (Position, usize),
+    keys_held: &HashSet<DoorKeyType>,
+    config: &PathfindingConfig,
+) -> Vec<(Position, usize)> {
+    let mut neighbors = Vec::new();
+
+    let diagonals: &[(isize, isize)] = if config.diagonal_cost.is_some() {
+        &DIAGONAL_DIRECTIONS
+    } else {
+        &[]
+    };
+
+    let Some(current_cell) = terrain.get(position.x, position.y) else {
+        return neighbors;
+    };
+
+    for (dx, dy) in CARDINAL_DIRECTIONS.iter().chain(diagonals) {
+        // Calculate neighbor position, skipping or wrapping around the grid edges
+        let Some(Position { x: nx, y: ny }) = terrain.step(position, *dx, *dy, config.wrap_edges)
+        else {
+            continue;
+        };
+        let Some(cell) = terrain.get(nx, ny) else {
+            continue;
+        };
+
+        // Check if position is occupied by another agent
+        if let Some(Some(_)) = agents.and_then(|agents| agents.get(nx, ny)) {
+            continue;
+        }
+
+        // Closed doors with a chip cost must be paid for
+        let mut chips_left = chips;
+        if let CellType::Door {
+            open: false,
+            cost: Some(cost),
+            ..
+        } = cell
+        {
+            match chips.checked_sub(*cost) {
+                Some(left) => chips_left = left,
+                None => continue,
+            }
+        }
+
+        // Apply the engine's terrain rules, relaxed or tightened by the config
+        match current_cell.entry_failure(cell, *dx, *dy, keys_held) {
+            None if config.avoid_hazards && *cell == CellType::Hazard => continue,
+            None => {}
+            Some(ActionFailure::UnknownCell)
+                if config.unknown_cells == UnknownCellPolicy::Optimistic => {}
+            Some(_) => continue,
+        }
+
+        neighbors.push((Position { x: nx, y: ny }, chips_left));
+    }
+
+    neighbors
+}
@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    EntityId,
+    agent::Agent,
+    environment::{ActionResult, Environment, EnvironmentSnapshot, load_environment_from_string},
+};
+
+/// The result of running a simulation without a UI. Serializable so tooling can store
+/// or exchange run results in any serde format.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunOutcome {
+    /// `Win` if every agent reached a goal, `Lose` if one was lost (e.g. to a hazard),
+    /// `TimedOut` if the environment's own turn budget ran out, otherwise the result of
+    /// the last turn.
+    pub result: ActionResult,
+    /// Number of turns processed.
+    pub turns: usize,
+    /// Result of every processed turn, in order.
+    pub turn_results: Vec<ActionResult>,
+    /// Agents that reached a goal and the number of turns each took, sorted by ID.
+    /// Agents that timed out are not listed.
+    pub finish_turns: Vec<(EntityId, usize)>,
+}
+
+impl RunOutcome {
+    /// Returns true if the run ended in a win.
+    pub fn is_win(&self) -> bool {
+        self.result == ActionResult::Win
+    }
+}
+
+/// Runs the environment until the run is won or lost, or `max_turns` turns have been processed.
+pub fn run_headless(environment: &mut Environment, max_turns: usize) -> RunOutcome {
+    let mut turn_results = Vec::new();
+    let mut result = ActionResult::Success;
+
+    while turn_results.len() < max_turns {
+        result = environment.process_turn();
+        turn_results.push(result.clone());
+        if matches!(
+            result,
+            ActionResult::Win | ActionResult::Lose | ActionResult::TimedOut
+        ) {
+            break;
+        }
+    }
+
+    RunOutcome {
+        result,
+        turns: turn_results.len(),
+        turn_results,
+        finish_turns: environment.finish_turns(),
+    }
+}
+
+/// Loads `map`, places the agent created by `make_agent(id, seed)` at the start position,
+/// and runs it headless for up to `max_turns`.
+pub fn run_map<F>(
+    map: &str,
+    seed: u64,
+    max_turns: usize,
+    make_agent: F,
+) -> Result<(RunOutcome, EnvironmentSnapshot), String>
+where
+    F: Fn(EntityId, u64) -> Box<dyn Agent>,
+{
+    let (mut environment, start) = load_environment_from_string(map)?;
+    let agent = make_agent(environment.reserve_entity_id(), seed);
+    environment.add_agent(start, agent, vec![])?;
+
+    let outcome = run_headless(&mut environment, max_turns);
+    Ok((outcome, environment.snapshot()))
+}
+
+/// Aggregate results of one agent strategy over several maps and seeds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentComparison {
+    /// Name the strategy was given in `compare_agents`.
+    pub name: String,
+    /// Number of runs, one per map and seed.
+    pub runs: usize,
+    /// Number of runs that ended in a win.
+    pub wins: usize,
+    /// Turns taken by each won run.
+    pub win_turns: Vec<usize>,
+}
+
+impl AgentComparison {
+    /// Fraction of runs that were won, between 0 and 1.
+    pub fn success_rate(&self) -> f64 {
+        if self.runs == 0 {
+            0.0
+        } else {
+            self.wins as f64 / self.runs as f64
+        }
+    }
+
+    /// Mean number of turns taken by won runs, or `None` if no run was won.
+    pub fn average_turns_to_win(&self) -> Option<f64> {
+        if self.win_turns.is_empty() {
+            None
+        } else {
+            Some(self.win_turns.iter().sum::<usize>() as f64 / self.win_turns.len() as f64)
+        }
+    }
+}
+
+/// A named agent strategy for `compare_agents`, built from an entity ID and seed.
+pub type AgentFactory<'a> = (&'a str, &'a dyn Fn(EntityId, u64) -> Box<dyn Agent>);
+
+/// Runs every agent strategy on every map with seeds `0..trials` and reports how often
+/// and how quickly each one wins. Results are in the same order as `agents` and are
+/// deterministic for deterministic agents.
+pub fn compare_agents(
+    maps: &[&str],
+    agents: &[AgentFactory],
+    trials: u64,
+    max_turns: usize,
+) -> Result<Vec<AgentComparison>, String> {
+    agents
+        .iter()
+        .map(|(name, make_agent)| {
+            let mut comparison = AgentComparison {
+                name: name.to_string(),
+                runs: 0,
+                wins: 0,
+                win_turns: Vec::new(),
+            };
+            for map in maps {
+                for seed in 0..trials {
+                    let (outcome, _) = run_map(map, seed, max_turns, make_agent)?;
+                    comparison.runs += 1;
+                    if outcome.is_win() {
+                        comparison.wins += 1;
+                        comparison.win_turns.push(outcome.turns);
+                    }
+                }
+            }
+            Ok(comparison)
+        })
+        .collect()
+}
+
+/// Runs the same scenario twice and checks that both runs produce identical turn results
+/// and final snapshots. Returns the outcome of the first run on success, or a description
+/// of the first divergence.
+pub fn run_twice_and_compare<F>(
+    map: &str,
+    seed: u64,
+    max_turns: usize,
+    make_agent: F,
+) -> Result<RunOutcome, String>
+where
+    F: Fn(EntityId, u64) -> Box<dyn Agent>,
+{
+    let (first, first_snapshot) = run_map(map, seed, max_turns, &make_agent)?;
+    let (second, second_snapshot) = run_map(map, seed, max_turns, &make_agent)?;
+
+    if let Some(turn) = first
+        .turn_results
+        .iter()
+        .zip(&second.turn_results)
+        .position(|(a, b)| a != b)
+    {
+        return Err(format!("Runs diverged at turn {}.", turn));
+    }
+    if first.turns != second.turns {
+        return Err(format!(
+            "Runs took different numbers of turns: {} and {}.",
+            first.turns, second.turns
+        ));
+    }
+    if first_snapshot != second_snapshot {
+        return Err("Final snapshots differ.".to_string());
+    }
+
+    Ok(first)
+}
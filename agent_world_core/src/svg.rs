@@ -0,0 +1,123 @@
+//! Renders an environment to a static SVG image, e.g. for documentation.
+//!
+//! Each cell is drawn as a square with its glyph from `crate::glyph`, the same the TUI
+//! uses, except that walls are drawn as gray blocks.
+
+use std::{collections::HashSet, fmt::Write as _, io, path::Path};
+
+use crate::{
+    Item, Position,
+    environment::{CellType, Environment},
+    glyph::{self, Glyph, GlyphColor},
+};
+
+/// Side length of one cell in the image, in pixels.
+pub const CELL_SIZE: usize = 24;
+
+const BACKGROUND: &str = "#000000";
+
+/// Returns the SVG color of a glyph color, as the default xterm palette shows it.
+fn fill(color: GlyphColor) -> &'static str {
+    match color {
+        GlyphColor::Default => "#e5e5e5",
+        GlyphColor::DarkGray => "#808080",
+        GlyphColor::Red => "#cd0000",
+        GlyphColor::Green => "#00cd00",
+        GlyphColor::Yellow => "#cdcd00",
+        GlyphColor::Blue => "#0000ee",
+        GlyphColor::Magenta => "#cd00cd",
+    }
+}
+
+/// Returns the fill of the cell's square, or `None` for cells left transparent.
+fn cell_fill(cell: &CellType) -> Option<&'static str> {
+    match cell {
+        CellType::Void => None,
+        CellType::Wall => Some(fill(GlyphColor::DarkGray)),
+        _ => Some(BACKGROUND),
+    }
+}
+
+/// Returns the glyph drawn on a cell, if any. Agents are drawn over terrain and items
+/// over agents, as in the TUI; walls are drawn as blocks instead of glyphs.
+fn drawn_glyph(cell: &CellType, item: Option<&Item>, agent: Option<Glyph>) -> Option<Glyph> {
+    if let Some(item) = item {
+        return Some(glyph::item_glyph(item, false));
+    }
+    if agent.is_some() {
+        return agent;
+    }
+    match cell {
+        CellType::Floor | CellType::Void | CellType::Wall => None,
+        _ => Some(glyph::cell_glyph(cell, false)),
+    }
+}
+
+/// Renders the environment's terrain, items and agents as an SVG document of
+/// `CELL_SIZE` pixels per cell. Ghosts are drawn faintly under other agents, finished
+/// agents are not drawn.
+pub fn render_to_svg(environment: &Environment) -> String {
+    let terrain = &environment.terrain;
+    // Ghosts are not tracked in `agent_locations`, so index them separately
+    let ghost_positions: HashSet<Position> = environment
+        .ghosts
+        .iter()
+        .filter_map(|id| environment.agents.get(id))
+        .map(|ghost| ghost.position)
+        .collect();
+    let (width, height) = (terrain.width() * CELL_SIZE, terrain.height() * CELL_SIZE);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         viewBox=\"0 0 {width} {height}\" font-family=\"monospace\" font-size=\"{}\" \
+         text-anchor=\"middle\" dominant-baseline=\"central\">\n",
+        CELL_SIZE * 3 / 4
+    );
+
+    for position in terrain.positions() {
+        let Position { x, y } = position;
+        let (left, top) = (x * CELL_SIZE, y * CELL_SIZE);
+        let cell = &terrain[position];
+        // Writing to a String can't fail
+        if let Some(fill) = cell_fill(cell) {
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{left}\" y=\"{top}\" width=\"{CELL_SIZE}\" height=\"{CELL_SIZE}\" fill=\"{fill}\"/>"
+            );
+        }
+        let agent = match environment.agent_locations[position] {
+            Some(id) => Some(glyph::agent_glyph(environment.patrollers.contains(&id))),
+            None if ghost_positions.contains(&position) => Some(glyph::ghost_glyph()),
+            None => None,
+        };
+        if let Some(glyph) = drawn_glyph(cell, environment.items[position].as_ref(), agent) {
+            let _ = writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" fill=\"{}\"{}{}>{}</text>",
+                left + CELL_SIZE / 2,
+                top + CELL_SIZE / 2,
+                fill(glyph.color),
+                if glyph.bold {
+                    " font-weight=\"bold\""
+                } else {
+                    ""
+                },
+                if glyph.dim { " opacity=\"0.5\"" } else { "" },
+                escape(&glyph.text)
+            );
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Escapes the characters XML treats specially, e.g. in one-way passage glyphs.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Renders the environment with `render_to_svg` and writes it to `path`.
+pub fn write_svg(environment: &Environment, path: impl AsRef<Path>) -> io::Result<()> {
+    std::fs::write(path, render_to_svg(environment))
+}
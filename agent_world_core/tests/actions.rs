@@ -0,0 +1,463 @@
+//! Checks how single actions are resolved and carried out by `Environment`.
+
+use agent_world_core::{
+    DoorKeyType, EntityId, Item, Position,
+    agent::{HumanAgent, HumanInput},
+    environment::{
+        Action, ActionFailure, ActionResult, BONUS_CHIP_VALUE, CellType, Environment,
+        load_environment_from_string,
+    },
+};
+
+/// Loads `map` with a human agent on its start, returning the environment and the agent.
+fn load(map: &str) -> (Environment, EntityId) {
+    let (mut env, start) = load_environment_from_string(map).expect("map loads");
+    let id = add_agent(&mut env, start);
+    (env, id)
+}
+
+/// Adds an idle human agent at `position`.
+fn add_agent(env: &mut Environment, position: Position) -> EntityId {
+    let id = env.reserve_entity_id();
+    env.add_agent(
+        position,
+        Box::new(HumanAgent::new(id, HumanInput::default())),
+        vec![],
+    )
+    .expect("agent is placed");
+    id
+}
+
+/// Asserts that previewing `action` predicts what processing it on a copy returns and
+/// leaves the environment unchanged.
+fn assert_preview_matches(env: &Environment, id: EntityId, action: Action) {
+    let before = env.snapshot();
+    let previewed = env.preview_action(id, action);
+    assert_eq!(env.snapshot(), before, "{action:?} changed the state");
+
+    let mut copy = env.clone();
+    assert_eq!(previewed, copy.process_action(id, action), "{action:?}");
+}
+
+#[test]
+fn preview_action_matches_process_action() {
+    let map = "WL WL WL WL WL WL WL\n\
+               WL ST BL CH KR DR PL\n\
+               WL HZ WL WL WL WL WL\n\
+               WL WL WL WL WL WL WL\n";
+    let (mut env, id) = load(map);
+    for action in [
+        Action::Wait,
+        Action::Move { dx: 1, dy: 0 },
+        Action::Move { dx: 0, dy: -1 },
+        Action::Move { dx: 0, dy: 1 },
+        Action::Dash { dx: 1, dy: 0 },
+        Action::Interact { dx: 1, dy: 0 },
+        Action::Drop { dx: 1, dy: 0 },
+        Action::MoveTo {
+            target: Position { x: 6, y: 1 },
+        },
+    ] {
+        assert_preview_matches(&env, id, action);
+    }
+
+    // A dash covers several cells and can end on the goal
+    env.agents.get_mut(&id).expect("agent exists").speed = 6;
+    assert_preview_matches(&env, id, Action::Dash { dx: 1, dy: 0 });
+    assert_eq!(
+        env.preview_action(id, Action::Dash { dx: 1, dy: 0 }),
+        ActionResult::Win
+    );
+
+    // Using up the last energy loses if exhaustion ends the run
+    env.exhaustion_ends_run = true;
+    env.agents.get_mut(&id).expect("agent exists").energy = Some(1);
+    assert_preview_matches(&env, id, Action::Move { dx: 1, dy: 0 });
+    assert_eq!(
+        env.preview_action(id, Action::Move { dx: 1, dy: 0 }),
+        ActionResult::Lose
+    );
+}
+
+#[test]
+fn dashing_covers_speed_cells_and_stops_at_walls() {
+    let map = "WL WL WL WL WL WL WL\n\
+               WL ST BL BL BL BL WL\n\
+               WL WL WL WL WL WL WL\n";
+    let (mut walker, walker_id) = load(map);
+    let (mut dasher, dasher_id) = load(map);
+    dasher
+        .agents
+        .get_mut(&dasher_id)
+        .expect("agent exists")
+        .speed = 2;
+    let position = |env: &Environment, id| env.agents[&id].position;
+
+    // The corridor takes four moves but only two dashes
+    for _ in 0..4 {
+        assert_eq!(
+            walker.process_action(walker_id, Action::Move { dx: 1, dy: 0 }),
+            ActionResult::Success
+        );
+    }
+    for _ in 0..2 {
+        assert_eq!(
+            dasher.process_action(dasher_id, Action::Dash { dx: 1, dy: 0 }),
+            ActionResult::Success
+        );
+    }
+    assert_eq!(position(&walker, walker_id), Position { x: 5, y: 1 });
+    assert_eq!(position(&dasher, dasher_id), Position { x: 5, y: 1 });
+
+    // A dash straight into the wall goes nowhere
+    assert_eq!(
+        dasher.process_action(dasher_id, Action::Dash { dx: 1, dy: 0 }),
+        ActionResult::Failure(ActionFailure::Wall)
+    );
+    assert_eq!(position(&dasher, dasher_id), Position { x: 5, y: 1 });
+
+    // A dash cut short by the wall ends next to it
+    dasher
+        .agents
+        .get_mut(&dasher_id)
+        .expect("agent exists")
+        .speed = 3;
+    dasher.process_action(dasher_id, Action::Move { dx: -1, dy: 0 });
+    assert_eq!(
+        dasher.process_action(dasher_id, Action::Dash { dx: 1, dy: 0 }),
+        ActionResult::Success
+    );
+    assert_eq!(position(&dasher, dasher_id), Position { x: 5, y: 1 });
+}
+
+#[test]
+fn bonus_chips_count_by_value() {
+    let map = "WL WL WL WL WL\n\
+               WL ST CH CB WL\n\
+               WL WL WL WL WL\n";
+    let (mut env, id) = load(map);
+    let chips = |env: &Environment| env.agents[&id].chip_count();
+
+    env.process_action(id, Action::Move { dx: 1, dy: 0 });
+    assert_eq!(chips(&env), 1);
+    env.process_action(id, Action::Move { dx: 1, dy: 0 });
+    assert_eq!(chips(&env), 1 + BONUS_CHIP_VALUE);
+    assert_eq!(BONUS_CHIP_VALUE, 3);
+
+    // Only the plain chip can be spent on doors
+    assert_eq!(env.agents[&id].spendable_chips(), 1);
+}
+
+#[test]
+fn one_way_passages_refuse_the_reverse() {
+    let map = "WL WL WL WL WL\n\
+               WL ST >> BL WL\n\
+               WL WL WL WL WL\n";
+    let (mut env, id) = load(map);
+    let west = Action::Move { dx: -1, dy: 0 };
+
+    assert_eq!(
+        env.process_action(id, Action::Move { dx: 1, dy: 0 }),
+        ActionResult::Success
+    );
+    assert_eq!(
+        env.process_action(id, west),
+        ActionResult::Failure(ActionFailure::OneWayExit)
+    );
+    assert_eq!(
+        env.process_action(id, Action::Move { dx: 1, dy: 0 }),
+        ActionResult::Success
+    );
+    assert_eq!(
+        env.process_action(id, west),
+        ActionResult::Failure(ActionFailure::OneWayEntry)
+    );
+    assert_eq!(env.agents[&id].position, Position { x: 3, y: 1 });
+}
+
+#[test]
+fn move_to_walks_to_reachable_targets_only() {
+    // The target is round the wall, the cell in the right corner is walled in
+    let map = "WL WL WL WL WL WL WL WL\n\
+               WL ST BL WL BL WL BL WL\n\
+               WL BL BL WL BL WL WL WL\n\
+               WL BL BL BL BL WL WL WL\n\
+               WL WL WL WL WL WL WL WL\n";
+    let (mut env, id) = load(map);
+    let target = Position { x: 4, y: 1 };
+
+    for _ in 0..7 {
+        assert_eq!(
+            env.process_action(id, Action::MoveTo { target }),
+            ActionResult::Success
+        );
+    }
+    assert_eq!(env.agents[&id].position, target);
+
+    let walled_in = Position { x: 6, y: 1 };
+    assert_eq!(
+        env.process_action(id, Action::MoveTo { target: walled_in }),
+        ActionResult::Failure(ActionFailure::Unreachable(walled_in))
+    );
+    assert_eq!(env.agents[&id].position, target);
+}
+
+#[test]
+fn each_failure_has_its_own_variant() {
+    let east = Action::Move { dx: 1, dy: 0 };
+    let failure = |env: &mut Environment, id, action| match env.process_action(id, action) {
+        ActionResult::Failure(failure) => failure,
+        result => panic!("{action:?} gave {result:?}"),
+    };
+    // Moving east from the start onto the cell `code`
+    let onto = |code: &str| {
+        let (mut env, id) = load(&format!("WL ST {code} WL\n"));
+        failure(&mut env, id, east)
+    };
+
+    assert_eq!(onto("WL"), ActionFailure::Wall);
+    assert_eq!(onto(".."), ActionFailure::Void);
+    assert_eq!(onto("DR"), ActionFailure::MissingKey(DoorKeyType::Red));
+    assert_eq!(onto("$2"), ActionFailure::NotEnoughChips(2));
+    assert_eq!(onto("DI"), ActionFailure::DoorClosed);
+    assert_eq!(onto("<<"), ActionFailure::OneWayEntry);
+    assert_eq!(
+        onto("C2 C1"),
+        ActionFailure::ChipOutOfOrder {
+            expected: 1,
+            found: 2
+        }
+    );
+
+    let (mut env, id) = load("WL ST CH WL\n");
+    env.process_action(id, east);
+    assert_eq!(
+        failure(&mut env, id, Action::Drop { dx: 1, dy: 0 }),
+        ActionFailure::CannotDropHere
+    );
+
+    let (mut env, id) = load("ST BL BL WL\n");
+    assert_eq!(
+        failure(&mut env, id, Action::Move { dx: -1, dy: 0 }),
+        ActionFailure::OutOfBounds
+    );
+    assert_eq!(
+        failure(&mut env, id, Action::Interact { dx: 1, dy: 0 }),
+        ActionFailure::NothingToInteract
+    );
+    assert_eq!(
+        failure(&mut env, id, Action::Drop { dx: 1, dy: 0 }),
+        ActionFailure::NoChipToDrop
+    );
+    assert_eq!(
+        failure(
+            &mut env,
+            id,
+            Action::MoveToAdjacent {
+                target: Position { x: 3, y: 0 }
+            }
+        ),
+        ActionFailure::NotAdjacent(Position { x: 3, y: 0 })
+    );
+    assert_eq!(
+        failure(&mut env, 99, east),
+        ActionFailure::AgentNotFound(99)
+    );
+
+    env.terrain[Position { x: 1, y: 0 }] = CellType::Unknown;
+    assert_eq!(failure(&mut env, id, east), ActionFailure::UnknownCell);
+
+    env.terrain[Position { x: 1, y: 0 }] = CellType::Floor;
+    add_agent(&mut env, Position { x: 1, y: 0 });
+    assert_eq!(failure(&mut env, id, east), ActionFailure::OccupiedByAgent);
+
+    env.agents.get_mut(&id).expect("agent exists").energy = Some(0);
+    assert_eq!(
+        failure(&mut env, id, Action::Move { dx: 0, dy: 1 }),
+        ActionFailure::OutOfEnergy
+    );
+}
+
+#[test]
+fn moving_to_an_adjacent_cell_is_a_relative_move() {
+    let map = "WL WL WL WL\n\
+               WL ST CH WL\n\
+               WL HZ KR WL\n\
+               WL WL WL WL\n";
+    let (env, id) = load(map);
+    let start = env.agents[&id].position;
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            if (dx, dy) == (0, 0) {
+                continue;
+            }
+            let mut relative = env.clone();
+            let mut absolute = env.clone();
+            let target = start.offset(dx, dy).expect("target is on the map");
+            assert_eq!(
+                absolute.process_action(id, Action::MoveToAdjacent { target }),
+                relative.process_action(id, Action::Move { dx, dy }),
+                "({dx}, {dy})"
+            );
+            assert_eq!(absolute.snapshot(), relative.snapshot(), "({dx}, {dy})");
+        }
+    }
+
+    let mut env = env;
+    for target in [start, Position { x: 3, y: 1 }] {
+        assert_eq!(
+            env.process_action(id, Action::MoveToAdjacent { target }),
+            ActionResult::Failure(ActionFailure::NotAdjacent(target))
+        );
+    }
+    assert_eq!(env.agents[&id].position, start);
+}
+
+#[test]
+fn duplicate_keys_are_only_picked_up_when_allowed() {
+    let map = "WL WL WL WL WL\n\
+               WL ST KR KR WL\n\
+               WL WL WL WL WL\n";
+    let red = Item::Key {
+        key_type: DoorKeyType::Red,
+    };
+    for allow in [false, true] {
+        let (mut env, id) = load(map);
+        env.allow_duplicate_keys = allow;
+        for _ in 0..2 {
+            env.process_action(id, Action::Move { dx: 1, dy: 0 });
+        }
+
+        let held = env.agents[&id]
+            .inventory_summary()
+            .key_count(DoorKeyType::Red);
+        let left = env.items[Position { x: 3, y: 1 }].clone();
+        if allow {
+            assert_eq!((held, left), (2, None));
+        } else {
+            assert_eq!((held, left), (1, Some(red.clone())));
+        }
+    }
+}
+
+#[test]
+fn ordered_chips_are_only_collected_in_order() {
+    let map = "WL WL WL WL WL\n\
+               WL C2 ST C1 WL\n\
+               WL WL WL WL WL\n";
+    let (mut env, id) = load(map);
+    let chip_2 = Position { x: 1, y: 1 };
+
+    // Chip 2 first is refused and stays put
+    assert_eq!(
+        env.process_action(id, Action::Move { dx: -1, dy: 0 }),
+        ActionResult::Failure(ActionFailure::ChipOutOfOrder {
+            expected: 1,
+            found: 2
+        })
+    );
+    assert_eq!(env.items[chip_2], Some(Item::OrderedChip { order: 2 }));
+    assert_eq!(env.agents[&id].chip_count(), 0);
+
+    for dx in [1, -1, -1] {
+        assert_eq!(
+            env.process_action(id, Action::Move { dx, dy: 0 }),
+            ActionResult::Success
+        );
+    }
+    assert_eq!(env.items[chip_2], None);
+    assert_eq!(env.agents[&id].chip_count(), 2);
+}
+
+#[test]
+fn moves_off_the_edge_wrap_around_when_enabled() {
+    let map = "WL WL WL WL\n\
+               BL WL BL ST\n\
+               WL WL WL WL\n";
+    let east = Action::Move { dx: 1, dy: 0 };
+    let (mut bounded, id) = load(map);
+    assert_eq!(
+        bounded.process_action(id, east),
+        ActionResult::Failure(ActionFailure::OutOfBounds)
+    );
+
+    let (mut wrapping, id) = load(map);
+    wrapping.wrap_edges = true;
+    assert_eq!(wrapping.process_action(id, east), ActionResult::Success);
+    assert_eq!(wrapping.agents[&id].position, Position { x: 0, y: 1 });
+    assert_eq!(wrapping.agent_at(Position { x: 0, y: 1 }), Some(id));
+}
+
+#[test]
+fn chips_dropped_on_hazards_bridge_them() {
+    let map = "WL WL WL WL WL WL\n\
+               WL ST CH HZ PL WL\n\
+               WL WL WL WL WL WL\n";
+    let (mut env, id) = load(map);
+    let hazard = Position { x: 3, y: 1 };
+    let east = Action::Move { dx: 1, dy: 0 };
+
+    env.process_action(id, east);
+    assert_eq!(
+        env.process_action(id, Action::Drop { dx: 1, dy: 0 }),
+        ActionResult::Success
+    );
+    assert_eq!(env.terrain[hazard], CellType::Floor);
+    assert_eq!(env.items[hazard], None);
+    assert_eq!(env.agents[&id].chip_count(), 0);
+
+    assert_eq!(env.process_action(id, east), ActionResult::Success);
+    assert_eq!(env.process_action(id, east), ActionResult::Win);
+}
+
+#[test]
+fn interact_only_doors_open_by_interacting() {
+    let map = "WL WL WL WL WL\n\
+               WL ST DI PL WL\n\
+               WL WL WL WL WL\n";
+    let (mut env, id) = load(map);
+    let door = Position { x: 2, y: 1 };
+    let east = Action::Move { dx: 1, dy: 0 };
+
+    assert_eq!(
+        env.process_action(id, east),
+        ActionResult::Failure(ActionFailure::DoorClosed)
+    );
+    assert_eq!(env.agents[&id].position, Position { x: 1, y: 1 });
+
+    assert_eq!(
+        env.process_action(id, Action::Interact { dx: 1, dy: 0 }),
+        ActionResult::Success
+    );
+    assert!(matches!(
+        env.terrain[door],
+        CellType::Door { open: true, .. }
+    ));
+    assert_eq!(env.agents[&id].position, Position { x: 1, y: 1 });
+    assert_eq!(env.process_action(id, east), ActionResult::Success);
+    assert_eq!(env.process_action(id, east), ActionResult::Win);
+}
+
+#[test]
+fn agents_stop_moving_when_their_energy_runs_out() {
+    let map = "WL WL WL WL WL WL WL WL WL\n\
+               WL ST BL CH BL BL BL PL WL\n\
+               WL WL WL WL WL WL WL WL WL\n";
+    let (mut env, start) = load_environment_from_string(map).expect("map loads");
+    env.starting_energy = Some(3);
+    env.chip_energy = 2;
+    let id = add_agent(&mut env, start);
+    let east = Action::Move { dx: 1, dy: 0 };
+
+    // Five steps paid for by the starting energy and the chip, one short of the goal
+    for _ in 0..5 {
+        assert_eq!(env.process_action(id, east), ActionResult::Success);
+    }
+    assert_eq!(env.agents[&id].energy, Some(0));
+    assert_eq!(
+        env.process_action(id, east),
+        ActionResult::Failure(ActionFailure::OutOfEnergy)
+    );
+    assert_eq!(env.agents[&id].position, Position { x: 6, y: 1 });
+    assert_eq!(env.process_action(id, Action::Wait), ActionResult::Success);
+}
@@ -0,0 +1,245 @@
+//! Checks the behavior of the built-in agents on small contrived maps.
+
+use std::collections::HashSet;
+
+use agent_world_core::{
+    EntityId, Position,
+    agent::{
+        Agent, BiasedWalker, CautiousPlanningAgent, GhostAgent, HumanAgent, HumanInput,
+        PatrolAgent, PlanningAgent, RandomWalker,
+    },
+    environment::{Action, ActionResult, Environment, load_environment_from_string},
+};
+
+/// Loads `map` with the agent made by `behavior` on its start.
+fn load(map: &str, behavior: impl FnOnce(EntityId) -> Box<dyn Agent>) -> (Environment, EntityId) {
+    let (mut env, start) = load_environment_from_string(map).expect("map loads");
+    let id = env.reserve_entity_id();
+    env.add_agent(start, behavior(id), vec![])
+        .expect("agent is placed");
+    (env, id)
+}
+
+/// Processes turns until the run ends, giving up after `turns`.
+fn play(env: &mut Environment, turns: usize) -> ActionResult {
+    for _ in 0..turns {
+        match env.process_turn() {
+            ActionResult::Success | ActionResult::Failure(_) => {}
+            result => return result,
+        }
+    }
+    ActionResult::Success
+}
+
+#[test]
+fn cautious_agent_goes_around_hazards() {
+    // The short way to the goal leads over the hazard
+    let map = "WL WL WL WL WL\n\
+               WL ST HZ PL WL\n\
+               WL BL BL BL WL\n\
+               WL WL WL WL WL\n";
+    let (mut plain, _) = load(map, |id| Box::new(PlanningAgent::new(id)));
+    assert_eq!(play(&mut plain, 10), ActionResult::Lose);
+
+    let (mut cautious, _) = load(map, |id| Box::new(CautiousPlanningAgent::new(id)));
+    assert_eq!(play(&mut cautious, 10), ActionResult::Win);
+}
+
+#[test]
+fn cautious_agent_sees_patrollers_across_wrapping_edges() {
+    // The goal is closest across the left edge, next to a patroller standing still
+    let map = "WL WL WL WL WL BL\n\
+               BL ST BL BL BL PL\n\
+               WL WL WL WL WL WL\n";
+    let (mut env, id) = load(map, |id| Box::new(CautiousPlanningAgent::new(id)));
+    env.wrap_edges = true;
+    let patroller = env.reserve_entity_id();
+    env.add_patroller(
+        Position { x: 5, y: 0 },
+        Box::new(PatrolAgent::new(patroller, vec![])),
+    )
+    .expect("patroller is placed");
+
+    env.step_agent(id);
+    assert_eq!(env.agents[&id].position, Position { x: 1, y: 1 });
+}
+
+#[test]
+fn planner_targets_the_chip_it_plans_to() {
+    let map = "WL WL WL WL WL WL\n\
+               WL ST BL BL CH WL\n\
+               WL BL BL BL PL WL\n\
+               WL WL WL WL WL WL\n";
+    let (mut env, id) = load(map, |id| Box::new(PlanningAgent::new(id)));
+    let target = |env: &Environment| env.agent_behaviors[&id].current_target();
+    assert_eq!(target(&env), None);
+
+    env.step_agent(id);
+    assert_eq!(target(&env), Some(Position { x: 4, y: 1 }));
+}
+
+/// Counts the distinct cells the agent made by `behavior` visits in `steps` steps around
+/// an empty room.
+fn coverage(steps: usize, behavior: impl FnOnce(EntityId) -> Box<dyn Agent>) -> usize {
+    let map: String = (0..12)
+        .map(|y| {
+            let row: Vec<&str> = (0..12)
+                .map(|x| match (x, y) {
+                    (0 | 11, _) | (_, 0 | 11) => "WL",
+                    (1, 1) => "ST",
+                    _ => "BL",
+                })
+                .collect();
+            row.join(" ") + "\n"
+        })
+        .collect();
+    let (mut env, id) = load(&map, behavior);
+    let mut visited = HashSet::new();
+    for _ in 0..steps {
+        env.step_agent(id);
+        visited.insert(env.agents[&id].position);
+    }
+    visited.len()
+}
+
+#[test]
+fn biased_walker_covers_more_ground_than_a_random_one() {
+    for seed in 0..5 {
+        let random = coverage(200, |id| Box::new(RandomWalker::new(id, seed)));
+        let biased = coverage(200, |id| Box::new(BiasedWalker::new(id, seed)));
+        assert!(biased > random, "seed {seed}: {biased} <= {random}");
+    }
+}
+
+/// Adds a patroller walking `route` to `env`, starting on the route's first waypoint.
+fn add_patroller(env: &mut Environment, route: Vec<Position>) {
+    let id = env.reserve_entity_id();
+    env.add_patroller(route[0], Box::new(PatrolAgent::new(id, route)))
+        .expect("patroller is placed");
+}
+
+#[test]
+fn patrollers_catch_agents_that_do_not_avoid_them() {
+    // A patroller paces between the corridor to the goal and the far corner
+    let map = "WL WL WL WL WL WL WL\n\
+               WL ST BL BL BL PL WL\n\
+               WL BL BL BL BL BL WL\n\
+               WL BL BL BL BL BL WL\n\
+               WL BL BL BL BL BL WL\n\
+               WL WL WL WL WL WL WL\n";
+    let route = vec![Position { x: 3, y: 1 }, Position { x: 1, y: 4 }];
+
+    // An agent standing still is walked into
+    let (mut idle, _) = load(map, |id| {
+        Box::new(HumanAgent::new(id, HumanInput::default()))
+    });
+    add_patroller(
+        &mut idle,
+        vec![Position { x: 3, y: 1 }, Position { x: 1, y: 1 }],
+    );
+    assert_eq!(play(&mut idle, 10), ActionResult::Lose);
+
+    // The planner walks into its way, the cautious agent lets it pass first
+    let (mut plain, _) = load(map, |id| Box::new(PlanningAgent::new(id)));
+    add_patroller(&mut plain, route.clone());
+    assert_eq!(play(&mut plain, 20), ActionResult::Lose);
+
+    let (mut cautious, _) = load(map, |id| Box::new(CautiousPlanningAgent::new(id)));
+    add_patroller(&mut cautious, route);
+    assert_eq!(play(&mut cautious, 20), ActionResult::Win);
+}
+
+#[test]
+fn ghosts_replay_their_path_then_wait() {
+    let map = "WL WL WL WL WL\n\
+               WL ST BL BL WL\n\
+               WL BL BL BL WL\n\
+               WL WL WL WL WL\n";
+    let (mut env, _) = load(map, |id| {
+        Box::new(HumanAgent::new(id, HumanInput::default()))
+    });
+    let path = vec![
+        Position { x: 1, y: 2 },
+        Position { x: 2, y: 2 },
+        Position { x: 2, y: 2 },
+        Position { x: 3, y: 2 },
+        Position { x: 3, y: 1 },
+    ];
+    let ghost = env.reserve_entity_id();
+    env.add_ghost(path[0], Box::new(GhostAgent::new(ghost, path.clone())))
+        .expect("ghost is placed");
+
+    let mut visited = vec![env.agents[&ghost].position];
+    for _ in 0..6 {
+        env.process_turn();
+        visited.push(env.agents[&ghost].position);
+    }
+    let mut expected = path;
+    expected.extend([Position { x: 3, y: 1 }; 2]);
+    assert_eq!(visited, expected);
+}
+
+#[test]
+fn planner_decisions_are_timed_when_enabled() {
+    let map = "WL WL WL WL WL WL\n\
+               WL ST BL BL PL WL\n\
+               WL WL WL WL WL WL\n";
+    let (mut env, id) = load(map, |id| Box::new(PlanningAgent::new(id)));
+    env.process_turn();
+    assert!(env.decision_timings().is_none());
+
+    env.enable_decision_timing();
+    assert_eq!(play(&mut env, 10), ActionResult::Win);
+    let timing = &env.decision_timings().expect("timing is enabled")[&id];
+    assert_eq!(timing.decisions, 2);
+    assert!(timing.max <= timing.total);
+    assert!(timing.average() <= timing.max);
+}
+
+/// Plays 100 turns of `map` and returns how many of them the agent made by `behavior`
+/// spent waiting or failing to move.
+fn wasted_turns(map: &str, behavior: impl FnOnce(EntityId) -> Box<dyn Agent>) -> usize {
+    let (mut env, id) = load(map, behavior);
+    env.enable_action_history(100);
+    for _ in 0..100 {
+        env.process_turn();
+    }
+    env.agent_history(id)
+        .expect("history is on")
+        .filter(|(_, action, result)| {
+            *action == Action::Wait || matches!(result, ActionResult::Failure(_))
+        })
+        .count()
+}
+
+#[test]
+fn smart_walkers_waste_fewer_turns_against_walls() {
+    // A corridor, so most random moves run into a wall
+    let map = "WL WL WL WL WL WL WL WL\n\
+               WL ST BL BL BL BL BL WL\n\
+               WL WL WL WL WL WL WL WL\n";
+    for seed in 0..5 {
+        let plain = wasted_turns(map, |id| Box::new(RandomWalker::new(id, seed)));
+        let smart = wasted_turns(map, |id| Box::new(RandomWalker::new_smart(id, seed, 4)));
+        assert!(smart < plain, "seed {seed}: {smart} vs {plain}");
+    }
+}
+
+#[test]
+fn planner_does_not_set_out_for_goals_beyond_its_energy() {
+    let map = "WL WL WL WL WL WL WL WL WL\n\
+               WL ST BL CH BL BL BL PL WL\n\
+               WL WL WL WL WL WL WL WL WL\n";
+    let (mut env, start) = load_environment_from_string(map).expect("map loads");
+    env.starting_energy = Some(3);
+    env.chip_energy = 2;
+    let id = env.reserve_entity_id();
+    env.add_agent(start, Box::new(PlanningAgent::new(id)), vec![])
+        .expect("agent is placed");
+
+    // The chip is in reach, the goal four steps on from it isn't
+    assert_eq!(play(&mut env, 20), ActionResult::Success);
+    let agent = &env.agents[&id];
+    assert_eq!(agent.position, Position { x: 3, y: 1 });
+    assert_eq!(agent.energy, Some(3));
+}
@@ -0,0 +1,111 @@
+//! Round-trips grids through the compact binary format.
+#![cfg(feature = "binary")]
+
+use agent_world_core::{
+    DoorKeyType, Item,
+    environment::{CellType, load_environment_from_string},
+    map::{Grid, GridError},
+};
+
+/// Every kind of door, one per combination of the optional attributes.
+fn doors() -> Vec<CellType> {
+    let mut doors = Vec::new();
+    for open in [false, true] {
+        for auto_open in [false, true] {
+            for (door_type, auto_close_after, cost) in [
+                (None, None, None),
+                (Some(DoorKeyType::Blue), Some(3), None),
+                (None, None, Some(2)),
+                (Some(DoorKeyType::Yellow), Some(usize::MAX), Some(0)),
+            ] {
+                doors.push(CellType::Door {
+                    open,
+                    door_type,
+                    auto_close_after,
+                    cost,
+                    auto_open,
+                });
+            }
+        }
+    }
+    doors
+}
+
+#[test]
+fn terrain_round_trips() {
+    let mut cells = vec![
+        CellType::Floor,
+        CellType::Wall,
+        CellType::Unknown,
+        CellType::Hazard,
+        CellType::Void,
+        CellType::OneWay { direction: (1, 0) },
+        CellType::OneWay { direction: (0, -1) },
+    ];
+    cells.extend(doors());
+    let width = 5;
+    let height = cells.len().div_ceil(width);
+    let grid = Grid::from_generator(width, height, |x, y| {
+        cells.get(y * width + x).cloned().unwrap_or(CellType::Floor)
+    });
+
+    assert_eq!(Grid::<CellType>::from_bytes(&grid.to_bytes()), Ok(grid));
+}
+
+#[test]
+fn items_round_trip() {
+    let mut items = vec![
+        None,
+        Some(Item::Chip),
+        Some(Item::BonusChip { value: 5 }),
+        Some(Item::OrderedChip { order: 3 }),
+        Some(Item::Goal),
+    ];
+    items.extend(DoorKeyType::ALL.map(|key_type| Some(Item::Key { key_type })));
+    let grid = Grid::from_generator(items.len(), 1, |x, _| items[x].clone());
+
+    assert_eq!(Grid::<Option<Item>>::from_bytes(&grid.to_bytes()), Ok(grid));
+}
+
+#[test]
+fn bundled_map_round_trips() {
+    let map = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/../maps/map03.txt"))
+        .expect("map is readable");
+    let (env, _) = load_environment_from_string(&map).expect("map loads");
+
+    assert_eq!(
+        Grid::<CellType>::from_bytes(&env.terrain.to_bytes()).as_ref(),
+        Ok(&env.terrain)
+    );
+    assert_eq!(
+        Grid::<Option<Item>>::from_bytes(&env.items.to_bytes()).as_ref(),
+        Ok(&env.items)
+    );
+}
+
+#[test]
+fn malformed_data_is_rejected() {
+    let grid = Grid::from_generator(2, 1, |_, _| doors()[7].clone());
+    let bytes = grid.to_bytes();
+
+    let truncated = &bytes[..bytes.len() - 1];
+    assert!(matches!(
+        Grid::<CellType>::from_bytes(truncated),
+        Err(GridError::InvalidEncoding(_))
+    ));
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(matches!(
+        Grid::<CellType>::from_bytes(&trailing),
+        Err(GridError::InvalidEncoding(_))
+    ));
+
+    // The first cell's door flags follow the 16 byte header and its tag
+    let mut unknown_flags = bytes;
+    unknown_flags[17] |= 1 << 7;
+    assert!(matches!(
+        Grid::<CellType>::from_bytes(&unknown_flags),
+        Err(GridError::InvalidEncoding(_))
+    ));
+}
@@ -0,0 +1,101 @@
+//! Checks maps built programmatically with `MapBuilder`.
+
+use agent_world_core::{
+    DoorKeyType, Item, Position,
+    builder::{ItemPlacement, MapBuilder, PlacementStrategy, Side},
+    environment::CellType,
+};
+
+/// Shorthand for a position.
+fn at(x: usize, y: usize) -> Position {
+    Position { x, y }
+}
+
+#[test]
+fn room_is_walled_but_for_its_door() {
+    let (env, _) = MapBuilder::new(8, 7)
+        .room(1, 1, 5, 4, Side::Right, Some(DoorKeyType::Red))
+        .start(at(2, 2))
+        .build()
+        .expect("map builds");
+    let door = at(5, 3);
+
+    for x in 1..6 {
+        for y in 1..5 {
+            let cell = &env.terrain[at(x, y)];
+            if at(x, y) == door {
+                assert!(
+                    matches!(
+                        cell,
+                        CellType::Door {
+                            open: false,
+                            door_type: Some(DoorKeyType::Red),
+                            ..
+                        }
+                    ),
+                    "{cell:?}"
+                );
+            } else if x == 1 || x == 5 || y == 1 || y == 4 {
+                assert_eq!(*cell, CellType::Wall, "({x}, {y})");
+            } else {
+                assert_eq!(*cell, CellType::Floor, "({x}, {y})");
+            }
+        }
+    }
+    // Outside the room is untouched
+    assert_eq!(env.terrain[at(0, 0)], CellType::Floor);
+    assert_eq!(env.terrain[at(6, 3)], CellType::Floor);
+}
+
+#[test]
+fn scattered_chips_land_on_free_floor() {
+    let start = at(2, 2);
+    for strategy in [
+        PlacementStrategy::Uniform,
+        PlacementStrategy::Clustered {
+            clusters: 2,
+            radius: 1,
+        },
+    ] {
+        for seed in 0..5 {
+            let placement = ItemPlacement {
+                chips: 12,
+                key_colors: vec![DoorKeyType::Blue],
+                strategy,
+                seed,
+            };
+            let (env, _) = MapBuilder::new(10, 8)
+                .room(1, 1, 5, 4, Side::Right, None)
+                .start(start)
+                .scatter(&placement)
+                .build()
+                .expect("map builds");
+
+            let chips: Vec<Position> = env
+                .items
+                .positions()
+                .filter(|position| env.items[*position] == Some(Item::Chip))
+                .collect();
+            assert_eq!(chips.len(), 12, "{strategy:?} with seed {seed}");
+            for chip in chips {
+                assert_eq!(env.terrain[chip], CellType::Floor, "{chip:?}");
+                assert_ne!(chip, start);
+            }
+            let keys = env
+                .items
+                .count(|item| matches!(item, Some(Item::Key { .. })));
+            assert_eq!(keys, 1);
+        }
+    }
+
+    // Seven cells can't hold eight chips
+    let placement = ItemPlacement {
+        chips: 8,
+        ..ItemPlacement::default()
+    };
+    let crowded = MapBuilder::new(4, 2)
+        .start(at(0, 0))
+        .scatter(&placement)
+        .build();
+    assert!(crowded.is_err());
+}
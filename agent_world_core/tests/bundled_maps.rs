@@ -0,0 +1,50 @@
+//! Checks that every map shipped in `maps/` loads and can be solved by the planning agent.
+
+use std::path::{Path, PathBuf};
+
+use agent_world_core::{
+    agent::PlanningAgent, environment::load_environment_from_string, runner::run_map,
+};
+
+/// Turns the planning agent gets to solve each map.
+const TURN_BUDGET: usize = 1000;
+
+fn bundled_maps() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../maps");
+    let mut maps: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .unwrap_or_else(|err| panic!("failed to read {}: {}", dir.display(), err))
+        .map(|entry| entry.expect("readable directory entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    maps.sort();
+    assert!(!maps.is_empty(), "no maps found in {}", dir.display());
+    maps
+}
+
+#[test]
+fn bundled_maps_load() {
+    for path in bundled_maps() {
+        let map = std::fs::read_to_string(&path).expect("map is readable");
+        if let Err(err) = load_environment_from_string(&map) {
+            panic!("{} failed to load: {}", path.display(), err);
+        }
+    }
+}
+
+#[test]
+fn planning_agent_solves_bundled_maps() {
+    for path in bundled_maps() {
+        let map = std::fs::read_to_string(&path).expect("map is readable");
+        let (outcome, _) = run_map(&map, 0, TURN_BUDGET, |id, _| {
+            Box::new(PlanningAgent::new(id))
+        })
+        .unwrap_or_else(|err| panic!("{} failed to load: {}", path.display(), err));
+        assert!(
+            outcome.is_win(),
+            "{} not solved within {} turns: {:?}",
+            path.display(),
+            TURN_BUDGET,
+            outcome.result
+        );
+    }
+}
@@ -0,0 +1,50 @@
+//! Plays campaigns made of the maps shipped in `maps/`.
+
+use std::path::{Path, PathBuf};
+
+use agent_world_core::{
+    agent::PlanningAgent,
+    campaign::{Campaign, CampaignProgress},
+    runner::run_headless,
+};
+
+/// Returns the path of a map shipped in `maps/`.
+fn bundled_map(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../maps")
+        .join(name)
+}
+
+/// Solves the current map with the planning agent, returning true if it was won.
+fn win_current(campaign: &Campaign) -> bool {
+    let (mut env, start) = campaign.load_current().expect("map loads");
+    let id = env.reserve_entity_id();
+    env.add_agent(start, Box::new(PlanningAgent::new(id)), vec![])
+        .expect("agent is placed");
+    run_headless(&mut env, 1000).is_win()
+}
+
+#[test]
+fn two_map_campaign_advances_then_completes() {
+    let maps = vec![bundled_map("map01.txt"), bundled_map("map02.txt")];
+    let mut campaign = Campaign::new(maps.clone()).expect("campaign has maps");
+    assert_eq!((campaign.len(), campaign.current_index()), (2, 0));
+
+    assert!(win_current(&campaign));
+    assert_eq!(campaign.advance(), CampaignProgress::Next(maps[1].clone()));
+    assert_eq!(campaign.current_map(), maps[1]);
+    assert!(!campaign.is_complete());
+
+    assert!(win_current(&campaign));
+    assert_eq!(campaign.advance(), CampaignProgress::Complete);
+    assert!(campaign.is_complete());
+    assert_eq!(campaign.current_index(), 1);
+}
+
+#[test]
+fn campaigns_need_maps() {
+    assert!(Campaign::new(Vec::new()).is_err());
+
+    let campaign = Campaign::from_directory(&bundled_map("")).expect("directory is readable");
+    assert_eq!(campaign.current_map(), bundled_map("map01.txt"));
+}
@@ -0,0 +1,4 @@
+//! Helpers shared by the integration tests. Each test binary only uses some of them.
+#![allow(dead_code)]
+
+pub mod json;
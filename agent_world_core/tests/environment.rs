@@ -0,0 +1,821 @@
+//! Checks the state `Environment` keeps about its agents and the queries over it.
+
+mod common;
+
+use std::{
+    f64::consts::FRAC_PI_4,
+    sync::{Arc, Mutex},
+};
+
+use agent_world_core::{
+    DoorKeyType, EntityId, Item, KeyHierarchy, Position,
+    agent::{Agent, HumanAgent, HumanInput, PlanningAgent, RandomWalker},
+    blackboard::Blackboard,
+    environment::{
+        Action, ActionFailure, ActionResult, CellType, Environment, EnvironmentView, Hint,
+        Inventory, Observation, load_environment_from_string,
+    },
+    map::Grid,
+};
+use common::json;
+
+/// Loads `map` with an idle human agent carrying `inventory` on its start.
+fn load_with(map: &str, inventory: Vec<Item>) -> (Environment, EntityId) {
+    let (mut env, start) = load_environment_from_string(map).expect("map loads");
+    let id = env.reserve_entity_id();
+    env.add_agent(
+        start,
+        Box::new(HumanAgent::new(id, HumanInput::default())),
+        inventory,
+    )
+    .expect("agent is placed");
+    (env, id)
+}
+
+/// Loads `map` with a human agent on its start that takes `actions` on the next turns.
+fn load_scripted(map: &str, actions: &[Action]) -> (Environment, EntityId) {
+    let input = HumanInput::new();
+    for action in actions {
+        input.push(*action);
+    }
+    let (mut env, start) = load_environment_from_string(map).expect("map loads");
+    let id = env.reserve_entity_id();
+    env.add_agent(start, Box::new(HumanAgent::new(id, input)), vec![])
+        .expect("agent is placed");
+    (env, id)
+}
+
+/// Shorthand for a position.
+fn at(x: usize, y: usize) -> Position {
+    Position { x, y }
+}
+
+/// A single floor cell to start on.
+const CELL: &str = "WL WL WL\n\
+                    WL ST WL\n\
+                    WL WL WL\n";
+
+#[test]
+fn inventory_summary_counts_mixed_items() {
+    let red = Item::Key {
+        key_type: DoorKeyType::Red,
+    };
+    let blue = Item::Key {
+        key_type: DoorKeyType::Blue,
+    };
+    let (env, id) = load_with(
+        CELL,
+        vec![
+            Item::Chip,
+            red.clone(),
+            Item::BonusChip { value: 4 },
+            blue,
+            Item::Chip,
+            red,
+        ],
+    );
+
+    let summary = env.agents[&id].inventory_summary();
+    assert_eq!(summary.chips, 6);
+    assert_eq!(summary.key_count(DoorKeyType::Red), 2);
+    assert_eq!(summary.key_count(DoorKeyType::Blue), 1);
+    assert_eq!(summary.key_count(DoorKeyType::Green), 0);
+    assert_eq!(summary.keys.len(), 2);
+}
+
+#[test]
+fn agent_lookups_follow_moves() {
+    let map = "WL WL WL WL WL\n\
+               WL ST BL BL WL\n\
+               WL WL WL WL WL\n";
+    let (mut env, id) = load_with(map, vec![]);
+    assert_eq!(env.agent_at(at(1, 1)), Some(id));
+
+    env.process_action(id, Action::Move { dx: 1, dy: 0 });
+    assert_eq!(env.agent_at(at(1, 1)), None);
+    assert_eq!(env.agent_at(at(2, 1)), Some(id));
+    assert_eq!(env.agent_at(at(9, 9)), None);
+
+    assert_eq!(env.agents_in_region(at(3, 2), at(2, 0)), vec![id]);
+    assert!(env.agents_in_region(at(0, 0), at(1, 2)).is_empty());
+    assert_eq!(env.agents_in_region(at(0, 0), at(99, 99)), vec![id]);
+}
+
+#[test]
+fn all_doors_lists_open_and_closed_doors() {
+    let map = "WL WL WL WL WL WL WL\n\
+               WL ST KR DR BL DB WL\n\
+               WL WL WL WL WL WL WL\n";
+    let (mut env, id) = load_with(map, vec![]);
+    let closed = vec![
+        (at(3, 1), false, Some(DoorKeyType::Red)),
+        (at(5, 1), false, Some(DoorKeyType::Blue)),
+    ];
+    assert_eq!(env.all_doors(), closed);
+
+    // Picking up the red key and walking through opens the red door
+    for _ in 0..2 {
+        env.process_action(id, Action::Move { dx: 1, dy: 0 });
+    }
+    assert_eq!(
+        env.all_doors(),
+        vec![
+            (at(3, 1), true, Some(DoorKeyType::Red)),
+            (at(5, 1), false, Some(DoorKeyType::Blue)),
+        ]
+    );
+}
+
+#[test]
+fn reset_restores_the_loaded_state() {
+    let map = "WL WL WL WL WL WL WL\n\
+               WL ST CH KR DR PL WL\n\
+               WL WL WL WL WL WL WL\n";
+    let (mut env, start) = load_environment_from_string(map).expect("map loads");
+    let id = env.reserve_entity_id();
+    env.add_agent(start, Box::new(PlanningAgent::new(id)), vec![])
+        .expect("agent is placed");
+    let initial = env.snapshot();
+
+    for _ in 0..3 {
+        env.process_turn();
+    }
+    assert_ne!(env.snapshot(), initial);
+
+    env.reset_to_initial();
+    assert_eq!(env.snapshot(), initial);
+    assert_eq!(env.agent_behaviors[&id].current_target(), None);
+}
+
+#[test]
+fn visit_counts_follow_a_scripted_path() {
+    let map = "WL WL WL WL WL\n\
+               WL ST BL BL WL\n\
+               WL WL WL WL WL\n";
+    let (mut env, id) = load_with(map, vec![]);
+    assert_eq!(env.visit_counts(), None);
+    env.enable_visit_tracking();
+
+    // There and back again, bumping into the wall at the far end once
+    for dx in [1, 1, 1, -1, -1, 1] {
+        env.process_action(id, Action::Move { dx, dy: 0 });
+    }
+    let expected = Grid::from_generator(5, 3, |x, y| match (x, y) {
+        (1, 1) => 1,
+        (2, 1) => 3,
+        (3, 1) => 1,
+        _ => 0,
+    });
+    assert_eq!(env.visit_counts(), Some(&expected));
+}
+
+#[test]
+fn doors_close_again_after_their_delay() {
+    let map = "WL WL WL WL WL WL\n\
+               WL ST BL BL BL WL\n\
+               WL WL WL WL WL WL\n";
+    let door = at(2, 1);
+    let east = Action::Move { dx: 1, dy: 0 };
+    let west = Action::Move { dx: -1, dy: 0 };
+    // Whether the door is open after each turn of `actions`
+    let door_states = |actions: &[Action]| {
+        let (mut env, _) = load_scripted(map, actions);
+        env.terrain[door] = CellType::Door {
+            open: false,
+            door_type: None,
+            auto_close_after: Some(2),
+            cost: None,
+            auto_open: true,
+        };
+        (0..actions.len())
+            .map(|_| {
+                env.process_turn();
+                matches!(env.terrain[door], CellType::Door { open: true, .. })
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Stepping in opens the door for two turns, coming back opens it again
+    assert_eq!(
+        door_states(&[east, east, Action::Wait, west, west, Action::Wait]),
+        vec![true, false, false, true, false, false]
+    );
+    // It stays open while someone stands in it
+    assert_eq!(
+        door_states(&[east, Action::Wait, Action::Wait, east, Action::Wait]),
+        vec![true, true, true, false, false]
+    );
+}
+
+#[test]
+fn batch_placement_keeps_the_valid_items() {
+    let map = "WL WL WL WL WL\n\
+               WL ST BL BL WL\n\
+               WL WL WL WL WL\n";
+    let (mut env, _) = load_with(map, vec![]);
+
+    let failures = env
+        .add_items([
+            (at(2, 1), Item::Chip),
+            (at(0, 0), Item::Chip),
+            (at(3, 1), Item::Goal),
+            (at(1, 1), Item::Chip),
+            (at(2, 1), Item::Goal),
+            (at(9, 9), Item::Chip),
+        ])
+        .expect_err("some placements fail");
+
+    let failed: Vec<Position> = failures.iter().map(|(position, _)| *position).collect();
+    assert_eq!(failed, vec![at(0, 0), at(1, 1), at(2, 1), at(9, 9)]);
+    assert_eq!(env.items[at(2, 1)], Some(Item::Chip));
+    assert_eq!(env.items[at(3, 1)], Some(Item::Goal));
+    assert_eq!(env.items[at(1, 1)], None);
+
+    assert_eq!(env.add_items([]), Ok(()));
+}
+
+/// Walks east, recording what `observe` makes of its view before each step.
+struct Observer<T> {
+    id: EntityId,
+    observe: fn(&EnvironmentView) -> T,
+    seen: Arc<Mutex<Vec<T>>>,
+}
+
+impl<T> Clone for Observer<T> {
+    fn clone(&self) -> Self {
+        Observer {
+            id: self.id,
+            observe: self.observe,
+            seen: Arc::clone(&self.seen),
+        }
+    }
+}
+
+impl<T: 'static> Agent for Observer<T> {
+    fn id(&self) -> EntityId {
+        self.id
+    }
+
+    fn get_action(&mut self, view: &EnvironmentView) -> Action {
+        let observation = (self.observe)(view);
+        self.seen
+            .lock()
+            .expect("observations are readable")
+            .push(observation);
+        Action::Move { dx: 1, dy: 0 }
+    }
+
+    fn box_clone(&self) -> Box<dyn Agent> {
+        Box::new(self.clone())
+    }
+}
+
+/// Loads `map` with an `Observer` on its start, processes `turns` turns and returns the
+/// environment, the observer's ID and its observations.
+fn observe<T: 'static>(
+    map: &str,
+    turns: usize,
+    observe: fn(&EnvironmentView) -> T,
+) -> (Environment, EntityId, Vec<T>) {
+    let (mut env, start) = load_environment_from_string(map).expect("map loads");
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let id = env.reserve_entity_id();
+    let observer = Observer {
+        id,
+        observe,
+        seen: Arc::clone(&seen),
+    };
+    env.add_agent(start, Box::new(observer), vec![])
+        .expect("agent is placed");
+    for _ in 0..turns {
+        env.process_turn();
+    }
+    let seen = std::mem::take(&mut *seen.lock().expect("observations are readable"));
+    (env, id, seen)
+}
+
+#[test]
+fn vision_cone_faces_the_way_last_moved() {
+    let map = "BL BL BL BL BL BL BL\n\
+               BL BL ST BL BL BL BL\n\
+               BL BL BL BL BL BL BL\n";
+    let (env, id, cones) = observe(map, 2, |view| view.cells_in_cone(2, FRAC_PI_4));
+
+    assert_eq!(env.agents[&id].facing, (1, 0));
+    // From (3, 1), only the cells ahead within 45 degrees and two cells are seen
+    assert_eq!(cones[1], vec![at(4, 0), at(4, 1), at(5, 1), at(4, 2)]);
+}
+
+#[test]
+fn find_path_opens_doors_the_agent_has_keys_for() {
+    let map = "WL WL WL WL WL\n\
+               WL ST DR PL WL\n\
+               WL WL WL WL WL\n";
+    let red = Item::Key {
+        key_type: DoorKeyType::Red,
+    };
+    let (with_key, id) = load_with(map, vec![red]);
+    assert_eq!(
+        with_key.find_path(at(1, 1), at(3, 1), id),
+        Some(vec![at(1, 1), at(2, 1), at(3, 1)])
+    );
+
+    let (without_key, id) = load_with(map, vec![Item::Chip]);
+    assert_eq!(without_key.find_path(at(1, 1), at(3, 1), id), None);
+    assert_eq!(without_key.find_path(at(1, 1), at(1, 1), id + 1), None);
+}
+
+#[test]
+fn higher_keys_open_lower_doors() {
+    // Gold opens silver and bronze doors, silver opens bronze ones
+    let hierarchy = KeyHierarchy::default()
+        .with_rank(DoorKeyType::Yellow, DoorKeyType::Green)
+        .with_rank(DoorKeyType::Green, DoorKeyType::Red);
+    assert!(hierarchy.can_open(DoorKeyType::Yellow, DoorKeyType::Red));
+    assert!(!hierarchy.can_open(DoorKeyType::Red, DoorKeyType::Yellow));
+    assert!(!hierarchy.can_open(DoorKeyType::Yellow, DoorKeyType::Blue));
+
+    let map = "WL WL WL WL WL\n\
+               WL ST DR PL WL\n\
+               WL WL WL WL WL\n";
+    let gold = Item::Key {
+        key_type: DoorKeyType::Yellow,
+    };
+    let east = Action::Move { dx: 1, dy: 0 };
+
+    let (mut exact, id) = load_with(map, vec![gold.clone()]);
+    assert_eq!(exact.find_path(at(1, 1), at(3, 1), id), None);
+    assert_eq!(
+        exact.process_action(id, east),
+        ActionResult::Failure(ActionFailure::MissingKey(DoorKeyType::Red))
+    );
+
+    let (mut ranked, id) = load_with(map, vec![gold]);
+    ranked.key_hierarchy = hierarchy;
+    assert!(ranked.find_path(at(1, 1), at(3, 1), id).is_some());
+    assert_eq!(ranked.process_action(id, east), ActionResult::Success);
+    assert_eq!(ranked.agents[&id].position, at(2, 1));
+}
+
+#[test]
+fn environments_are_built_from_matching_grids() {
+    let terrain: Grid<CellType> = Grid::new(3, 1);
+    assert!(Environment::from_grids(terrain.clone(), Grid::new(3, 2)).is_err());
+    assert!(Environment::from_grids(terrain.clone(), Grid::new(2, 1)).is_err());
+
+    let items = Grid::from_generator(3, 1, |x, _| (x == 2).then_some(Item::Goal));
+    let mut env = Environment::from_grids(terrain, items).expect("grids match");
+    let id = env.reserve_entity_id();
+    env.add_agent(at(0, 0), Box::new(PlanningAgent::new(id)), vec![])
+        .expect("agent is placed");
+
+    assert_eq!(env.process_turn(), ActionResult::Success);
+    assert_eq!(env.process_turn(), ActionResult::Win);
+}
+
+#[test]
+fn chip_doors_open_for_enough_chips_and_take_them() {
+    let map = "WL WL WL WL WL\n\
+               WL ST $2 PL WL\n\
+               WL WL WL WL WL\n";
+    let east = Action::Move { dx: 1, dy: 0 };
+
+    let (mut poor, id) = load_with(map, vec![Item::Chip, Item::BonusChip { value: 5 }]);
+    assert_eq!(poor.find_path(at(1, 1), at(3, 1), id), None);
+    assert_eq!(
+        poor.process_action(id, east),
+        ActionResult::Failure(ActionFailure::NotEnoughChips(2))
+    );
+    assert_eq!(poor.agents[&id].spendable_chips(), 1);
+
+    let (mut rich, id) = load_with(map, vec![Item::Chip; 3]);
+    assert!(rich.find_path(at(1, 1), at(3, 1), id).is_some());
+    assert_eq!(rich.process_action(id, east), ActionResult::Success);
+    assert_eq!(rich.agents[&id].spendable_chips(), 1);
+    assert!(matches!(
+        rich.terrain[at(2, 1)],
+        CellType::Door { open: true, .. }
+    ));
+}
+
+#[test]
+fn view_turn_counts_processed_turns() {
+    let map = "ST BL BL BL BL\n";
+    let (env, _, turns) = observe(map, 4, |view| view.turn);
+    assert_eq!(turns, vec![0, 1, 2, 3]);
+    assert_eq!(env.turn, 4);
+}
+
+#[test]
+fn nearest_item_goes_by_path_length() {
+    // The chip just across the wall is a long walk away, the one down the corridor isn't
+    let map = "WL WL WL WL WL WL WL\n\
+               WL ST WL CH BL BL WL\n\
+               WL BL WL WL WL BL WL\n\
+               WL BL BL CH BL BL WL\n\
+               WL WL WL WL WL WL WL\n";
+    let (env, start) = load_environment_from_string(map).expect("map loads");
+
+    let nearest = env.nearest_item(start, |item| *item == Item::Chip);
+    assert_eq!(nearest, Some((at(3, 3), 4)));
+    assert_eq!(env.nearest_item(start, |item| *item == Item::Goal), None);
+}
+
+/// Waits, claiming the red key on the blackboard every turn.
+#[derive(Clone)]
+struct Claimant {
+    id: EntityId,
+}
+
+impl Agent for Claimant {
+    fn id(&self) -> EntityId {
+        self.id
+    }
+
+    fn get_action(&mut self, _view: &EnvironmentView) -> Action {
+        Action::Wait
+    }
+
+    fn write_blackboard(&mut self, blackboard: &mut Blackboard) {
+        blackboard.post(self.id, "red key", "mine");
+    }
+
+    fn box_clone(&self) -> Box<dyn Agent> {
+        Box::new(self.clone())
+    }
+}
+
+#[test]
+fn blackboard_posts_are_read_by_later_agents() {
+    let (mut env, start) = load_environment_from_string("ST BL BL BL\n").expect("map loads");
+    let claimant = env.reserve_entity_id();
+    env.add_agent(start, Box::new(Claimant { id: claimant }), vec![])
+        .expect("agent is placed");
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let id = env.reserve_entity_id();
+    let observer = Observer {
+        id,
+        observe: |view| {
+            view.blackboard
+                .get("red key")
+                .map(|message| (message.author, message.turn, message.body.clone()))
+        },
+        seen: Arc::clone(&seen),
+    };
+    env.add_agent(at(1, 0), Box::new(observer), vec![])
+        .expect("agent is placed");
+    env.process_turn();
+    env.process_turn();
+
+    assert_eq!(
+        *seen.lock().expect("observations are readable"),
+        vec![
+            Some((claimant, 0, "mine".to_string())),
+            Some((claimant, 1, "mine".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn bounding_boxes_frame_scattered_items_and_agents() {
+    let map = "BL BL BL BL BL BL\n\
+               BL CH BL BL BL BL\n\
+               BL BL ST BL KR BL\n\
+               BL BL BL PL BL BL\n";
+    let (mut env, _) = load_with(map, vec![]);
+    assert_eq!(env.items_bounding_box(), Some((at(1, 1), at(4, 3))));
+    assert_eq!(env.agents_bounding_box(), Some((at(2, 2), at(2, 2))));
+
+    let other = env.reserve_entity_id();
+    env.add_agent(
+        at(5, 0),
+        Box::new(HumanAgent::new(other, HumanInput::default())),
+        vec![],
+    )
+    .expect("agent is placed");
+    assert_eq!(env.agents_bounding_box(), Some((at(2, 0), at(5, 2))));
+
+    let (empty, _) = load_with("ST BL\n", vec![]);
+    assert_eq!(empty.items_bounding_box(), None);
+}
+
+#[test]
+fn cloned_environments_advance_independently() {
+    let map = "WL WL WL WL WL WL\n\
+               WL ST BL BL BL WL\n\
+               WL BL BL BL BL WL\n\
+               WL BL BL BL BL WL\n\
+               WL WL WL WL WL WL\n";
+    let (mut env, start) = load_environment_from_string(map).expect("map loads");
+    let id = env.reserve_entity_id();
+    env.add_agent(start, Box::new(RandomWalker::new(id, 7)), vec![])
+        .expect("agent is placed");
+    let before = env.snapshot();
+
+    let mut copy = env.clone();
+    for _ in 0..10 {
+        copy.process_turn();
+    }
+    assert_eq!(env.snapshot(), before);
+    assert_eq!(copy.snapshot().turn, 10);
+
+    // The walker was cloned along with its random state, so it takes the same steps
+    for _ in 0..10 {
+        env.process_turn();
+    }
+    assert_eq!(env.snapshot(), copy.snapshot());
+    assert_ne!(env.snapshot(), before);
+}
+
+#[test]
+fn can_enter_agrees_with_moving() {
+    let door = |open, door_type, cost, auto_open| CellType::Door {
+        open,
+        door_type,
+        auto_close_after: None,
+        cost,
+        auto_open,
+    };
+    let one_way = |dx, dy| CellType::OneWay {
+        direction: (dx, dy),
+    };
+    let targets = [
+        CellType::Floor,
+        CellType::Wall,
+        CellType::Hazard,
+        CellType::Void,
+        CellType::Unknown,
+        one_way(1, 0),
+        one_way(-1, 0),
+        one_way(0, 1),
+        door(false, Some(DoorKeyType::Red), None, true),
+        door(false, Some(DoorKeyType::Blue), None, true),
+        door(true, Some(DoorKeyType::Blue), None, true),
+        door(false, None, None, false),
+        door(false, None, Some(1), true),
+    ];
+    let map = "WL WL WL WL\n\
+               WL ST BL WL\n\
+               WL WL WL WL\n";
+    let (from, to) = (at(1, 1), at(2, 1));
+    let red = Item::Key {
+        key_type: DoorKeyType::Red,
+    };
+
+    for from_cell in [CellType::Floor, one_way(1, 0), one_way(0, 1)] {
+        for to_cell in &targets {
+            for inventory in [vec![Item::Chip], vec![Item::Chip, red.clone()]] {
+                let (mut env, id) = load_with(map, inventory);
+                env.terrain[from] = from_cell.clone();
+                env.terrain[to] = to_cell.clone();
+                let keys = env.usable_keys(id);
+
+                let allowed = env.can_enter(from, to, &keys);
+                // Interact-only doors are opened before walking through, as planners do
+                if let CellType::Door {
+                    auto_open: false, ..
+                } = to_cell
+                {
+                    env.process_action(id, Action::Interact { dx: 1, dy: 0 });
+                }
+                let moved = !matches!(
+                    env.process_action(id, Action::Move { dx: 1, dy: 0 }),
+                    ActionResult::Failure(_)
+                );
+                assert_eq!(allowed, moved, "{from_cell:?} to {to_cell:?} with {keys:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn action_history_records_the_latest_actions() {
+    let map = "WL WL WL WL\n\
+               WL ST BL WL\n\
+               WL WL WL WL\n";
+    let east = Action::Move { dx: 1, dy: 0 };
+    let (mut env, id) = load_scripted(map, &[east, east, Action::Wait]);
+    assert!(env.agent_history(id).is_none());
+    env.enable_action_history(2);
+    assert_eq!(env.agent_history(id).expect("history is on").count(), 0);
+
+    for _ in 0..3 {
+        env.process_turn();
+    }
+    let history: Vec<_> = env.agent_history(id).expect("history is on").collect();
+    assert_eq!(
+        history,
+        [
+            &(1, east, ActionResult::Failure(ActionFailure::Wall)),
+            &(2, Action::Wait, ActionResult::Success),
+        ]
+    );
+}
+
+#[test]
+fn hints_fetch_the_key_to_a_locked_goal() {
+    let map = "WL WL WL WL WL WL\n\
+               WL ST BL WL BL WL\n\
+               WL BL KR DR PL WL\n\
+               WL WL WL WL WL WL\n";
+    let (mut env, id) = load_with(map, vec![]);
+    assert_eq!(
+        env.next_hint(id),
+        Some(Hint::GoToKey(DoorKeyType::Red, at(2, 2)))
+    );
+
+    env.process_action(id, Action::Move { dx: 0, dy: 1 });
+    env.process_action(id, Action::Move { dx: 1, dy: 0 });
+    assert_eq!(env.next_hint(id), Some(Hint::OpenDoor(at(3, 2))));
+    env.process_action(id, Action::Move { dx: 1, dy: 0 });
+    assert_eq!(env.next_hint(id), Some(Hint::GoToGoal(at(4, 2))));
+}
+
+#[test]
+fn runs_time_out_when_the_countdown_reaches_zero() {
+    let (mut env, _) = load_with(CELL, vec![]);
+    assert_eq!(env.turns_remaining(), None);
+    env.max_turns = Some(3);
+    assert_eq!(env.turns_remaining(), Some(3));
+
+    for remaining in [2, 1] {
+        assert_eq!(env.process_turn(), ActionResult::Success);
+        assert_eq!(env.turns_remaining(), Some(remaining));
+    }
+    assert_eq!(env.process_turn(), ActionResult::TimedOut);
+    assert_eq!(env.turns_remaining(), Some(0));
+
+    // Nothing more is processed once the time is up
+    assert_eq!(env.process_turn(), ActionResult::TimedOut);
+    assert_eq!(env.snapshot().turn, 3);
+}
+
+#[test]
+fn observations_serialize_with_the_agents_state() {
+    let map = "WL WL WL WL WL\n\
+               WL ST CH BL WL\n\
+               WL WL WL WL WL\n";
+    let (_, id, observations) = observe(map, 2, |view| {
+        json::to_string(&view.to_observation(Some(1))).expect("observation serializes")
+    });
+
+    let last = observations.last().expect("the observer looked around");
+    assert!(last.contains(r#""position":{"x":2,"y":1}"#), "{last}");
+    assert!(last.contains(r#""inventory":["Chip"]"#), "{last}");
+
+    let observation: Observation = json::from_str(last).expect("observation deserializes");
+    assert_eq!(observation.agent.id, id);
+    assert_eq!(observation.agent.position, at(2, 1));
+    assert_eq!(observation.agent.chip_count(), 1);
+    assert_eq!(observation.origin, at(1, 0));
+}
+
+#[test]
+fn terrain_version_only_changes_with_the_terrain() {
+    let map = "WL WL WL WL WL WL\n\
+               WL ST KR DR BL WL\n\
+               WL WL WL WL WL WL\n";
+    let (_, _, versions) = observe(map, 4, |view| view.terrain_version);
+    // Picking up the key leaves the terrain alone, its door opening doesn't
+    assert_eq!(versions, [0, 0, 1, 1]);
+}
+
+#[test]
+fn inventories_answer_queries_and_give_up_keys() {
+    let red = Item::Key {
+        key_type: DoorKeyType::Red,
+    };
+    let mut inventory = Inventory::new();
+    assert!(inventory.is_empty());
+    for item in [
+        red.clone(),
+        Item::Chip,
+        Item::BonusChip { value: 3 },
+        red.clone(),
+        Item::Key {
+            key_type: DoorKeyType::Blue,
+        },
+    ] {
+        inventory.add(item);
+    }
+
+    assert_eq!(inventory.len(), 5);
+    assert_eq!(inventory.count_chips(), 4);
+    assert_eq!(inventory.count(&red), 2);
+    assert_eq!(
+        inventory.keys().collect::<Vec<_>>(),
+        [DoorKeyType::Red, DoorKeyType::Red, DoorKeyType::Blue]
+    );
+    assert!(inventory.has_key(DoorKeyType::Blue));
+    assert!(!inventory.has_key(DoorKeyType::Green));
+
+    assert!(inventory.take_key(DoorKeyType::Red));
+    assert!(inventory.take_key(DoorKeyType::Red));
+    assert!(!inventory.take_key(DoorKeyType::Red));
+    assert!(!inventory.has_key(DoorKeyType::Red));
+    assert!(inventory.remove(&Item::Chip));
+    assert!(!inventory.remove(&Item::Chip));
+    assert_eq!(
+        inventory.as_slice(),
+        [
+            Item::BonusChip { value: 3 },
+            Item::Key {
+                key_type: DoorKeyType::Blue
+            }
+        ]
+    );
+}
+
+#[test]
+fn collected_chips_respawn_after_their_delay() {
+    let map = "WL WL WL WL WL WL\n\
+               WL ST CH BL BL WL\n\
+               WL WL WL WL WL WL\n";
+    let east = Action::Move { dx: 1, dy: 0 };
+    let (mut env, id) = load_scripted(map, &[east, east, east]);
+    env.chip_respawn_delay = Some(3);
+    let chip = at(2, 1);
+
+    let mut seen = Vec::new();
+    for _ in 0..5 {
+        env.process_turn();
+        seen.push(env.items[chip].is_some());
+    }
+    // Collected in the first turn, which counts towards the delay
+    assert_eq!(seen, [false, false, true, true, true]);
+    assert_eq!(env.agents[&id].chip_count(), 1);
+}
+
+#[test]
+fn shared_keys_open_doors_for_teammates() {
+    // Agent A holds the key at the start, agent B stands at the door
+    let map = "WL WL WL WL WL WL\n\
+               WL ST BL DR PL WL\n\
+               WL WL WL WL WL WL\n";
+    let red = Item::Key {
+        key_type: DoorKeyType::Red,
+    };
+    for shared in [false, true] {
+        let (mut env, a) = load_with(map, vec![red.clone()]);
+        env.shared_inventory = shared;
+        let b = env.reserve_entity_id();
+        env.add_agent(
+            at(2, 1),
+            Box::new(HumanAgent::new(b, HumanInput::default())),
+            vec![],
+        )
+        .expect("agent is placed");
+
+        let found = env.find_path(at(2, 1), at(4, 1), b);
+        let result = env.process_action(b, Action::Move { dx: 1, dy: 0 });
+        if shared {
+            assert_eq!(found, Some(vec![at(2, 1), at(3, 1), at(4, 1)]));
+            assert_eq!(result, ActionResult::Success);
+            assert!(!env.agents[&a].inventory.has_key(DoorKeyType::Red));
+        } else {
+            assert_eq!(found, None);
+            assert_eq!(
+                result,
+                ActionResult::Failure(ActionFailure::MissingKey(DoorKeyType::Red))
+            );
+            assert!(env.agents[&a].inventory.has_key(DoorKeyType::Red));
+        }
+    }
+}
+
+#[test]
+fn closest_door_goes_by_path_length() {
+    // The blue door is nearer as the crow flies, but only reachable through the red one
+    let map = "WL WL WL WL WL WL WL\n\
+               WL ST WL DB BL BL WL\n\
+               WL BL WL WL WL BL WL\n\
+               WL BL BL DR BL BL WL\n\
+               WL WL WL WL WL WL WL\n";
+    let red = Item::Key {
+        key_type: DoorKeyType::Red,
+    };
+    let (mut env, id) = load_with(map, vec![red]);
+    let start = at(1, 1);
+    assert_eq!(
+        env.closest_door(start, false),
+        Some((at(3, 3), Some(DoorKeyType::Red)))
+    );
+    assert_eq!(env.closest_door(start, true), None);
+
+    for action in [
+        Action::Move { dx: 0, dy: 1 },
+        Action::Move { dx: 0, dy: 1 },
+        Action::Move { dx: 1, dy: 0 },
+        Action::Move { dx: 1, dy: 0 },
+    ] {
+        env.process_action(id, action);
+    }
+    assert_eq!(
+        env.closest_door(start, true),
+        Some((at(3, 3), Some(DoorKeyType::Red)))
+    );
+    assert_eq!(
+        env.closest_door(start, false),
+        Some((at(3, 1), Some(DoorKeyType::Blue)))
+    );
+}
@@ -0,0 +1,49 @@
+//! Drives an `ExternalAgent` with small shell scripts. Only built where `sh` exists.
+#![cfg(unix)]
+
+use std::{process::Command, time::Duration};
+
+use agent_world_core::{external::ExternalAgent, runner::run_map};
+
+/// Generous per-turn response timeout, so slow CI machines don't fall back to waiting.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn shell(script: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(script);
+    command
+}
+
+#[test]
+fn scripted_agent_reaches_the_goal() {
+    // Steps right after reading each observation
+    let script = r#"while read line; do [ "$line" = end ] && echo "move 1 0"; done"#;
+    let (outcome, _) = run_map("ST BL PL", 0, 10, |id, _| {
+        Box::new(ExternalAgent::spawn(id, shell(script), TIMEOUT).expect("sh starts"))
+    })
+    .expect("map loads");
+    assert!(outcome.is_win(), "{:?}", outcome.result);
+    assert_eq!(outcome.turns, 2);
+}
+
+#[test]
+fn malformed_responses_wait() {
+    let script = r#"while read line; do [ "$line" = end ] && echo "jump"; done"#;
+    let (outcome, snapshot) = run_map("ST BL PL", 0, 3, |id, _| {
+        Box::new(ExternalAgent::spawn(id, shell(script), TIMEOUT).expect("sh starts"))
+    })
+    .expect("map loads");
+    assert!(!outcome.is_win());
+    assert_eq!(snapshot.agents[0].position.x, 0);
+}
+
+#[test]
+fn silent_programs_time_out() {
+    let (outcome, snapshot) = run_map("ST BL PL", 0, 2, |id, _| {
+        let agent = ExternalAgent::spawn(id, shell("cat > /dev/null"), Duration::from_millis(50));
+        Box::new(agent.expect("sh starts"))
+    })
+    .expect("map loads");
+    assert_eq!(outcome.turns, 2);
+    assert_eq!(snapshot.agents[0].position.x, 0);
+}
@@ -0,0 +1,151 @@
+//! Checks `Grid` and `Position` on their own, without an environment.
+
+use agent_world_core::{
+    Offset, Position,
+    map::{Grid, GridError},
+};
+
+/// Shorthand for a position.
+fn at(x: usize, y: usize) -> Position {
+    Position { x, y }
+}
+
+/// A grid whose cells hold their own row-major index.
+fn numbered(width: usize, height: usize) -> Grid<usize> {
+    Grid::from_generator(width, height, |x, y| y * width + x)
+}
+
+#[test]
+fn swap_and_replace_exchange_cells() {
+    let mut grid = numbered(3, 2);
+    grid.swap(at(0, 0), at(2, 1))
+        .expect("positions are in bounds");
+    assert_eq!((grid[at(0, 0)], grid[at(2, 1)]), (5, 0));
+
+    assert_eq!(grid.replace(at(1, 0), 10), Some(1));
+    assert_eq!(grid.replace(at(1, 0), 11), Some(10));
+    assert_eq!(grid[at(1, 0)], 11);
+
+    // Out of bounds positions leave the grid alone
+    let before = grid.clone();
+    assert!(matches!(
+        grid.swap(at(0, 0), at(3, 0)),
+        Err(GridError::OutOfBounds { x: 3, y: 0, .. })
+    ));
+    assert_eq!(grid.replace(at(0, 2), 12), None);
+    assert_eq!(grid, before);
+}
+
+#[test]
+fn distances_and_offsets_handle_edges() {
+    let origin = at(0, 0);
+    assert_eq!(origin.manhattan_distance(&origin), 0);
+    assert_eq!(origin.chebyshev_distance(&origin), 0);
+    assert_eq!(at(1, 5).manhattan_distance(&at(4, 1)), 7);
+    assert_eq!(at(1, 5).chebyshev_distance(&at(4, 1)), 4);
+    // Both are symmetric
+    assert_eq!(at(4, 1).manhattan_distance(&at(1, 5)), 7);
+    assert_eq!(at(4, 1).chebyshev_distance(&at(1, 5)), 4);
+
+    assert_eq!(origin.offset(0, 0), Some(origin));
+    assert_eq!(at(2, 3).offset(-2, 1), Some(at(0, 4)));
+    assert_eq!(origin.offset(-1, 0), None);
+    assert_eq!(origin.offset(0, -1), None);
+    assert_eq!(at(usize::MAX, 0).offset(1, 0), None);
+    assert_eq!(at(0, 5).offset(isize::MIN, 0), None);
+}
+
+#[test]
+fn neighbors_come_with_their_cells() {
+    let grid = numbered(3, 3);
+    let neighbors = |position, diagonal| -> Vec<(Position, usize)> {
+        grid.neighbors_of(position, diagonal)
+            .map(|(neighbor, cell)| (neighbor, *cell))
+            .collect()
+    };
+
+    assert_eq!(
+        neighbors(at(1, 1), false),
+        vec![(at(1, 2), 7), (at(1, 0), 1), (at(2, 1), 5), (at(0, 1), 3)]
+    );
+    assert_eq!(
+        neighbors(at(1, 1), true)[4..],
+        [(at(2, 2), 8), (at(2, 0), 2), (at(0, 2), 6), (at(0, 0), 0)]
+    );
+    // Neighbors off the grid are skipped
+    assert_eq!(
+        neighbors(at(0, 0), false),
+        vec![(at(0, 1), 3), (at(1, 0), 1)]
+    );
+    assert_eq!(neighbors(at(2, 2), true).len(), 3);
+}
+
+#[test]
+fn positions_subtract_to_offsets_and_add_them_back() {
+    let offset = at(1, 5) - at(4, 2);
+    assert_eq!(offset, Offset { dx: -3, dy: 3 });
+    assert_eq!(at(4, 2) + offset, Some(at(1, 5)));
+    assert_eq!(at(3, 3) - at(3, 3), Offset::default());
+    assert_eq!((at(0, 9) - at(5, 0)).signum(), Offset { dx: -1, dy: 1 });
+
+    // Offsets taking a coordinate below zero give no position
+    assert_eq!(at(2, 0) + offset, None);
+    assert_eq!(at(0, 0) + Offset { dx: 0, dy: -1 }, None);
+}
+
+#[test]
+fn degenerate_grids_are_empty() {
+    for (width, height) in [(0, 0), (0, 4), (4, 0)] {
+        let mut grid: Grid<u8> = Grid::new(width, height);
+        let size = format!("{width}x{height}");
+        assert_eq!(grid.enumerate().count(), 0, "{size}");
+        assert_eq!(grid.positions().count(), 0, "{size}");
+        assert_eq!(grid.rows().count(), 0, "{size}");
+        assert_eq!(grid.get(0, 0), None, "{size}");
+        assert_eq!(grid.get_mut(0, 0), None, "{size}");
+        assert_eq!(grid.index_to_coords(0), None, "{size}");
+        assert_eq!(grid.coords_to_index(0, 0), None, "{size}");
+        assert_eq!(grid.step(at(0, 0), 1, 0, true), None, "{size}");
+        assert_eq!(grid.find_position(|_| true), None, "{size}");
+        assert!(grid.set(0, 0, 1).is_err(), "{size}");
+    }
+}
+
+#[test]
+fn resizing_keeps_the_top_left_cells() {
+    let mut grid = numbered(2, 2);
+    grid.resize(3, 3, 9);
+    let grown = Grid::from_generator(3, 3, |x, y| if x < 2 && y < 2 { y * 2 + x } else { 9 });
+    assert_eq!(grid, grown);
+
+    grid.resize(1, 2, 9);
+    assert_eq!(grid, Grid::from_generator(1, 2, |_, y| y * 2));
+}
+
+#[test]
+fn rows_are_slices_of_the_cells() {
+    let grid = numbered(3, 2);
+    let rows: Vec<&[usize]> = grid.rows().collect();
+    assert_eq!(rows.len(), 2);
+    for (y, row) in rows.iter().enumerate() {
+        assert_eq!(row.len(), 3);
+        for (x, cell) in row.iter().enumerate() {
+            assert_eq!(grid.get(x, y), Some(cell));
+        }
+    }
+}
+
+#[test]
+fn count_and_find_position_scan_in_row_major_order() {
+    // Cells 0..12 of a 4x3 grid, multiples of 3 being 0, 3, 6 and 9
+    let grid = numbered(4, 3);
+    assert_eq!(grid.count(|cell| cell % 3 == 0), 4);
+    assert_eq!(grid.count(|cell| *cell > 100), 0);
+
+    assert_eq!(grid.find_position(|cell| cell % 3 == 0), Some(at(0, 0)));
+    assert_eq!(
+        grid.find_position(|cell| *cell > 4 && cell % 3 == 0),
+        Some(at(2, 1))
+    );
+    assert_eq!(grid.find_position(|cell| *cell > 100), None);
+}
@@ -0,0 +1,74 @@
+//! Captures what the environment logs through the `log` facade.
+#![cfg(feature = "log")]
+
+use std::sync::Mutex;
+
+use agent_world_core::{
+    agent::{HumanAgent, HumanInput},
+    environment::{Action, load_environment_from_string},
+};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Keeps every record logged, for the test to inspect.
+struct CapturingLogger {
+    records: Mutex<Vec<(Level, String)>>,
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        if let Ok(mut records) = self.records.lock() {
+            records.push((record.level(), record.args().to_string()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger {
+    records: Mutex::new(Vec::new()),
+};
+
+/// Returns and clears the records captured so far.
+fn take_records() -> Vec<(Level, String)> {
+    std::mem::take(&mut *LOGGER.records.lock().expect("records are readable"))
+}
+
+#[test]
+fn loading_and_acting_are_logged() {
+    log::set_logger(&LOGGER).expect("no other logger is set");
+    log::set_max_level(LevelFilter::Trace);
+
+    let (mut env, start) = load_environment_from_string("WL ST BL WL").expect("map loads");
+    assert_eq!(
+        take_records(),
+        vec![(
+            Level::Warn,
+            "Map has no goal ('PL') and cannot be won.".to_string()
+        )]
+    );
+
+    let id = env.reserve_entity_id();
+    env.add_agent(
+        start,
+        Box::new(HumanAgent::new(id, HumanInput::default())),
+        vec![],
+    )
+    .expect("agent is placed");
+    take_records();
+
+    env.process_action(id, Action::Move { dx: 1, dy: 0 });
+    let records = take_records();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].0, Level::Trace);
+    assert!(records[0].1.contains("moved"), "{}", records[0].1);
+
+    env.process_action(id, Action::Move { dx: 1, dy: 0 });
+    let records = take_records();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].0, Level::Debug);
+    assert!(records[0].1.contains("wall"), "{}", records[0].1);
+}
@@ -0,0 +1,173 @@
+//! Checks the reachability and key analyses `Environment` runs over whole maps.
+
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use agent_world_core::{
+    DoorKeyType, Item, Position,
+    environment::{CellType, Environment, MapWarning, load_environment_from_string},
+    glyph::cell_glyph,
+};
+
+/// Two rooms joined by a red door, the right one holding the goal.
+const TWO_ROOMS: &str = "WL WL WL WL WL WL WL\n\
+                         WL ST BL WL BL BL WL\n\
+                         WL BL KR DR BL PL WL\n\
+                         WL WL WL WL WL WL WL\n";
+
+/// Grows the set of cells reachable from `start` one step at a time until it stops
+/// changing, as a reference for the flood fills.
+fn brute_force_reachable(
+    env: &Environment,
+    start: Position,
+    keys: &HashSet<DoorKeyType>,
+) -> HashSet<Position> {
+    let mut reachable = HashSet::from([start]);
+    loop {
+        let next: HashSet<Position> = env
+            .terrain
+            .positions()
+            .filter(|position| {
+                reachable.contains(position)
+                    || (env.terrain[*position].is_passable_with(keys)
+                        && reachable
+                            .iter()
+                            .any(|other| other.manhattan_distance(position) == 1))
+            })
+            .collect();
+        if next.len() == reachable.len() {
+            return reachable;
+        }
+        reachable = next;
+    }
+}
+
+#[test]
+fn reachable_from_matches_a_brute_force_search() {
+    let (env, start) = load_environment_from_string(TWO_ROOMS).expect("map loads");
+    let locked = env.reachable_from(start, &HashSet::new());
+    assert_eq!(locked, brute_force_reachable(&env, start, &HashSet::new()));
+    assert_eq!(locked.len(), 4);
+    assert!(!locked.contains(&Position { x: 5, y: 2 }));
+
+    let keys = HashSet::from([DoorKeyType::Red]);
+    let unlocked = env.reachable_from(start, &keys);
+    assert_eq!(unlocked, brute_force_reachable(&env, start, &keys));
+    assert!(unlocked.contains(&Position { x: 5, y: 2 }));
+}
+
+/// A room full of red keys with the goal behind four red doors. Every subset of keys
+/// picked up is a distinct search state, far more than the search explores before
+/// giving up, although four keys would do.
+fn key_room() -> String {
+    let keys = |count| vec!["KR"; count].join(" ");
+    let walls = vec!["WL"; 22].join(" ");
+    format!(
+        "{walls}\n\
+         WL ST {} DR DR DR DR PL WL\n\
+         WL {} WL WL WL WL WL WL\n\
+         {walls}\n",
+        keys(14),
+        keys(15),
+    )
+}
+
+#[test]
+fn key_search_gives_up_on_maps_with_many_keys() {
+    let (mut env, start) = load_environment_from_string(&key_room()).expect("map loads");
+    env.allow_duplicate_keys = true;
+
+    let started = Instant::now();
+    assert_eq!(env.min_keys_required(start), None);
+    assert!(env.reachable_goals(start, &HashSet::new()).is_empty());
+    assert_eq!(env.validate(start), vec![MapWarning::ValidationIncomplete]);
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn locked_doors_split_regions_without_their_key() {
+    let (env, start) = load_environment_from_string(TWO_ROOMS).expect("map loads");
+    let door = Position { x: 3, y: 2 };
+    let goal = Position { x: 5, y: 2 };
+
+    let locked = env.terrain.connected_components(&HashSet::new());
+    assert!(locked[start].is_some() && locked[goal].is_some());
+    assert_ne!(locked[start], locked[goal]);
+    assert_eq!(locked[door], None);
+    assert_eq!(locked[Position { x: 0, y: 0 }], None);
+
+    let unlocked = env
+        .terrain
+        .connected_components(&HashSet::from([DoorKeyType::Red]));
+    assert_eq!(unlocked[start], unlocked[goal]);
+    assert_eq!(unlocked[door], unlocked[start]);
+}
+
+#[test]
+fn void_is_blank_and_never_reachable() {
+    let map = "-- WL WL WL --\n\
+               WL ST BL .. WL\n\
+               -- WL WL WL --\n";
+    let (env, start) = load_environment_from_string(map).expect("map loads");
+    let void = Position { x: 3, y: 1 };
+    assert_eq!(env.terrain[void], CellType::Void);
+    for symbols in [false, true] {
+        assert_eq!(cell_glyph(&env.terrain[void], symbols).text, " ");
+    }
+
+    let reachable = env.reachable_from(start, &HashSet::new());
+    assert_eq!(reachable, HashSet::from([start, Position { x: 2, y: 1 }]));
+}
+
+#[test]
+fn min_keys_counts_only_the_keys_needed() {
+    let (gated, start) = load_environment_from_string(TWO_ROOMS).expect("map loads");
+    assert_eq!(gated.min_keys_required(start), Some(1));
+
+    // A key lying about on an open map doesn't need picking up
+    let open = "WL WL WL WL WL\n\
+                WL ST KR PL WL\n\
+                WL BL BL BL WL\n\
+                WL WL WL WL WL\n";
+    let (open, start) = load_environment_from_string(open).expect("map loads");
+    assert_eq!(open.min_keys_required(start), Some(0));
+}
+
+#[test]
+fn goals_behind_unobtainable_doors_are_not_reachable() {
+    // The red door can be opened, the blue one never
+    let map = "WL WL WL WL WL WL WL\n\
+               WL ST KR DR PL BL WL\n\
+               WL BL WL WL WL DB PL\n\
+               WL WL WL WL WL WL WL\n";
+    let (env, start) = load_environment_from_string(map).expect("map loads");
+
+    assert_eq!(
+        env.reachable_goals(start, &HashSet::new()),
+        vec![Position { x: 4, y: 1 }]
+    );
+    assert_eq!(
+        env.reachable_goals(start, &HashSet::from([DoorKeyType::Blue])),
+        vec![Position { x: 4, y: 1 }, Position { x: 6, y: 2 }]
+    );
+}
+
+#[test]
+fn chips_sealed_off_from_the_start_are_reported() {
+    // The chip on the right is walled in; the one behind the red door can be reached
+    let map = "WL WL WL WL WL WL WL WL\n\
+               WL ST KR DR CH WL CH WL\n\
+               WL BL BL WL PL WL WL WL\n\
+               WL WL WL WL WL WL WL WL\n";
+    let (env, start) = load_environment_from_string(map).expect("map loads");
+
+    assert_eq!(
+        env.validate(start),
+        vec![MapWarning::UnreachableItem {
+            position: Position { x: 6, y: 1 },
+            item: Item::Chip,
+        }]
+    );
+}
@@ -0,0 +1,93 @@
+//! Checks what the map parser makes of well-formed maps and how it sizes them up.
+
+use agent_world_core::{
+    Position,
+    agent::RandomWalker,
+    environment::{Environment, load_environment_from_string, peek_map_dimensions},
+};
+
+#[test]
+fn comma_and_space_separated_maps_are_equal() {
+    let spaces = "WL WL WL WL WL\n\
+                  WL ST CH KG WL\n\
+                  WL BL DG PL WL\n\
+                  WL WL WL WL WL\n";
+    let commas = "WL,WL,WL,WL,WL\n\
+                  WL,ST,CH,KG,WL\n\
+                  WL,BL,DG,PL,WL\n\
+                  WL,WL,WL,WL,WL\n";
+    let mixed = "WL, WL,WL WL ,WL\n\
+                 WL ST,CH, KG WL\n\
+                 WL,BL DG,PL,WL\n\
+                 WL WL WL WL WL\n";
+
+    let (env, start) = load_environment_from_string(spaces).expect("map loads");
+    for map in [commas, mixed] {
+        let (other, other_start) = load_environment_from_string(map).expect("map loads");
+        assert_eq!(other_start, start, "{map}");
+        assert_eq!(other.snapshot(), env.snapshot(), "{map}");
+    }
+}
+
+#[test]
+fn peeking_at_dimensions_matches_loading() {
+    let map = "# seed: 3\n\
+               WL WL WL WL\n\
+               WL ST PL WL\n\
+               WL WL WL WL\n";
+    assert_eq!(peek_map_dimensions(map), Ok((4, 3)));
+    let (env, _) = load_environment_from_string(map).expect("map loads");
+    assert_eq!((env.terrain.width(), env.terrain.height()), (4, 3));
+
+    let ragged = "WL WL WL\n\
+                  WL ST PL WL\n";
+    let error = peek_map_dimensions(ragged).expect_err("rows differ in width");
+    assert_eq!(load_environment_from_string(ragged).err(), Some(error));
+    assert!(peek_map_dimensions("").is_err());
+}
+
+#[test]
+fn seeds_in_the_header_make_walks_repeatable() {
+    let map = "# seed: 42\n\
+               WL WL WL WL WL\n\
+               WL ST BL BL WL\n\
+               WL BL BL BL WL\n\
+               WL WL WL WL WL\n";
+    // Loads the map and lets a walker seeded from it wander for a while
+    let walk = || -> Environment {
+        let (mut env, start) = load_environment_from_string(map).expect("map loads");
+        let seed = env.seed.expect("map has a seed");
+        let id = env.reserve_entity_id();
+        env.add_agent(start, Box::new(RandomWalker::new(id, seed)), vec![])
+            .expect("agent is placed");
+        for _ in 0..20 {
+            env.process_turn();
+        }
+        env
+    };
+    assert_eq!(walk().seed, Some(42));
+    assert_eq!(walk().snapshot(), walk().snapshot());
+
+    let unseeded = map.trim_start_matches("# seed: 42\n");
+    let (env, _) = load_environment_from_string(unseeded).expect("map loads");
+    assert_eq!(env.seed, None);
+    assert!(load_environment_from_string(&map.replace("42", "many")).is_err());
+}
+
+#[test]
+fn zones_in_the_header_name_their_cells() {
+    let map = "# zone: vault 3 0 4 2\n\
+               # zone: door 3 1 3 1\n\
+               WL WL WL WL WL\n\
+               WL ST BL DR BL\n\
+               WL WL WL WL WL\n";
+    let (env, _) = load_environment_from_string(map).expect("map loads");
+    let at = |x, y| Position { x, y };
+    assert_eq!(env.zone_containing(at(4, 2)), Some("vault"));
+    // The smaller of two overlapping zones wins
+    assert_eq!(env.zone_containing(at(3, 1)), Some("door"));
+    assert_eq!(env.zone_containing(at(1, 1)), None);
+    assert_eq!(env.zone_containing(at(9, 9)), None);
+
+    assert!(load_environment_from_string(&map.replace("3 0 4 2", "3 0 4")).is_err());
+}
@@ -0,0 +1,96 @@
+//! Feeds random and adversarial strings to the map parser, which must always return
+//! `Ok` or an `Err` and never panic.
+
+use agent_world_core::environment::load_environment_from_string;
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+/// Valid map codes, so generated maps regularly get past tokenizing.
+const TOKENS: &[&str] = &[
+    "ST", "BL", "WL", "WA", "HZ", "..", "--", ">>", "<<", "^^", "vv", "DP", "PL", "CH", "CB", "DG",
+    "DY", "DB", "DR", "KG", "KY", "KB", "KR", "$0", "$1", "$9",
+];
+
+/// Separators and junk, including Unicode whitespace and malformed chip door costs.
+const NOISE: &[&str] = &[
+    " ",
+    ",",
+    ",,",
+    "\t",
+    "\n",
+    "\r\n",
+    "\u{a0}",
+    "\u{2003}",
+    "\u{3000}",
+    "\u{feff}",
+    "$",
+    "$-1",
+    "$99999999999999999999999",
+    "S",
+    "STST",
+    "é",
+    "🦀",
+    "\0",
+];
+
+fn random_map(rng: &mut StdRng) -> String {
+    let length = rng.random_range(0..200);
+    let mut map = String::new();
+    for _ in 0..length {
+        let piece = match rng.random_range(0..10) {
+            0..=5 => TOKENS.choose(rng).copied(),
+            6..=8 => NOISE.choose(rng).copied(),
+            _ => None,
+        };
+        match piece {
+            Some(piece) => map.push_str(piece),
+            // An arbitrary character
+            None => map.push(rng.random()),
+        }
+        if rng.random_bool(0.6) {
+            map.push(' ');
+        }
+    }
+    map
+}
+
+#[test]
+fn random_maps_never_panic() {
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..20_000 {
+        let map = random_map(&mut rng);
+        let _ = load_environment_from_string(&map);
+    }
+}
+
+#[test]
+fn malformed_maps_are_rejected() {
+    for map in [
+        "",
+        "   \n\t\n",
+        "\u{3000}\u{a0}",
+        ",,,",
+        "ST BL\nBL",
+        "ST\n\nBL",
+        "ST ST",
+        "BL BL",
+        "ST $",
+        "ST $-1",
+        "ST $99999999999999999999999",
+        "ST 🦀",
+    ] {
+        assert!(load_environment_from_string(map).is_err(), "{:?}", map);
+    }
+}
+
+#[test]
+fn large_maps_parse() {
+    let row = vec!["BL"; 2000].join(" ");
+    let map = format!(
+        "ST {}\n{}",
+        row,
+        vec![format!("BL {}", row); 499].join("\n")
+    );
+    let (environment, _) = load_environment_from_string(&map).expect("large map parses");
+    assert_eq!(environment.terrain.width(), 2001);
+    assert_eq!(environment.terrain.height(), 500);
+}
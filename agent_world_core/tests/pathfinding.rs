@@ -0,0 +1,192 @@
+//! Checks the A* pathfinder and the path measures built on it.
+
+use std::collections::HashSet;
+
+use agent_world_core::{
+    DoorKeyType, Position,
+    environment::{CellType, load_environment_from_string},
+    map::Grid,
+    pathfinding::{
+        CARDINAL_COST, DIAGONAL_COST, PathfindingConfig, PathfindingFailure, UnknownCellPolicy,
+        find_path, find_path_diagnosed, path_cost, path_turns,
+    },
+};
+
+/// Shorthand for a position.
+fn at(x: usize, y: usize) -> Position {
+    Position { x, y }
+}
+
+/// Finds a path on `terrain` without agents, keys or chips.
+fn path(
+    terrain: &Grid<CellType>,
+    start: Position,
+    goal: Position,
+    config: &PathfindingConfig,
+) -> Option<Vec<Position>> {
+    find_path(terrain, None, start, goal, &HashSet::new(), 0, config)
+}
+
+/// A `size` x `size` serpentine maze: every other row is a wall with a gap at
+/// alternating ends, so the only way from the top to the bottom winds through every row.
+fn serpentine(size: usize) -> Grid<CellType> {
+    Grid::from_generator(size, size, |x, y| {
+        let gap = if y % 4 == 1 { size - 1 } else { 0 };
+        if y % 2 == 1 && x != gap {
+            CellType::Wall
+        } else {
+            CellType::Floor
+        }
+    })
+}
+
+/// Counts the diagonal steps along `path`.
+fn diagonal_steps(path: &[Position]) -> usize {
+    path.windows(2)
+        .filter(|step| step[0].x != step[1].x && step[0].y != step[1].y)
+        .count()
+}
+
+#[test]
+fn diagonals_are_only_taken_when_shorter() {
+    let terrain: Grid<CellType> = Grid::new(5, 5);
+    let config = PathfindingConfig::with_diagonals();
+    let walk = |goal, config: &PathfindingConfig| {
+        let path = path(&terrain, at(0, 0), goal, config).expect("path exists");
+        (diagonal_steps(&path), path_cost(&terrain, &path, config))
+    };
+
+    assert_eq!(walk(at(3, 0), &config), (0, 3 * CARDINAL_COST));
+    assert_eq!(
+        walk(at(3, 1), &config),
+        (1, DIAGONAL_COST + 2 * CARDINAL_COST)
+    );
+    assert_eq!(walk(at(3, 3), &config), (3, 3 * DIAGONAL_COST));
+
+    // A diagonal dearer than two cardinal steps is never worth it
+    let dear = PathfindingConfig {
+        diagonal_cost: Some(2 * CARDINAL_COST + 5),
+        ..PathfindingConfig::default()
+    };
+    assert_eq!(walk(at(3, 3), &dear), (0, 6 * CARDINAL_COST));
+}
+
+#[test]
+fn unknown_cells_are_entered_only_when_optimistic() {
+    // The direct row is unexplored, the row below is known floor
+    let terrain = Grid::from_generator(5, 3, |x, y| match (x, y) {
+        (_, 0) => CellType::Wall,
+        (1..=3, 1) => CellType::Unknown,
+        _ => CellType::Floor,
+    });
+    let plan = |unknown_cells| {
+        let config = PathfindingConfig {
+            unknown_cells,
+            ..PathfindingConfig::default()
+        };
+        path(&terrain, at(0, 1), at(4, 1), &config).expect("path exists")
+    };
+
+    let optimistic = plan(UnknownCellPolicy::Optimistic);
+    assert_eq!(optimistic, (0..5).map(|x| at(x, 1)).collect::<Vec<_>>());
+
+    let pessimistic = plan(UnknownCellPolicy::Pessimistic);
+    assert_eq!(pessimistic.len(), 7);
+    assert!(pessimistic.iter().all(|p| terrain[*p] != CellType::Unknown));
+}
+
+#[test]
+fn one_way_passages_are_only_routed_through_forwards() {
+    // The one-way cell is a shortcut east; going west has to take the long way round
+    let terrain = Grid::from_generator(3, 3, |x, y| match (x, y) {
+        (1, 0) => CellType::OneWay { direction: (1, 0) },
+        (1, 1) => CellType::Wall,
+        _ => CellType::Floor,
+    });
+    let config = PathfindingConfig::default();
+
+    let east = path(&terrain, at(0, 0), at(2, 0), &config).expect("path exists");
+    assert_eq!(east, vec![at(0, 0), at(1, 0), at(2, 0)]);
+
+    let west = path(&terrain, at(2, 0), at(0, 0), &config).expect("path exists");
+    assert_eq!(west.len(), 7);
+    assert!(!west.contains(&at(1, 0)));
+
+    // Without the detour there is no way back at all
+    let corridor = Grid::from_generator(3, 1, |x, _| match x {
+        1 => CellType::OneWay { direction: (1, 0) },
+        _ => CellType::Floor,
+    });
+    assert_eq!(path(&corridor, at(2, 0), at(0, 0), &config), None);
+}
+
+#[test]
+fn tiny_expansion_limits_abort_the_search() {
+    let maze = serpentine(41);
+    let (start, goal) = (at(0, 0), at(0, 40));
+    assert!(path(&maze, start, goal, &PathfindingConfig::default()).is_some());
+
+    let limited = PathfindingConfig {
+        max_expansions: Some(10),
+        ..PathfindingConfig::default()
+    };
+    assert_eq!(path(&maze, start, goal, &limited), None);
+    assert_eq!(
+        find_path_diagnosed(&maze, None, start, goal, &HashSet::new(), 0, &limited),
+        Err(PathfindingFailure::ExpansionLimit)
+    );
+}
+
+#[test]
+fn straight_runs_across_wrapped_edges_count_once() {
+    let terrain: Grid<CellType> = Grid::new(5, 1);
+    let path = [at(3, 0), at(4, 0), at(0, 0), at(1, 0)];
+    let config = PathfindingConfig {
+        wrap_edges: true,
+        speed: 3,
+        ..PathfindingConfig::default()
+    };
+
+    assert_eq!(path_cost(&terrain, &path, &config), CARDINAL_COST);
+    assert_eq!(path_turns(&terrain, &path, 3, true), 1);
+    assert_eq!(path_turns(&terrain, &path, 1, true), 3);
+}
+
+#[test]
+fn failures_name_the_door_in_the_way() {
+    let config = PathfindingConfig::default();
+    // Finds a path from the start of `map` to (5, 1)
+    let diagnose = |map: &str, keys: &[DoorKeyType], chips| {
+        let (env, start) = load_environment_from_string(map).expect("map loads");
+        let keys: HashSet<DoorKeyType> = keys.iter().copied().collect();
+        find_path_diagnosed(&env.terrain, None, start, at(5, 1), &keys, chips, &config)
+    };
+
+    let red = "WL WL WL WL WL WL WL\n\
+               WL ST BL DR BL PL WL\n\
+               WL WL WL WL WL WL WL\n";
+    assert_eq!(
+        diagnose(red, &[], 0),
+        Err(PathfindingFailure::BlockedByDoor(
+            DoorKeyType::Red,
+            at(3, 1)
+        ))
+    );
+    assert!(diagnose(red, &[DoorKeyType::Red], 0).is_ok());
+
+    let paid = red.replace("DR", "$2");
+    assert_eq!(
+        diagnose(&paid, &[], 1),
+        Err(PathfindingFailure::NotEnoughChips {
+            cost: 2,
+            position: at(3, 1)
+        })
+    );
+    assert!(diagnose(&paid, &[], 2).is_ok());
+
+    let walled = red.replace("DR", "WL");
+    assert_eq!(
+        diagnose(&walled, &[DoorKeyType::Red], 0),
+        Err(PathfindingFailure::Unreachable)
+    );
+}
@@ -0,0 +1,115 @@
+//! Checks the headless runner and the tooling built on it.
+
+mod common;
+
+use std::cell::Cell;
+
+use agent_world_core::{
+    DoorKeyType, Position,
+    agent::{PlanningAgent, RandomWalker},
+    environment::{ActionFailure, ActionResult, load_environment_from_string},
+    runner::{AgentFactory, RunOutcome, compare_agents, run_headless, run_twice_and_compare},
+};
+use common::json;
+
+/// Reads a map shipped in `maps/`.
+fn bundled_map(name: &str) -> String {
+    let path = format!("{}/../maps/{}", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read_to_string(path).expect("map is readable")
+}
+
+#[test]
+fn runs_on_map01_are_deterministic() {
+    let map = bundled_map("map01.txt");
+
+    let outcome = run_twice_and_compare(&map, 0, 1000, |id, _| Box::new(PlanningAgent::new(id)))
+        .expect("planner runs agree");
+    assert!(outcome.is_win());
+
+    for seed in 0..3 {
+        run_twice_and_compare(&map, seed, 200, |id, seed| {
+            Box::new(RandomWalker::new(id, seed))
+        })
+        .expect("seeded walker runs agree");
+    }
+
+    // A walker seeded differently on every run is caught
+    let runs = Cell::new(0);
+    let unseeded = run_twice_and_compare(&map, 0, 200, |id, _| {
+        runs.set(runs.get() + 1);
+        Box::new(RandomWalker::new(id, runs.get()))
+    });
+    assert!(unseeded.is_err());
+}
+
+#[test]
+fn both_racers_finish_times_are_recorded() {
+    // The first agent is two steps from the goal, the second one three
+    let map = "WL WL WL WL WL WL WL WL\n\
+               WL ST BL PL BL BL BL WL\n\
+               WL WL WL WL WL WL WL WL\n";
+    let (mut env, start) = load_environment_from_string(map).expect("map loads");
+    let near = env.reserve_entity_id();
+    env.add_agent(start, Box::new(PlanningAgent::new(near)), vec![])
+        .expect("agent is placed");
+    let far = env.reserve_entity_id();
+    env.add_agent(
+        Position { x: 6, y: 1 },
+        Box::new(PlanningAgent::new(far)),
+        vec![],
+    )
+    .expect("agent is placed");
+
+    let outcome = run_headless(&mut env, 10);
+    assert!(outcome.is_win());
+    assert_eq!(outcome.turns, 3);
+    // The run goes on after the first agent finishes
+    assert_eq!(outcome.finish_turns, vec![(near, 2), (far, 3)]);
+}
+
+#[test]
+fn agent_comparisons_on_map01_are_deterministic() {
+    let map = bundled_map("map01.txt");
+    let agents: [AgentFactory; 2] = [
+        ("random", &|id, seed| Box::new(RandomWalker::new(id, seed))),
+        ("planning", &|id, _| Box::new(PlanningAgent::new(id))),
+    ];
+    let compare = || compare_agents(&[&map], &agents, 3, 300).expect("map loads");
+
+    let results = compare();
+    assert_eq!(results, compare());
+    assert_eq!(
+        results
+            .iter()
+            .map(|result| result.name.as_str())
+            .collect::<Vec<_>>(),
+        ["random", "planning"]
+    );
+    assert!(results.iter().all(|result| result.runs == 3));
+    assert_eq!(results[1].success_rate(), 1.0);
+    assert!(results[1].average_turns_to_win().is_some());
+}
+
+#[test]
+fn run_outcomes_survive_json() {
+    let map = bundled_map("map01.txt");
+    let (mut env, start) = load_environment_from_string(&map).expect("map loads");
+    let id = env.reserve_entity_id();
+    env.add_agent(start, Box::new(PlanningAgent::new(id)), vec![])
+        .expect("agent is placed");
+    let outcome = run_headless(&mut env, 1000);
+    assert!(outcome.is_win());
+    assert_eq!(json::round_trip(&outcome), Ok(outcome));
+
+    // Failures carry data of their own, though turns never end in one
+    let failed = RunOutcome {
+        result: ActionResult::Failure(ActionFailure::MissingKey(DoorKeyType::Red)),
+        turns: 2,
+        turn_results: vec![
+            ActionResult::Success,
+            ActionResult::Failure(ActionFailure::MissingKey(DoorKeyType::Red)),
+        ],
+        finish_turns: vec![],
+    };
+    assert_eq!(json::round_trip(&failed), Ok(failed));
+}
@@ -0,0 +1,131 @@
+//! Checks that environment snapshots survive serialization, including ones saved by
+//! older versions of the crate, and what diffs between snapshots report.
+
+mod common;
+
+use agent_world_core::{
+    DoorKeyType, EntityId, Item, Position,
+    agent::{HumanAgent, HumanInput},
+    environment::{
+        Action, AgentState, CellType, Change, Environment, EnvironmentSnapshot, Inventory,
+        SNAPSHOT_VERSION, load_environment_from_string,
+    },
+};
+use common::json;
+
+/// A version 1 snapshot: no version field, door timers, finish turns, chip counters or
+/// respawns, and no door `auto_open` flag or agent facing, speed and energy either.
+const VERSION_1: &str = r#"{
+    "turn": 3,
+    "terrain": {
+        "width": 3,
+        "height": 1,
+        "cells": [
+            "Wall",
+            {"Door": {"open": false, "door_type": "Red", "auto_close_after": null, "cost": null}},
+            "Floor"
+        ]
+    },
+    "items": {"width": 3, "height": 1, "cells": [null, null, "Goal"]},
+    "agents": [{"id": 0, "position": {"x": 2, "y": 0}, "inventory": ["Chip"]}]
+}"#;
+
+#[test]
+fn version_1_snapshots_load_with_defaults() {
+    let snapshot: EnvironmentSnapshot = json::from_str(VERSION_1).expect("snapshot loads");
+
+    assert_eq!(snapshot.version, 1);
+    assert_eq!(snapshot.turn, 3);
+    assert_eq!(
+        snapshot.terrain[Position { x: 1, y: 0 }],
+        CellType::Door {
+            open: false,
+            door_type: Some(DoorKeyType::Red),
+            auto_close_after: None,
+            cost: None,
+            auto_open: true,
+        }
+    );
+    assert_eq!(snapshot.items[Position { x: 2, y: 0 }], Some(Item::Goal));
+    assert!(snapshot.door_timers.is_empty());
+    assert!(snapshot.finished.is_empty());
+    assert_eq!(snapshot.ordered_chips_collected, 0);
+    assert_eq!(snapshot.keys_collected, 0);
+    assert!(snapshot.chip_respawns.is_empty());
+
+    let agent: &AgentState = &snapshot.agents[0];
+    assert_eq!(agent.position, Position { x: 2, y: 0 });
+    assert_eq!(agent.chip_count(), 1);
+    assert_eq!(agent.facing, AgentState::INITIAL_FACING);
+    assert_eq!(agent.speed, 1);
+    assert_eq!(agent.energy, None);
+}
+
+/// Loads `map` with an idle human agent on its start.
+fn load(map: &str) -> (Environment, EntityId) {
+    let (mut env, start) = load_environment_from_string(map).expect("map loads");
+    let id = env.reserve_entity_id();
+    env.add_agent(
+        start,
+        Box::new(HumanAgent::new(id, HumanInput::default())),
+        vec![],
+    )
+    .expect("agent is placed");
+    (env, id)
+}
+
+#[test]
+fn current_snapshots_round_trip() {
+    let map = "WL WL WL WL WL\n\
+               WL ST KR DR PL\n\
+               WL WL WL WL WL\n";
+    let (mut env, id) = load(map);
+    env.process_action(id, Action::Move { dx: 1, dy: 0 });
+
+    let snapshot = env.snapshot();
+    assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+    assert_eq!(json::round_trip(&snapshot), Ok(snapshot));
+}
+
+#[test]
+fn diffs_list_each_change_once() {
+    let map = "WL WL WL WL WL\n\
+               WL ST BL CH WL\n\
+               WL WL WL WL WL\n";
+    let (mut env, id) = load(map);
+    let start = env.snapshot();
+    assert!(start.diff(&start).is_empty());
+
+    env.process_action(id, Action::Move { dx: 1, dy: 0 });
+    let moved = env.snapshot();
+    assert_eq!(
+        start.diff(&moved),
+        [Change::AgentMoved {
+            id,
+            from: Position { x: 1, y: 1 },
+            to: Position { x: 2, y: 1 },
+        }]
+    );
+
+    env.process_action(id, Action::Move { dx: 1, dy: 0 });
+    let chip = Position { x: 3, y: 1 };
+    assert_eq!(
+        moved.diff(&env.snapshot()),
+        [
+            Change::ItemRemoved {
+                position: chip,
+                item: Item::Chip,
+            },
+            Change::AgentMoved {
+                id,
+                from: Position { x: 2, y: 1 },
+                to: chip,
+            },
+            Change::InventoryChanged {
+                id,
+                from: Inventory::new(),
+                to: Inventory::from(vec![Item::Chip]),
+            },
+        ]
+    );
+}
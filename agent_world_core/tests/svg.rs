@@ -0,0 +1,54 @@
+//! Checks the SVG export of maps.
+#![cfg(feature = "svg")]
+
+use agent_world_core::{
+    Position,
+    agent::{GhostAgent, HumanAgent, HumanInput},
+    environment::load_environment_from_string,
+    glyph,
+    svg::{CELL_SIZE, render_to_svg, write_svg},
+};
+
+const MAP: &str = "WL WL WL WL\nWL ST CH WL\nWL BL PL WL\n";
+
+#[test]
+fn image_has_one_square_per_cell() {
+    let (env, _) = load_environment_from_string(MAP).expect("map loads");
+    let path = std::env::temp_dir().join(format!("agent_world_svg_{}.svg", std::process::id()));
+    write_svg(&env, &path).expect("image is written");
+    let svg = std::fs::read_to_string(&path).expect("image is readable");
+    std::fs::remove_file(&path).ok();
+
+    let (width, height) = (4 * CELL_SIZE, 3 * CELL_SIZE);
+    assert!(svg.starts_with(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\""
+    )));
+    assert_eq!(svg.matches("<rect ").count(), 12);
+    assert!(svg.trim_end().ends_with("</svg>"));
+}
+
+#[test]
+fn agents_and_ghosts_use_the_shared_glyphs() {
+    let (mut env, start) = load_environment_from_string(MAP).expect("map loads");
+    let id = env.reserve_entity_id();
+    env.add_agent(
+        start,
+        Box::new(HumanAgent::new(id, HumanInput::default())),
+        vec![],
+    )
+    .expect("agent is placed");
+    let ghost = env.reserve_entity_id();
+    env.add_ghost(
+        Position { x: 1, y: 2 },
+        Box::new(GhostAgent::new(ghost, vec![])),
+    )
+    .expect("ghost is placed");
+    let svg = render_to_svg(&env);
+
+    let agent = glyph::agent_glyph(false);
+    assert!(svg.contains(&format!("font-weight=\"bold\">{}</text>", agent.text)));
+    let ghost = glyph::ghost_glyph();
+    assert!(ghost.dim);
+    assert!(svg.contains(&format!("opacity=\"0.5\">{}</text>", ghost.text)));
+    assert_eq!(svg.matches("</text>").count(), 4);
+}
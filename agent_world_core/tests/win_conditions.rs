@@ -0,0 +1,267 @@
+//! Checks when runs are won under `WinCondition::AllKeysCollected`,
+//! `WinCondition::DeliverToGoal` and custom `WinEvaluator`s.
+
+use agent_world_core::{
+    DoorKeyType, EntityId, Item, Position,
+    agent::{HumanAgent, HumanInput, PatrolAgent},
+    environment::{
+        Action, ActionFailure, ActionResult, Environment, GameStatus, WinCondition, WinEvaluator,
+        load_environment_from_string,
+    },
+};
+
+/// Loads `map` with the all-keys win condition and a human agent on its start.
+fn keys_map(map: &str) -> (Environment, EntityId, HumanInput) {
+    let (mut env, start) = load_environment_from_string(map).expect("map loads");
+    env.win_condition = WinCondition::AllKeysCollected;
+    let input = HumanInput::default();
+    let id = add_player(&mut env, start, &input);
+    (env, id, input)
+}
+
+fn add_player(env: &mut Environment, position: Position, input: &HumanInput) -> EntityId {
+    let id = env.reserve_entity_id();
+    env.add_agent(
+        position,
+        Box::new(HumanAgent::new(id, input.clone())),
+        vec![],
+    )
+    .expect("agent is placed");
+    id
+}
+
+/// Queues a step right and processes a turn.
+fn step_right(env: &mut Environment, input: &HumanInput) -> ActionResult {
+    input.push(Action::Move { dx: 1, dy: 0 });
+    env.process_turn()
+}
+
+#[test]
+fn collecting_the_last_key_wins() {
+    // The second key is of a type the agent already holds
+    let (mut env, _, input) = keys_map("WL WL WL WL WL\nWL ST KR KR WL\nWL WL WL WL WL\n");
+    assert_eq!(step_right(&mut env, &input), ActionResult::Success);
+    assert_eq!(step_right(&mut env, &input), ActionResult::Win);
+    assert_eq!(env.game_status(), GameStatus::Won);
+}
+
+#[test]
+fn chips_do_not_win_on_maps_without_keys() {
+    let (mut env, _, input) = keys_map("WL WL WL WL WL\nWL ST CH PL WL\nWL WL WL WL WL\n");
+    assert_eq!(step_right(&mut env, &input), ActionResult::Success);
+    assert_eq!(env.game_status(), GameStatus::InProgress);
+    assert_eq!(step_right(&mut env, &input), ActionResult::Win);
+}
+
+#[test]
+fn goals_do_not_win_while_keys_remain() {
+    let (mut env, _, input) = keys_map("WL WL WL WL WL\nWL ST PL KB WL\nWL WL WL WL WL\n");
+    assert_eq!(step_right(&mut env, &input), ActionResult::Success);
+    assert_eq!(step_right(&mut env, &input), ActionResult::Win);
+}
+
+#[test]
+fn collecting_the_last_key_ends_the_run_for_every_agent() {
+    let (mut env, collector, input) =
+        keys_map("WL WL WL WL\nWL ST KG WL\nWL BL BL WL\nWL WL WL WL\n");
+    let idle = add_player(&mut env, Position { x: 1, y: 2 }, &HumanInput::default());
+    assert_eq!(step_right(&mut env, &input), ActionResult::Win);
+    // Nobody reached a goal, so nobody is marked finished
+    assert!(!env.finished.contains_key(&collector));
+    assert!(!env.finished.contains_key(&idle));
+    assert_eq!(env.game_status(), GameStatus::Won);
+}
+
+#[test]
+fn finished_agents_are_not_stepped_again() {
+    let (mut env, start) =
+        load_environment_from_string("WL WL WL WL\nWL ST PL WL\nWL BL BL WL\nWL WL WL WL\n")
+            .expect("map loads");
+    let input = HumanInput::default();
+    let finisher = add_player(&mut env, start, &input);
+    add_player(&mut env, Position { x: 1, y: 2 }, &HumanInput::default());
+    input.push(Action::Move { dx: 1, dy: 0 });
+    assert_eq!(env.step_agent(finisher), ActionResult::Win);
+    assert_eq!(env.game_status(), GameStatus::InProgress);
+
+    input.push(Action::Move { dx: -1, dy: 0 });
+    assert_eq!(
+        env.step_agent(finisher),
+        ActionResult::Failure(ActionFailure::AgentFinished(finisher))
+    );
+    assert_eq!(input.len(), 1);
+}
+
+/// Wins once any agent stands on a given cell.
+#[derive(Clone)]
+struct ReachCell(Position);
+
+impl WinEvaluator for ReachCell {
+    fn evaluate(&self, env: &Environment) -> Option<ActionResult> {
+        env.agents
+            .values()
+            .any(|agent| agent.position == self.0)
+            .then_some(ActionResult::Win)
+    }
+
+    fn box_clone(&self) -> Box<dyn WinEvaluator> {
+        Box::new(self.clone())
+    }
+}
+
+#[test]
+fn custom_evaluator_wins_at_its_cell() {
+    let (mut env, start) =
+        load_environment_from_string("WL WL WL WL WL WL\nWL ST BL BL PL WL\nWL WL WL WL WL WL\n")
+            .expect("map loads");
+    env.win_evaluator = Some(Box::new(ReachCell(Position { x: 3, y: 1 })));
+    let input = HumanInput::default();
+    add_player(&mut env, start, &input);
+    assert_eq!(step_right(&mut env, &input), ActionResult::Success);
+    assert_eq!(env.game_status(), GameStatus::InProgress);
+    assert_eq!(step_right(&mut env, &input), ActionResult::Win);
+    assert_eq!(env.game_status(), GameStatus::Won);
+}
+
+/// Ends the run with a fixed result after any action.
+#[derive(Clone)]
+struct Always(ActionResult);
+
+impl WinEvaluator for Always {
+    fn evaluate(&self, _env: &Environment) -> Option<ActionResult> {
+        Some(self.0.clone())
+    }
+
+    fn box_clone(&self) -> Box<dyn WinEvaluator> {
+        Box::new(self.clone())
+    }
+}
+
+#[test]
+fn failed_actions_are_not_overridden_by_the_evaluator() {
+    let (mut env, start) =
+        load_environment_from_string("WL WL WL WL\nWL ST BL WL\nWL WL WL WL\n").expect("map loads");
+    env.win_evaluator = Some(Box::new(Always(ActionResult::Win)));
+    env.enable_action_history(4);
+    let input = HumanInput::default();
+    let id = add_player(&mut env, start, &input);
+
+    let blocked = Action::Move { dx: 0, dy: -1 };
+    let failure = ActionResult::Failure(ActionFailure::Wall);
+    assert_eq!(env.process_action(id, blocked), failure);
+    assert_eq!(
+        env.agent_history(id).expect("history is on").last(),
+        Some(&(0, blocked, failure))
+    );
+    assert_eq!(
+        env.process_action(id, Action::Move { dx: 1, dy: 0 }),
+        ActionResult::Win
+    );
+}
+
+#[test]
+fn only_agents_reaching_a_goal_are_marked_finished() {
+    let (mut env, start) =
+        load_environment_from_string("WL WL WL WL\nWL ST PL WL\nWL BL BL WL\nWL WL WL WL\n")
+            .expect("map loads");
+    env.win_evaluator = Some(Box::new(ReachCell(Position { x: 2, y: 1 })));
+    let input = HumanInput::default();
+    let player = add_player(&mut env, start, &input);
+    let id = env.reserve_entity_id();
+    let patroller = env
+        .add_patroller(
+            Position { x: 1, y: 2 },
+            Box::new(PatrolAgent::new(id, vec![Position { x: 2, y: 2 }])),
+        )
+        .expect("patroller is placed");
+
+    input.push(Action::Move { dx: 1, dy: 0 });
+    assert_eq!(env.step_agent(player), ActionResult::Win);
+    assert!(env.finished.contains_key(&player));
+
+    // The run is already won, but the patroller didn't reach a goal itself
+    assert_eq!(env.step_agent(patroller), ActionResult::Win);
+    assert!(!env.finished.contains_key(&patroller));
+}
+
+#[test]
+fn stepping_each_agent_matches_processing_a_turn() {
+    let map = "WL WL WL WL WL WL\nWL ST CH BL PL WL\nWL BL KR DR BL WL\nWL WL WL WL WL WL\n";
+    let (template, start) = load_environment_from_string(map).expect("map loads");
+    let setup = || {
+        let mut env = template.clone();
+        let inputs = [HumanInput::default(), HumanInput::default()];
+        let ids = [
+            add_player(&mut env, start, &inputs[0]),
+            add_player(&mut env, Position { x: 1, y: 2 }, &inputs[1]),
+        ];
+        for input in &inputs {
+            input.push(Action::Move { dx: 1, dy: 0 });
+        }
+        (env, ids)
+    };
+
+    let (mut stepped, [first, second]) = setup();
+    assert_eq!(stepped.step_agent(first), ActionResult::Success);
+    assert_eq!(stepped.step_agent(second), ActionResult::Success);
+    let (mut turned, _) = setup();
+    assert_eq!(turned.process_turn(), ActionResult::Success);
+
+    // Only the turn counter is left to `process_turn`
+    let mut snapshot = stepped.snapshot();
+    snapshot.turn += 1;
+    assert_eq!(snapshot, turned.snapshot());
+}
+
+#[test]
+fn goals_only_win_once_the_delivery_is_carried() {
+    let map = "WL WL WL WL WL\n\
+               WL ST PL KR WL\n\
+               WL WL WL WL WL\n";
+    let (mut env, start) = load_environment_from_string(map).expect("map loads");
+    env.win_condition = WinCondition::DeliverToGoal {
+        required: vec![Item::Key {
+            key_type: DoorKeyType::Red,
+        }],
+    };
+    let input = HumanInput::default();
+    let id = add_player(&mut env, start, &input);
+
+    // Walking over the goal empty-handed doesn't win
+    assert_eq!(step_right(&mut env, &input), ActionResult::Success);
+    assert_eq!(env.game_status(), GameStatus::InProgress);
+    assert_eq!(step_right(&mut env, &input), ActionResult::Success);
+
+    input.push(Action::Move { dx: -1, dy: 0 });
+    assert_eq!(env.process_turn(), ActionResult::Win);
+    assert_eq!(env.agents[&id].position, Position { x: 2, y: 1 });
+}
+
+#[test]
+fn game_status_follows_the_run_without_stepping_it() {
+    let map = "WL WL WL WL WL\n\
+               WL ST BL PL WL\n\
+               WL HZ WL WL WL\n\
+               WL WL WL WL WL\n";
+    let (mut env, start) = load_environment_from_string(map).expect("map loads");
+    let input = HumanInput::default();
+    add_player(&mut env, start, &input);
+    let mut lost = env.clone();
+
+    assert_eq!(env.game_status(), GameStatus::InProgress);
+    step_right(&mut env, &input);
+    assert_eq!(env.game_status(), GameStatus::InProgress);
+    assert_eq!(step_right(&mut env, &input), ActionResult::Win);
+    assert_eq!(env.game_status(), GameStatus::Won);
+
+    input.push(Action::Move { dx: 0, dy: 1 });
+    assert_eq!(lost.process_turn(), ActionResult::Lose);
+    assert_eq!(lost.game_status(), GameStatus::Lost);
+
+    // Walled off from the goal
+    let (mut stalled, start) =
+        load_environment_from_string(&map.replace("WL ST BL PL", "WL ST WL PL"))
+            .expect("map loads");
+    add_player(&mut stalled, start, &input);
+    assert_eq!(stalled.game_status(), GameStatus::Stalled);
+}
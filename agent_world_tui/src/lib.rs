@@ -0,0 +1,1366 @@
+//! The agent_world terminal UI: an `App` holding the simulation and its view state,
+//! and `run_app`, which draws it and feeds it key presses until the user quits.
+
+use agent_world_core::{
+    DoorKeyType, EntityId, Item, Position,
+    agent::{
+        Agent, BiasedWalker, CautiousPlanningAgent, HumanAgent, HumanInput, PlanningAgent,
+        RandomWalker,
+    },
+    campaign::{Campaign, CampaignProgress},
+    environment::{
+        Action, ActionFailure, ActionResult, AgentState, BONUS_CHIP_VALUE, CellType, Environment,
+        EnvironmentSnapshot, Hint, MapWarning, save_environment_to_string,
+    },
+    glyph::{self, Glyph, GlyphColor},
+    map::Grid,
+};
+use anyhow::Result;
+use clap::ValueEnum;
+use ratatui::{
+    crossterm::{
+        self,
+        event::{self, Event, KeyCode},
+    },
+    prelude::*,
+    widgets::*,
+};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    io::Stdout,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// The agent behaviors selectable in the TUI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AgentKind {
+    Planning,
+    Cautious,
+    Random,
+    Biased,
+    /// Controlled with the arrow keys
+    Human,
+}
+
+impl AgentKind {
+    /// Creates the agent behavior for this kind. Human agents take their moves from
+    /// `input`.
+    fn create(self, id: EntityId, seed: u64, input: &HumanInput) -> Box<dyn Agent> {
+        match self {
+            AgentKind::Planning => Box::new(PlanningAgent::new(id)),
+            AgentKind::Cautious => Box::new(CautiousPlanningAgent::new(id)),
+            AgentKind::Random => Box::new(RandomWalker::new(id, seed)),
+            AgentKind::Biased => Box::new(BiasedWalker::new(id, seed)),
+            AgentKind::Human => Box::new(HumanAgent::new(id, input.clone())),
+        }
+    }
+}
+
+/// Settings used to construct an `App`, independent of command line parsing.
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    /// Map file, directory of maps or `.campaign` manifest to load.
+    pub map_file: PathBuf,
+    /// Milliseconds between simulation steps.
+    pub tick_rate_ms: u64,
+    /// Agent behavior placed at the start position.
+    pub agent_kind: AgentKind,
+    /// Seed passed to the agent.
+    pub seed: u64,
+    /// Whether the simulation starts paused.
+    pub paused: bool,
+    /// Whether key/door types are rendered as symbols.
+    pub symbols: bool,
+    /// Whether walls are drawn with box-drawing characters.
+    pub box_walls: bool,
+    /// Turns allowed on each map, or `None` for no limit.
+    pub turn_limit: Option<usize>,
+}
+
+/// What the map editor paints onto the cell under the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Brush {
+    Floor,
+    Wall,
+    Hazard,
+    Door(DoorKeyType),
+    Key(DoorKeyType),
+    Chip,
+    BonusChip,
+    Goal,
+    /// Moves the start position to the cursor.
+    Start,
+}
+
+impl Brush {
+    /// Returns the brush bound to a key in edit mode, if any.
+    fn from_key(key: char) -> Option<Brush> {
+        Some(match key {
+            '.' => Brush::Floor,
+            'w' => Brush::Wall,
+            'x' => Brush::Hazard,
+            '1' => Brush::Door(DoorKeyType::Red),
+            '2' => Brush::Door(DoorKeyType::Green),
+            '3' => Brush::Door(DoorKeyType::Blue),
+            '4' => Brush::Door(DoorKeyType::Yellow),
+            'r' => Brush::Key(DoorKeyType::Red),
+            'g' => Brush::Key(DoorKeyType::Green),
+            'b' => Brush::Key(DoorKeyType::Blue),
+            'y' => Brush::Key(DoorKeyType::Yellow),
+            'c' => Brush::Chip,
+            'C' => Brush::BonusChip,
+            'o' => Brush::Goal,
+            't' => Brush::Start,
+            _ => return None,
+        })
+    }
+
+    /// Returns the terrain and item this brush leaves on a cell.
+    fn cell(self) -> (CellType, Option<Item>) {
+        match self {
+            Brush::Floor | Brush::Start => (CellType::Floor, None),
+            Brush::Wall => (CellType::Wall, None),
+            Brush::Hazard => (CellType::Hazard, None),
+            Brush::Door(key_type) => (
+                CellType::Door {
+                    open: false,
+                    door_type: Some(key_type),
+                    auto_close_after: None,
+                    cost: None,
+                    auto_open: true,
+                },
+                None,
+            ),
+            Brush::Key(key_type) => (CellType::Floor, Some(Item::Key { key_type })),
+            Brush::Chip => (CellType::Floor, Some(Item::Chip)),
+            Brush::BonusChip => (
+                CellType::Floor,
+                Some(Item::BonusChip {
+                    value: BONUS_CHIP_VALUE,
+                }),
+            ),
+            Brush::Goal => (CellType::Floor, Some(Item::Goal)),
+        }
+    }
+}
+
+/// State of the map editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Editor {
+    /// Cell the next brush stroke applies to.
+    cursor: Position,
+    /// Outcome of the last edit or save, shown in the status line.
+    message: Option<String>,
+}
+
+/// State of replay mode, which shows recorded states of the current map.
+struct Replay {
+    /// The environment as it was when replay started, restored when it ends.
+    live: Environment,
+    /// Index into `App::recording` of the state shown.
+    cursor: usize,
+}
+
+/// Maps a cell, the item on it (if any) and whether symbol mode is on to the text and
+/// style drawn for it.
+type TileRenderer = Box<dyn Fn(&CellType, Option<&Item>, bool) -> (String, Style)>;
+
+/// Styles patched over the base style of individual cells by `render_map`, e.g. to
+/// highlight visited cells, agent targets or the editor cursor.
+type Overlay = HashMap<Position, Style>;
+
+/// Patches `style` onto the overlay at `position`, on top of any style already there.
+fn tint(overlay: &mut Overlay, position: Position, style: Style) {
+    overlay
+        .entry(position)
+        .and_modify(|existing| *existing = existing.patch(style))
+        .or_insert(style);
+}
+
+/// The simulation being shown together with the state of the view around it.
+pub struct App {
+    /// The core simulation environment.
+    environment: Environment,
+    /// Where the agent starts on the current map.
+    start: Position,
+    /// Maps to play; winning the current map loads the next.
+    campaign: Campaign,
+    /// Agent behavior placed on each map.
+    agent_kind: AgentKind,
+    /// Seed passed to the agent on each map.
+    seed: u64,
+    /// Moves queued by the arrow keys for a human-controlled agent.
+    human_input: HumanInput,
+    /// Turns allowed on each map, or `None` for no limit.
+    turn_limit: Option<usize>,
+    /// Error from loading the next campaign map, if that ended the game.
+    load_error: Option<String>,
+    /// Problems found when validating the current map.
+    map_warnings: Vec<MapWarning>,
+    /// Flag to control the main loop.
+    should_quit: bool,
+    /// Flag to control if the game is over.
+    game_over: bool,
+    /// Flag to render key/door types as symbols rather than colors only.
+    symbols: bool,
+    /// Flag to draw walls as connected box-drawing lines rather than '#'.
+    box_walls: bool,
+    /// Flag to shade cells by how often agents visited them.
+    heatmap: bool,
+    /// Flag to show the legend panel next to the map.
+    legend: bool,
+    /// Flag to stop advancing the simulation.
+    paused: bool,
+    /// Remaining steps towards the objective queued by `advance_to_next_objective`.
+    objective: VecDeque<Position>,
+    /// Map editor state, if edit mode is on.
+    editor: Option<Editor>,
+    /// Hint requested with `show_hint`, cleared once the simulation moves on.
+    hint: Option<Hint>,
+    /// Snapshots of the current map, starting with its initial state and taken after
+    /// every step since.
+    recording: Vec<EnvironmentSnapshot>,
+    /// Replay mode state, if replaying.
+    replay: Option<Replay>,
+    /// Time between simulation steps.
+    tick_rate: Duration,
+    /// Draws terrain and items; agents and overlays are drawn on top by `render_map`.
+    /// Starts out as `default_tile_renderer`, replaced with `with_tile_renderer`.
+    tile_renderer: TileRenderer,
+}
+
+impl AppConfig {
+    /// Default settings for the given map file.
+    pub fn new(map_file: PathBuf) -> Self {
+        AppConfig {
+            map_file,
+            tick_rate_ms: 250,
+            agent_kind: AgentKind::Planning,
+            seed: 0,
+            paused: false,
+            symbols: false,
+            box_walls: false,
+            turn_limit: None,
+        }
+    }
+}
+
+impl App {
+    /// Creates an app running a planning agent on the given map with default settings.
+    pub fn new(map_file: PathBuf) -> Result<Self> {
+        Self::from_config(AppConfig::new(map_file))
+    }
+
+    /// Creates an app from the given settings.
+    pub fn from_config(config: AppConfig) -> Result<Self> {
+        let map_file = &config.map_file;
+        let campaign = if map_file.is_dir() {
+            Campaign::from_directory(map_file)
+        } else if map_file.extension().is_some_and(|ext| ext == "campaign") {
+            Campaign::from_manifest(map_file)
+        } else {
+            Campaign::new(vec![map_file.clone()])
+        }
+        .map_err(anyhow::Error::msg)?;
+        let human_input = HumanInput::new();
+        let (mut environment, start, map_warnings) =
+            load_level(&campaign, config.agent_kind, config.seed, &human_input)?;
+        environment.max_turns = config.turn_limit;
+
+        Ok(App {
+            recording: vec![environment.snapshot()],
+            replay: None,
+            environment,
+            start,
+            campaign,
+            agent_kind: config.agent_kind,
+            seed: config.seed,
+            human_input,
+            turn_limit: config.turn_limit,
+            load_error: None,
+            map_warnings,
+            should_quit: false,
+            game_over: false,
+            symbols: config.symbols,
+            box_walls: config.box_walls,
+            heatmap: false,
+            legend: false,
+            paused: config.paused,
+            objective: VecDeque::new(),
+            hint: None,
+            editor: None,
+            tick_rate: Duration::from_millis(config.tick_rate_ms),
+            tile_renderer: Box::new(default_tile_renderer),
+        })
+    }
+
+    /// Replaces the renderer used for terrain and items, e.g. to theme the map or draw
+    /// cell types the default renderer doesn't know about.
+    pub fn with_tile_renderer<F>(mut self, renderer: F) -> Self
+    where
+        F: Fn(&CellType, Option<&Item>, bool) -> (String, Style) + 'static,
+    {
+        self.tile_renderer = Box::new(renderer);
+        self
+    }
+
+    /// Handles one step of the simulation. While an objective is queued, the player agent
+    /// takes its next step towards it instead, even when paused.
+    fn tick(&mut self) {
+        if self.game_over || self.replay.is_some() {
+            return;
+        }
+        let result = match (self.objective.pop_front(), self.player()) {
+            (Some(target), Some(player)) => self.step_towards(player, target),
+            _ if self.paused => return,
+            _ => self.environment.process_turn(),
+        };
+        self.hint = None;
+        self.recording.push(self.environment.snapshot());
+        match result {
+            ActionResult::Win => match self.campaign.advance() {
+                CampaignProgress::Next(_) => self.next_level(),
+                CampaignProgress::Complete => self.game_over = true,
+            },
+            ActionResult::Lose | ActionResult::TimedOut => self.game_over = true,
+            _ => {}
+        }
+    }
+
+    /// Processes a turn in which agent `player` moves next to `target` instead of doing
+    /// what its behavior would. Clears the objective if the step was blocked, e.g. by
+    /// another agent; the next request replans.
+    fn step_towards(&mut self, player: EntityId, target: Position) -> ActionResult {
+        let input = HumanInput::new();
+        input.push(Action::MoveToAdjacent { target });
+        let Some(behavior) = self.environment.agent_behaviors.get_mut(&player) else {
+            self.objective.clear();
+            return ActionResult::Failure(ActionFailure::NoBehavior(player));
+        };
+        let behavior = std::mem::replace(behavior, Box::new(HumanAgent::new(player, input)));
+        let before = self
+            .environment
+            .agents
+            .get(&player)
+            .map(|state| state.position);
+        let result = self.environment.process_turn();
+        self.environment.agent_behaviors.insert(player, behavior);
+
+        let after = self
+            .environment
+            .agents
+            .get(&player)
+            .map(|state| state.position);
+        if before == after && !self.environment.finished.contains_key(&player) {
+            self.objective.clear();
+        }
+        result
+    }
+
+    /// Returns the ID of the agent the player follows: the lowest-ID agent that isn't a
+    /// patroller or ghost and hasn't finished.
+    fn player(&self) -> Option<EntityId> {
+        let environment = &self.environment;
+        environment
+            .agents
+            .keys()
+            .copied()
+            .filter(|id| {
+                !environment.patrollers.contains(id)
+                    && !environment.ghosts.contains(id)
+                    && !environment.finished.contains_key(id)
+            })
+            .min()
+    }
+
+    /// Pauses autopilot and queues the path from the player agent to its nearest
+    /// objective (a chip, needed key, or goal), which is then walked one step per tick.
+    /// Does nothing while a previously queued path is still being walked.
+    fn advance_to_next_objective(&mut self) {
+        if self.game_over || !self.objective.is_empty() {
+            return;
+        }
+        self.paused = true;
+        if let Some(path) = self
+            .player()
+            .and_then(|player| self.environment.path_to_nearest_objective(player))
+        {
+            // The first position is where the agent already stands
+            self.objective = path.into_iter().skip(1).collect();
+        }
+    }
+
+    /// Asks the engine what the player agent should do next and shows it in the status
+    /// line until the simulation moves on.
+    fn show_hint(&mut self) {
+        self.hint = self
+            .player()
+            .and_then(|player| self.environment.next_hint(player));
+    }
+
+    /// Loads the campaign's current map, ending the game if it fails to load.
+    fn next_level(&mut self) {
+        self.objective.clear();
+        self.human_input.clear();
+        match load_level(
+            &self.campaign,
+            self.agent_kind,
+            self.seed,
+            &self.human_input,
+        ) {
+            Ok((environment, start, map_warnings)) => {
+                self.environment = environment;
+                self.environment.max_turns = self.turn_limit;
+                self.recording = vec![self.environment.snapshot()];
+                self.start = start;
+                self.map_warnings = map_warnings;
+                if self.heatmap {
+                    self.environment.enable_visit_tracking();
+                }
+            }
+            Err(err) => {
+                self.load_error = Some(err.to_string());
+                self.game_over = true;
+            }
+        }
+    }
+
+    /// Describes campaign progress and how the game ended, if it has.
+    fn status(&self) -> String {
+        let progress = format!(
+            "Map {}/{}",
+            self.campaign.current_index() + 1,
+            self.campaign.len()
+        );
+        if let Some(replay) = &self.replay {
+            format!(
+                "Replaying turn {} ({}/{})",
+                self.environment.turn,
+                replay.cursor + 1,
+                self.recording.len()
+            )
+        } else if let Some(editor) = &self.editor {
+            let cursor = editor.cursor;
+            match &editor.message {
+                Some(message) => format!("Editing ({}, {}) - {}", cursor.x, cursor.y, message),
+                None => format!("Editing ({}, {})", cursor.x, cursor.y),
+            }
+        } else if let Some(err) = &self.load_error {
+            format!("{} failed to load: {}", progress, err)
+        } else if self.campaign.is_complete() {
+            format!("{} - campaign complete!", progress)
+        } else if self.game_over && self.environment.turns_remaining() == Some(0) {
+            format!("{} - out of turns, run lost.", progress)
+        } else if self.game_over {
+            format!("{} - run lost.", progress)
+        } else if let Some(hint) = self.hint {
+            format!("{} - hint: {}", progress, describe_hint(hint))
+        } else if let Some(warning) = self.map_warnings.first() {
+            format!(
+                "{} - {} map warning(s), first: {}",
+                progress,
+                self.map_warnings.len(),
+                warning
+            )
+        } else {
+            progress
+        }
+    }
+
+    /// Enters or leaves edit mode. Entering pauses the simulation and restores the map
+    /// to how it was before the first turn so edits apply to the original layout.
+    fn toggle_editor(&mut self) {
+        if self.editor.take().is_some() {
+            return;
+        }
+        self.environment.reset_to_initial();
+        self.recording.truncate(1);
+        self.objective.clear();
+        self.paused = true;
+        self.game_over = false;
+        self.editor = Some(Editor {
+            cursor: self.start,
+            message: None,
+        });
+    }
+
+    /// Moves the editor cursor by `(dx, dy)`, staying within the map.
+    fn move_cursor(&mut self, dx: isize, dy: isize) {
+        let terrain = &self.environment.terrain;
+        if let Some(editor) = &mut self.editor
+            && let Some(cursor) = editor.cursor.offset(dx, dy)
+            && terrain.is_valid(cursor.x, cursor.y)
+        {
+            editor.cursor = cursor;
+        }
+    }
+
+    /// Paints the cell under the cursor and rebuilds the environment from the edited map.
+    /// The start cell only accepts floor; painting the start elsewhere moves it.
+    fn paint(&mut self, brush: Brush) {
+        let Some(cursor) = self.editor.as_ref().map(|editor| editor.cursor) else {
+            return;
+        };
+        if cursor == self.start && !matches!(brush, Brush::Floor | Brush::Start) {
+            self.set_editor_message("The start must stay empty floor; move it with 't' first.");
+            return;
+        }
+
+        let (cell, item) = brush.cell();
+        let mut terrain = self.environment.terrain.clone();
+        let mut items = self.environment.items.clone();
+        terrain[cursor] = cell;
+        items[cursor] = item;
+        let start = if brush == Brush::Start {
+            cursor
+        } else {
+            self.start
+        };
+
+        let rebuilt = Environment::from_grids(terrain, items)
+            .map_err(anyhow::Error::msg)
+            .and_then(|mut environment| {
+                copy_map_settings(&self.environment, &mut environment);
+                let warnings = place_agent(
+                    &mut environment,
+                    start,
+                    self.agent_kind,
+                    self.seed,
+                    &self.human_input,
+                )?;
+                Ok((environment, warnings))
+            });
+        match rebuilt {
+            Ok((environment, warnings)) => {
+                self.environment = environment;
+                self.environment.max_turns = self.turn_limit;
+                self.recording = vec![self.environment.snapshot()];
+                self.start = start;
+                self.map_warnings = warnings;
+                if self.heatmap {
+                    self.environment.enable_visit_tracking();
+                }
+                if let Some(editor) = &mut self.editor {
+                    editor.message = None;
+                }
+            }
+            Err(err) => self.set_editor_message(&err.to_string()),
+        }
+    }
+
+    /// Writes the edited map over the current map file.
+    fn save_map(&mut self) {
+        let path = self.campaign.current_map().to_path_buf();
+        let message = save_environment_to_string(&self.environment, self.start)
+            .map_err(anyhow::Error::msg)
+            .and_then(|map| Ok(std::fs::write(&path, map + "\n")?))
+            .map_or_else(
+                |err| format!("Failed to save {}: {}", path.display(), err),
+                |_| format!("Saved {}", path.display()),
+            );
+        self.set_editor_message(&message);
+    }
+
+    fn set_editor_message(&mut self, message: &str) {
+        if let Some(editor) = &mut self.editor {
+            editor.message = Some(message.to_string());
+        }
+    }
+
+    /// Key help for the current mode.
+    fn help(&self) -> &'static str {
+        if self.replay.is_some() {
+            "Left/Right step through the recorded turns, Home/End jump to the first/last, 'r' or 'Esc' to stop replaying."
+        } else if self.editor.is_some() {
+            "Arrows move, '.' floor, 'w' wall, 'x' hazard, '1'-'4' doors, 'r'/'g'/'b'/'y' keys, 'c'/'C' chips, 'o' goal, 't' start, 'W' save, 'e' or 'Esc' to stop editing."
+        } else if self.agent_kind == AgentKind::Human {
+            "Arrows move, 'q' or 'Esc' to quit, 'p' to pause, 's' to toggle symbols, 'b' to toggle box walls, 'h' to toggle heatmap, 'l' to toggle legend, '?' for a hint, 'r' to replay, 'e' to edit."
+        } else {
+            "Press 'q' or 'Esc' to quit, 'p' to pause, 's' to toggle symbols, 'b' to toggle box walls, 'h' to toggle heatmap, 'l' to toggle legend, 'n' to walk to the next objective, '?' for a hint, 'r' to replay, 'e' to edit."
+        }
+    }
+
+    /// Switches between color-only and symbol rendering of keys and doors.
+    fn toggle_symbols(&mut self) {
+        self.symbols = !self.symbols;
+    }
+
+    /// Switches between '#' and box-drawing walls.
+    fn toggle_box_walls(&mut self) {
+        self.box_walls = !self.box_walls;
+    }
+
+    /// Toggles the visit heatmap, starting visit tracking the first time it is shown.
+    fn toggle_heatmap(&mut self) {
+        self.environment.enable_visit_tracking();
+        self.heatmap = !self.heatmap;
+    }
+
+    /// Shows or hides the legend panel.
+    fn toggle_legend(&mut self) {
+        self.legend = !self.legend;
+    }
+
+    /// Pauses or resumes the simulation.
+    fn toggle_paused(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Queues a move for a human-controlled agent, taken on the next tick. Does nothing
+    /// for other agent kinds.
+    fn steer(&mut self, dx: isize, dy: isize) {
+        if self.agent_kind == AgentKind::Human && !self.game_over {
+            self.human_input.push(Action::Move { dx, dy });
+        }
+    }
+
+    /// Enters or leaves replay mode. Entering shows the latest recorded state and stops
+    /// the simulation until replay ends, which restores the environment as it was.
+    fn toggle_replay(&mut self) {
+        if let Some(replay) = self.replay.take() {
+            self.environment = replay.live;
+            return;
+        }
+        if self.editor.is_some() || self.recording.is_empty() {
+            return;
+        }
+        self.replay = Some(Replay {
+            live: self.environment.clone(),
+            cursor: self.recording.len() - 1,
+        });
+    }
+
+    /// Shows the recorded state `offset` steps from the current one while replaying,
+    /// clamped to the recording.
+    fn scrub(&mut self, offset: isize) {
+        let Some(replay) = &mut self.replay else {
+            return;
+        };
+        let last = self.recording.len() - 1;
+        replay.cursor = replay.cursor.saturating_add_signed(offset).min(last);
+        self.environment.reset(&self.recording[replay.cursor]);
+    }
+
+    /// Sets the quit flag.
+    fn quit(&mut self) {
+        self.should_quit = true;
+    }
+}
+
+/// Loads the campaign's current map and places an agent of the given kind at its start.
+/// Also returns the start position and any problems found validating the map.
+fn load_level(
+    campaign: &Campaign,
+    agent_kind: AgentKind,
+    seed: u64,
+    input: &HumanInput,
+) -> Result<(Environment, Position, Vec<MapWarning>)> {
+    let (mut environment, start_position) = campaign.load_current().map_err(anyhow::Error::msg)?;
+    let warnings = place_agent(&mut environment, start_position, agent_kind, seed, input)?;
+    Ok((environment, start_position, warnings))
+}
+
+/// Places an agent of the given kind at `start`, returning any problems found validating
+/// the map from there.
+fn place_agent(
+    environment: &mut Environment,
+    start: Position,
+    agent_kind: AgentKind,
+    seed: u64,
+    input: &HumanInput,
+) -> Result<Vec<MapWarning>> {
+    let warnings = environment.validate(start);
+    let agent = agent_kind.create(environment.reserve_entity_id(), seed, input);
+    environment
+        .add_agent(start, agent, vec![])
+        .map_err(anyhow::Error::msg)?;
+    Ok(warnings)
+}
+
+/// Runs the main loop of the TUI application.
+pub fn run_app(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App) -> Result<()> {
+    let tick_rate = app.tick_rate; // Update rate
+    let mut last_tick = Instant::now();
+
+    loop {
+        // Draw the UI
+        terminal.draw(|f| ui(f, app))?;
+
+        // Calculate timeout for event polling
+        let timeout = tick_rate
+            .checked_sub(last_tick.elapsed())
+            .unwrap_or_else(|| Duration::from_secs(0));
+
+        // Poll for events (keyboard, mouse, etc.)
+        if crossterm::event::poll(timeout)?
+            && let Event::Key(key) = event::read()?
+        {
+            if app.replay.is_some() {
+                match key.code {
+                    KeyCode::Char('r') | KeyCode::Esc => app.toggle_replay(),
+                    KeyCode::Char('q') => app.quit(),
+                    KeyCode::Left => app.scrub(-1),
+                    KeyCode::Right => app.scrub(1),
+                    KeyCode::Home => app.scrub(isize::MIN),
+                    KeyCode::End => app.scrub(isize::MAX),
+                    _ => {}
+                }
+            } else if app.editor.is_some() {
+                match key.code {
+                    KeyCode::Char('e') | KeyCode::Esc => app.toggle_editor(),
+                    KeyCode::Char('W') => app.save_map(),
+                    KeyCode::Up => app.move_cursor(0, -1),
+                    KeyCode::Down => app.move_cursor(0, 1),
+                    KeyCode::Left => app.move_cursor(-1, 0),
+                    KeyCode::Right => app.move_cursor(1, 0),
+                    KeyCode::Char(key) => {
+                        if let Some(brush) = Brush::from_key(key) {
+                            app.paint(brush);
+                        }
+                    }
+                    _ => {}
+                }
+            } else {
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => app.quit(),
+                    KeyCode::Char('s') => app.toggle_symbols(),
+                    KeyCode::Char('b') => app.toggle_box_walls(),
+                    KeyCode::Char('h') => app.toggle_heatmap(),
+                    KeyCode::Char('p') => app.toggle_paused(),
+                    KeyCode::Char('l') => app.toggle_legend(),
+                    KeyCode::Char('n') => app.advance_to_next_objective(),
+                    KeyCode::Char('?') => app.show_hint(),
+                    KeyCode::Char('e') => app.toggle_editor(),
+                    KeyCode::Char('r') => app.toggle_replay(),
+                    KeyCode::Up => app.steer(0, -1),
+                    KeyCode::Down => app.steer(0, 1),
+                    KeyCode::Left => app.steer(-1, 0),
+                    KeyCode::Right => app.steer(1, 0),
+                    _ => {}
+                }
+            }
+        }
+
+        // Update application state if enough time has passed
+        if last_tick.elapsed() >= tick_rate {
+            app.tick(); // Perform simulation step
+            last_tick = Instant::now();
+        }
+
+        // Exit loop if requested
+        if app.should_quit {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Renders the user interface.
+fn ui(frame: &mut Frame, app: &App) {
+    let main_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(70), // Area for the map
+            Constraint::Percentage(20), // Area for inventory
+            Constraint::Percentage(10), // Area for status/help
+        ])
+        .split(frame.area());
+
+    // Render the map, with the legend beside it if shown
+    if app.legend {
+        let map_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(0), Constraint::Length(28)])
+            .split(main_layout[0]);
+        render_map(frame, map_layout[0], app);
+        render_legend(frame, map_layout[1], app);
+    } else {
+        render_map(frame, main_layout[0], app);
+    }
+
+    // Render the inventory
+    render_inventory(frame, main_layout[1], &app.environment.agents, app.symbols);
+
+    // Render status/help text
+    let help_text = Paragraph::new(vec![Line::from(app.status()), Line::from(app.help())])
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::TOP));
+    frame.render_widget(help_text, main_layout[2]);
+}
+
+/// How an agent is drawn on the map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AgentGlyph {
+    Agent,
+    Patroller,
+    Ghost,
+}
+
+impl AgentGlyph {
+    /// Returns the span drawn for this kind of agent.
+    fn span(self) -> Span<'static> {
+        let glyph = match self {
+            AgentGlyph::Agent => glyph::agent_glyph(false),
+            AgentGlyph::Patroller => glyph::agent_glyph(true),
+            AgentGlyph::Ghost => glyph::ghost_glyph(),
+        };
+        let style = glyph_style(&glyph);
+        Span::styled(glyph.text, style)
+    }
+}
+
+/// Renders a legend of the glyphs drawn by `render_map`. Entries are produced with the
+/// same renderers as the map so the two stay in sync.
+fn render_legend(frame: &mut Frame, area: Rect, app: &App) {
+    let tile = |cell: CellType| {
+        let (text, style) = (app.tile_renderer)(&cell, None, app.symbols);
+        Span::styled(text, style)
+    };
+    let item = |item: Item| {
+        let (text, style) = (app.tile_renderer)(&CellType::Floor, Some(&item), app.symbols);
+        Span::styled(text, style)
+    };
+    let door = |open: bool, door_type: Option<DoorKeyType>, cost: Option<usize>| CellType::Door {
+        open,
+        door_type,
+        auto_close_after: None,
+        cost,
+        auto_open: true,
+    };
+
+    let mut entries = vec![
+        (AgentGlyph::Agent.span(), "Agent".to_string()),
+        (AgentGlyph::Patroller.span(), "Patroller".to_string()),
+        (AgentGlyph::Ghost.span(), "Ghost".to_string()),
+        (item(Item::Goal), "Goal".to_string()),
+        (item(Item::Chip), "Chip".to_string()),
+        (
+            item(Item::BonusChip {
+                value: BONUS_CHIP_VALUE,
+            }),
+            "Bonus chip".to_string(),
+        ),
+        (
+            item(Item::OrderedChip { order: 1 }),
+            "Ordered chip".to_string(),
+        ),
+    ];
+    for key_type in DoorKeyType::ALL {
+        entries.push((item(Item::Key { key_type }), format!("{:?} key", key_type)));
+    }
+    entries.push((tile(CellType::Wall), "Wall".to_string()));
+    entries.push((tile(door(false, None, None)), "Door".to_string()));
+    for key_type in DoorKeyType::ALL {
+        entries.push((
+            tile(door(false, Some(key_type), None)),
+            format!("{:?} door", key_type),
+        ));
+    }
+    entries.push((tile(door(false, None, Some(1))), "Chip door".to_string()));
+    entries.push((tile(door(true, None, None)), "Open door".to_string()));
+    entries.push((
+        tile(CellType::OneWay { direction: (1, 0) }),
+        "One-way passage".to_string(),
+    ));
+    entries.push((tile(CellType::Hazard), "Hazard".to_string()));
+    entries.push((tile(CellType::Unknown), "Unknown".to_string()));
+    entries.push((
+        Span::styled("x", Style::default()).add_modifier(Modifier::REVERSED),
+        "Agent target".to_string(),
+    ));
+
+    let lines: Vec<Line> = entries
+        .into_iter()
+        .map(|(glyph, meaning)| Line::from(vec![glyph, Span::raw(format!(" {}", meaning))]))
+        .collect();
+    let legend =
+        Paragraph::new(lines).block(Block::default().title("Legend").borders(Borders::ALL));
+    frame.render_widget(legend, area);
+}
+
+/// Returns the terminal style of a glyph.
+fn glyph_style(glyph: &Glyph) -> Style {
+    let color = match glyph.color {
+        GlyphColor::Default => None,
+        GlyphColor::DarkGray => Some(Color::DarkGray),
+        GlyphColor::Red => Some(Color::Red),
+        GlyphColor::Green => Some(Color::Green),
+        GlyphColor::Yellow => Some(Color::Yellow),
+        GlyphColor::Blue => Some(Color::Blue),
+        GlyphColor::Magenta => Some(Color::Magenta),
+    };
+    let mut style = color.map_or_else(Style::default, |color| Style::default().fg(color));
+    if glyph.bold {
+        style = style.bold();
+    }
+    if glyph.dim {
+        style = style.dim();
+    }
+    style
+}
+
+/// Turns left at which the countdown starts flashing.
+const LOW_TURNS: usize = 10;
+
+/// The turn countdown shown in the map's title bar, flashing red once few turns remain.
+fn countdown_title(remaining: usize) -> Line<'static> {
+    let style = if remaining <= LOW_TURNS {
+        Style::default()
+            .fg(Color::Red)
+            .bold()
+            .add_modifier(Modifier::SLOW_BLINK)
+    } else {
+        Style::default().bold()
+    };
+    Line::styled(format!(" {} turns left ", remaining), style).right_aligned()
+}
+
+/// Phrases a hint for the status line.
+fn describe_hint(hint: Hint) -> String {
+    match hint {
+        Hint::GoToChip(Position { x, y }) => format!("collect the chip at ({}, {})", x, y),
+        Hint::GoToKey(key_type, Position { x, y }) => {
+            format!("fetch the {:?} key at ({}, {})", key_type, x, y)
+        }
+        Hint::OpenDoor(Position { x, y }) => format!("open the door at ({}, {})", x, y),
+        Hint::GoToGoal(Position { x, y }) => format!("head for the goal at ({}, {})", x, y),
+    }
+}
+
+/// Returns the span used to draw a key.
+fn key_span(key_type: DoorKeyType, symbols: bool) -> Span<'static> {
+    let glyph = glyph::key_glyph(key_type, symbols);
+    let style = glyph_style(&glyph);
+    Span::styled(glyph.text, style)
+}
+
+/// Renders the inventory of each agent onto the frame.
+fn render_inventory(
+    frame: &mut Frame,
+    area: Rect,
+    agents: &HashMap<EntityId, AgentState>,
+    symbols: bool,
+) {
+    let inventory_items: Vec<ListItem> = agents
+        .iter()
+        .map(|(id, agent)| {
+            let summary = agent.inventory_summary();
+            // Colored keys collected, stacked by type
+            let mut collected_keys: Vec<Span> = Vec::new();
+            for key_type in DoorKeyType::ALL {
+                match summary.key_count(key_type) {
+                    0 => {}
+                    1 => collected_keys.push(key_span(key_type, symbols)),
+                    count => {
+                        collected_keys.push(key_span(key_type, symbols));
+                        collected_keys.push(Span::raw(format!("x{} ", count)));
+                    }
+                }
+            }
+            let agent_pos = agent.position;
+            let mut agent_info_text = vec![Span::styled(
+                format!(
+                    "Agent: {:?} Pos: ({}, {}) Chips collected: {} Keys collected: ",
+                    id, agent_pos.x, agent_pos.y, summary.chips
+                ),
+                Style::default(),
+            )];
+            agent_info_text.extend(collected_keys);
+            ListItem::from(Line::from(agent_info_text))
+        })
+        .collect();
+
+    let inventory_widget =
+        List::new(inventory_items).block(Block::default().borders(Borders::ALL).title("Inventory"));
+    frame.render_widget(inventory_widget, area);
+}
+
+/// The built-in tile renderer: the item on the cell if there is one, otherwise the terrain.
+fn default_tile_renderer(tile: &CellType, item: Option<&Item>, symbols: bool) -> (String, Style) {
+    let glyph = match item {
+        Some(item) => glyph::item_glyph(item, symbols),
+        None => glyph::cell_glyph(tile, symbols),
+    };
+    let style = glyph_style(&glyph);
+    (glyph.text, style)
+}
+
+/// Picks the box-drawing character for the wall at (`x`, `y`) that connects to the walls
+/// directly above, below, left and right of it.
+fn wall_glyph(map: &Grid<CellType>, x: usize, y: usize) -> &'static str {
+    let wall = |dx: isize, dy: isize| {
+        Position { x, y }
+            .offset(dx, dy)
+            .and_then(|position| map.get(position.x, position.y))
+            == Some(&CellType::Wall)
+    };
+    match (wall(0, -1), wall(0, 1), wall(-1, 0), wall(1, 0)) {
+        // (up, down, left, right)
+        (false, false, false, false) => "■",
+        (_, _, false, false) => "│",
+        (false, false, _, _) => "─",
+        (false, true, false, true) => "┌",
+        (false, true, true, false) => "┐",
+        (true, false, false, true) => "└",
+        (true, false, true, false) => "┘",
+        (true, true, false, true) => "├",
+        (true, true, true, false) => "┤",
+        (false, true, true, true) => "┬",
+        (true, false, true, true) => "┴",
+        (true, true, true, true) => "┼",
+    }
+}
+
+/// Collects the cell highlights of the features currently shown: the visit heatmap,
+/// agent targets and the editor cursor, later ones patched over earlier ones.
+fn build_overlay(app: &App, targets: &HashSet<Position>) -> Overlay {
+    let mut overlay = Overlay::new();
+    // Shade visited cells, brighter for more visits
+    if let Some(counts) = app.environment.visit_counts().filter(|_| app.heatmap) {
+        for (position, &visits) in counts.positions().zip(counts.iter()) {
+            if visits > 0 {
+                let shade = 232 + visits.min(23) as u8;
+                tint(
+                    &mut overlay,
+                    position,
+                    Style::default().bg(Color::Indexed(shade)),
+                );
+            }
+        }
+    }
+    // Highlight the editor cursor
+    if let Some(editor) = &app.editor {
+        tint(
+            &mut overlay,
+            editor.cursor,
+            Style::default().bg(Color::LightBlue),
+        );
+    }
+    for target in targets {
+        tint(
+            &mut overlay,
+            *target,
+            Style::default().add_modifier(Modifier::REVERSED),
+        );
+    }
+    overlay
+}
+
+/// Carries the settings of a map that aren't part of its grids, e.g. its seed and zones,
+/// over to a rebuilt copy so they survive editing and saving.
+fn copy_map_settings(from: &Environment, to: &mut Environment) {
+    to.seed = from.seed;
+    to.zones = from.zones.clone();
+    to.key_hierarchy = from.key_hierarchy.clone();
+    to.win_condition = from.win_condition.clone();
+    to.win_evaluator = from.win_evaluator.clone();
+    to.allow_duplicate_keys = from.allow_duplicate_keys;
+    to.shared_inventory = from.shared_inventory;
+    to.wrap_edges = from.wrap_edges;
+    to.max_turns = from.max_turns;
+    to.chip_respawn_delay = from.chip_respawn_delay;
+    to.starting_energy = from.starting_energy;
+    to.chip_energy = from.chip_energy;
+    to.exhaustion_ends_run = from.exhaustion_ends_run;
+}
+
+/// Renders the environment map onto the frame.
+fn render_map(frame: &mut Frame, area: Rect, app: &App) {
+    let environment = &app.environment;
+    let symbols = app.symbols;
+    let map = &environment.terrain;
+    let items = &environment.items;
+    // Ghosts are not tracked in `agent_locations`, so index them separately
+    let ghost_positions: HashSet<Position> = environment
+        .ghosts
+        .iter()
+        .filter_map(|id| environment.agents.get(id))
+        .map(|ghost| ghost.position)
+        .collect();
+    // Cells agents are currently heading towards
+    let targets: HashSet<Position> = environment
+        .agent_behaviors
+        .values()
+        .filter_map(|behavior| behavior.current_target())
+        .collect();
+    let overlay = build_overlay(app, &targets);
+
+    // Create a representation of the map grid with agents
+    let mut lines: Vec<Line> = Vec::with_capacity(map.height());
+
+    for y in 0..map.height() {
+        let mut spans: Vec<Span> = Vec::with_capacity(map.width());
+        for x in 0..map.width() {
+            // Check if an agent is at this position, preferring real agents over ghosts.
+            // Display agent character '@' with color, patrollers as 'E', ghosts dimmed
+            let agent_char = match environment.agent_locations.get(x, y) {
+                Some(Some(id)) if environment.patrollers.contains(id) => {
+                    Some(AgentGlyph::Patroller.span())
+                }
+                Some(Some(_)) => Some(AgentGlyph::Agent.span()),
+                _ if ghost_positions.contains(&Position { x, y }) => Some(AgentGlyph::Ghost.span()),
+                _ => None,
+            };
+            let tile = map.get(x, y).unwrap_or(&CellType::Floor); // Handle potential out-of-bounds safely
+            let item = items.get(x, y).and_then(|item| item.as_ref());
+
+            // Items are drawn over agents, agents over terrain
+            let span = match agent_char {
+                Some(agent_span) if item.is_none() => agent_span,
+                _ => {
+                    let (text, style) = (app.tile_renderer)(tile, item, symbols);
+                    // Box walls replace the renderer's glyph but keep its style
+                    if app.box_walls && item.is_none() && *tile == CellType::Wall {
+                        Span::styled(wall_glyph(map, x, y), style)
+                    } else {
+                        Span::styled(text, style)
+                    }
+                }
+            };
+
+            // Mark empty target cells with an 'x'
+            let position = Position { x, y };
+            let span = if targets.contains(&position) && span.content == " " {
+                Span::styled("x", span.style)
+            } else {
+                span
+            };
+            match overlay.get(&position) {
+                Some(style) => spans.push(span.patch_style(*style)),
+                None => spans.push(span),
+            }
+        }
+        lines.push(Line::from(spans));
+    }
+
+    let mut block = Block::default().title("Agent World").borders(Borders::ALL);
+    if let Some(remaining) = app.environment.turns_remaining() {
+        block = block.title(countdown_title(remaining));
+    }
+    let map_paragraph = Paragraph::new(lines)
+        .block(block)
+        .alignment(Alignment::Center);
+
+    frame.render_widget(map_paragraph, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_world_core::environment::load_environment_from_string;
+
+    /// Path of a bundled map, independent of the directory tests run in.
+    fn bundled_map(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../maps")
+            .join(name)
+    }
+
+    #[test]
+    fn app_starts_with_the_configured_settings() {
+        let app = App::from_config(AppConfig {
+            map_file: bundled_map("map01.txt"),
+            tick_rate_ms: 100,
+            agent_kind: AgentKind::Random,
+            seed: 7,
+            paused: true,
+            symbols: true,
+            box_walls: true,
+            turn_limit: Some(50),
+        })
+        .expect("app is created");
+
+        assert_eq!(app.tick_rate, Duration::from_millis(100));
+        assert_eq!(app.agent_kind, AgentKind::Random);
+        assert_eq!(app.seed, 7);
+        assert!(app.paused && app.symbols && app.box_walls);
+        assert_eq!(app.turn_limit, Some(50));
+        assert_eq!(app.environment.max_turns, Some(50));
+        assert_eq!(app.environment.agents.len(), 1);
+        assert_eq!(app.recording.len(), 1);
+        assert!(!app.game_over && !app.should_quit);
+        assert!(app.editor.is_none() && app.replay.is_none());
+    }
+
+    #[test]
+    fn new_uses_the_default_config() {
+        let app = App::new(bundled_map("map01.txt")).expect("app is created");
+        let defaults = AppConfig::new(bundled_map("map01.txt"));
+
+        assert_eq!(app.tick_rate, Duration::from_millis(defaults.tick_rate_ms));
+        assert_eq!(app.agent_kind, AgentKind::Planning);
+        assert_eq!(app.seed, 0);
+        assert!(!app.paused && !app.symbols && !app.box_walls);
+        assert_eq!(app.turn_limit, None);
+    }
+
+    /// Draws the app into an off-screen buffer and returns its text.
+    fn draw(app: &App) -> String {
+        let mut terminal = Terminal::new(backend::TestBackend::new(80, 40)).expect("terminal");
+        terminal.draw(|frame| ui(frame, app)).expect("draws");
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect()
+    }
+
+    #[test]
+    fn custom_tile_renderer_changes_wall_glyphs() {
+        let app = App::new(bundled_map("map01.txt")).expect("app is created");
+        assert!(draw(&app).contains('#'));
+        assert!(!draw(&app).contains('▓'));
+
+        let themed = app.with_tile_renderer(|tile, item, symbols| match (tile, item) {
+            (CellType::Wall, None) => ("▓".to_string(), Style::default()),
+            _ => default_tile_renderer(tile, item, symbols),
+        });
+        let text = draw(&themed);
+        assert!(text.contains('▓'));
+        assert!(!text.contains('#'));
+    }
+
+    #[test]
+    fn walking_to_each_objective_solves_the_map() {
+        let mut config = AppConfig::new(bundled_map("map01.txt"));
+        config.agent_kind = AgentKind::Human;
+        let mut app = App::from_config(config).expect("app is created");
+
+        // The human agent never moves by itself, so only the objectives move it
+        for _ in 0..500 {
+            if app.game_over {
+                break;
+            }
+            app.advance_to_next_objective();
+            app.tick();
+        }
+        assert!(app.game_over);
+        assert!(app.paused);
+        assert_eq!(app.environment.finished.len(), 1);
+    }
+
+    #[test]
+    fn scrubbing_shows_the_recorded_turn() {
+        let mut app = App::new(bundled_map("map01.txt")).expect("app is created");
+        for _ in 0..5 {
+            app.tick();
+        }
+        let live = app.environment.snapshot();
+        assert_eq!(app.recording.len(), 6);
+
+        app.toggle_replay();
+        app.scrub(-3);
+        assert_eq!(app.environment.turn, 2);
+        assert_eq!(app.environment.snapshot(), app.recording[2]);
+        assert!(draw(&app).contains("Replaying turn 2 (3/6)"));
+
+        // Scrubbing stops at the ends of the recording
+        app.scrub(-10);
+        assert_eq!(app.environment.snapshot(), app.recording[0]);
+        app.scrub(10);
+        assert_eq!(app.environment.turn, 5);
+
+        app.toggle_replay();
+        assert_eq!(app.environment.snapshot(), live);
+    }
+
+    #[test]
+    fn arrow_keys_feed_the_human_agent() {
+        let mut config = AppConfig::new(bundled_map("map01.txt"));
+        config.agent_kind = AgentKind::Human;
+        let mut app = App::from_config(config).expect("app is created");
+        let position = |app: &App| {
+            app.environment
+                .agents
+                .values()
+                .next()
+                .expect("agent")
+                .position
+        };
+        assert_eq!(position(&app), Position { x: 0, y: 2 });
+
+        app.steer(1, 0);
+        assert_eq!(app.human_input.len(), 1);
+        app.tick();
+        assert!(app.human_input.is_empty());
+        assert_eq!(position(&app), Position { x: 1, y: 2 });
+
+        // Without a key press the agent waits out the turn
+        app.tick();
+        assert_eq!(app.environment.turn, 2);
+        assert_eq!(position(&app), Position { x: 1, y: 2 });
+
+        // Other agents steer themselves
+        let mut planned = App::new(bundled_map("map01.txt")).expect("app is created");
+        planned.steer(1, 0);
+        assert!(planned.human_input.is_empty());
+    }
+
+    #[test]
+    fn overlays_are_patched_over_the_base_style() {
+        let mut app = App::new(bundled_map("map01.txt")).expect("app is created");
+        app.toggle_editor();
+        app.move_cursor(0, -2);
+
+        // The 7x4 map fills the bordered area exactly, so cell (x, y) is drawn at
+        // (x + 1, y + 1)
+        let mut terminal = Terminal::new(backend::TestBackend::new(9, 6)).expect("terminal");
+        terminal
+            .draw(|frame| render_map(frame, frame.area(), &app))
+            .expect("draws");
+        let buffer = terminal.backend().buffer();
+
+        let chip = &buffer[(1, 1)];
+        assert_eq!(chip.symbol(), "c");
+        assert_eq!(chip.bg, Color::LightBlue);
+        // The overlay only adds a background, so the chip keeps its own color
+        assert_eq!(chip.fg, Color::Yellow);
+        assert_eq!(buffer[(2, 1)].bg, Color::Reset);
+    }
+
+    /// Parses rows of map codes into terrain.
+    fn terrain(map: &str) -> Grid<CellType> {
+        let (env, _) = load_environment_from_string(map).expect("map loads");
+        env.terrain
+    }
+
+    #[test]
+    fn box_walls_connect_to_neighboring_walls() {
+        let room = terrain(
+            "WL WL WL BL WL\n\
+             WL ST WL BL BL\n\
+             WL WL WL BL BL\n",
+        );
+        let glyphs = [
+            ((0, 0), "┌"),
+            ((1, 0), "─"),
+            ((2, 0), "┐"),
+            ((0, 1), "│"),
+            ((2, 1), "│"),
+            ((0, 2), "└"),
+            ((2, 2), "┘"),
+            ((4, 0), "■"),
+        ];
+        for ((x, y), glyph) in glyphs {
+            assert_eq!(wall_glyph(&room, x, y), glyph, "({x}, {y})");
+        }
+
+        let junctions = terrain(
+            "WL WL WL ST\n\
+             WL WL WL BL\n\
+             WL WL BL BL\n",
+        );
+        assert_eq!(wall_glyph(&junctions, 1, 0), "┬");
+        assert_eq!(wall_glyph(&junctions, 0, 1), "├");
+        assert_eq!(wall_glyph(&junctions, 1, 1), "┼");
+        assert_eq!(wall_glyph(&junctions, 2, 1), "┘");
+    }
+
+    #[test]
+    fn missing_maps_are_reported() {
+        assert!(App::new(bundled_map("no_such_map.txt")).is_err());
+    }
+}